@@ -0,0 +1,624 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Sub, Mul, Neg};
+
+
+const DECIMAL_BASE: u32 = 1_000_000_000;
+const DECIMAL_DIGITS: usize = 9;
+
+
+/// Arbitrary-size integer: sign and little-endian base 2^32 magnitude.
+/// Zero is always an empty magnitude with a positive sign.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct BigInt {
+    negative: bool,
+    magnitude: Vec<u32>,
+}
+
+
+fn trim(vec: &mut Vec<u32>) {
+    while let Some(&0) = vec.last() {
+        vec.pop();
+    }
+}
+
+
+fn cmp_magnitude(lhs: &[u32], rhs: &[u32]) -> Ordering {
+    if lhs.len() != rhs.len() {
+        return lhs.len().cmp(&rhs.len())
+    }
+    for (l, r) in lhs.iter().rev().zip(rhs.iter().rev()) {
+        if l != r {
+            return l.cmp(r)
+        }
+    }
+    Ordering::Equal
+}
+
+
+fn add_magnitude(lhs: &[u32], rhs: &[u32]) -> Vec<u32> {
+    let (long, short) = if lhs.len() >= rhs.len() {(lhs, rhs)} else {(rhs, lhs)};
+    let mut vec = Vec::with_capacity(long.len() + 1);
+    let mut carry = 0u64;
+    for (i, digit) in long.iter().enumerate() {
+        let sum = *digit as u64 + *short.get(i).unwrap_or(&0) as u64 + carry;
+        vec.push(sum as u32);
+        carry = sum >> 32;
+    }
+    if carry > 0 {
+        vec.push(carry as u32);
+    }
+    vec
+}
+
+
+/// lhs must be greater than or equal to rhs
+fn sub_magnitude(lhs: &[u32], rhs: &[u32]) -> Vec<u32> {
+    let mut vec = Vec::with_capacity(lhs.len());
+    let mut borrow = 0i64;
+    for (i, digit) in lhs.iter().enumerate() {
+        let mut diff = *digit as i64 - *rhs.get(i).unwrap_or(&0) as i64 - borrow;
+        if diff < 0 {
+            diff += 1 << 32;
+            borrow = 1;
+        } else {
+            borrow = 0;
+        }
+        vec.push(diff as u32);
+    }
+    trim(&mut vec);
+    vec
+}
+
+
+fn mul_magnitude(lhs: &[u32], rhs: &[u32]) -> Vec<u32> {
+    if lhs.is_empty() || rhs.is_empty() {
+        return Vec::new()
+    }
+    let mut vec = vec![0u32; lhs.len() + rhs.len()];
+    for (i, &l) in lhs.iter().enumerate() {
+        let mut carry = 0u64;
+        for (j, &r) in rhs.iter().enumerate() {
+            let value = l as u64 * r as u64 + vec[i + j] as u64 + carry;
+            vec[i + j] = value as u32;
+            carry = value >> 32;
+        }
+        vec[i + rhs.len()] = carry as u32;
+    }
+    trim(&mut vec);
+    vec
+}
+
+
+fn mul_small_add(vec: &mut Vec<u32>, mul: u32, add: u32) {
+    let mut carry = add as u64;
+    for digit in vec.iter_mut() {
+        let value = *digit as u64 * mul as u64 + carry;
+        *digit = value as u32;
+        carry = value >> 32;
+    }
+    if carry > 0 {
+        vec.push(carry as u32);
+    }
+}
+
+
+fn divrem_small(lhs: &[u32], rhs: u32) -> (Vec<u32>, u32) {
+    let mut vec = vec![0u32; lhs.len()];
+    let mut rem = 0u64;
+    for i in (0..lhs.len()).rev() {
+        let value = (rem << 32) | lhs[i] as u64;
+        vec[i] = (value / rhs as u64) as u32;
+        rem = value % rhs as u64;
+    }
+    trim(&mut vec);
+    (vec, rem as u32)
+}
+
+
+fn shl_magnitude(lhs: &[u32], bits: usize) -> Vec<u32> {
+    if lhs.is_empty() {
+        return Vec::new()
+    }
+    let words = bits / 32;
+    let bits = bits % 32;
+    let mut vec = vec![0u32; words];
+    if bits == 0 {
+        vec.extend_from_slice(lhs);
+    } else {
+        let mut carry = 0u32;
+        for &digit in lhs {
+            vec.push((digit << bits) | carry);
+            carry = digit >> (32 - bits);
+        }
+        if carry > 0 {
+            vec.push(carry);
+        }
+    }
+    vec
+}
+
+
+fn shr_magnitude(lhs: &[u32], bits: usize) -> Vec<u32> {
+    let words = bits / 32;
+    if words >= lhs.len() {
+        return Vec::new()
+    }
+    let bits = bits % 32;
+    let mut vec: Vec<u32> = lhs[words..].to_vec();
+    if bits > 0 {
+        for i in 0..vec.len() {
+            let high = if i + 1 < vec.len() {vec[i + 1] << (32 - bits)} else {0};
+            vec[i] = (vec[i] >> bits) | high;
+        }
+    }
+    trim(&mut vec);
+    vec
+}
+
+
+/// Knuth's algorithm D. rhs must not be empty.
+fn divrem_magnitude(lhs: &[u32], rhs: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    if cmp_magnitude(lhs, rhs) == Ordering::Less {
+        return (Vec::new(), lhs.to_vec())
+    }
+    if rhs.len() == 1 {
+        let (q, r) = divrem_small(lhs, rhs[0]);
+        let mut r = vec![r];
+        trim(&mut r);
+        return (q, r)
+    }
+    let shift = rhs.last().unwrap().leading_zeros() as usize;
+    let v = shl_magnitude(rhs, shift);
+    let mut u = shl_magnitude(lhs, shift);
+    if u.len() == lhs.len() {
+        u.push(0);
+    }
+    let n = v.len();
+    let m = u.len() - n;
+    let mut q = vec![0u32; m];
+    let base = 1u64 << 32;
+    for j in (0..m).rev() {
+        let numerator = ((u[j + n] as u64) << 32) | u[j + n - 1] as u64;
+        let mut q_hat = numerator / v[n - 1] as u64;
+        let mut r_hat = numerator % v[n - 1] as u64;
+        while q_hat >= base || q_hat * v[n - 2] as u64 > ((r_hat << 32) | u[j + n - 2] as u64) {
+            q_hat -= 1;
+            r_hat += v[n - 1] as u64;
+            if r_hat >= base {
+                break
+            }
+        }
+        let mut borrow = 0i64;
+        let mut carry = 0u64;
+        for i in 0..n {
+            let product = q_hat * v[i] as u64 + carry;
+            carry = product >> 32;
+            let diff = u[i + j] as i64 - (product & 0xffff_ffff) as i64 - borrow;
+            u[i + j] = diff as u32;
+            borrow = if diff < 0 {1} else {0};
+        }
+        let diff = u[j + n] as i64 - carry as i64 - borrow;
+        u[j + n] = diff as u32;
+        if diff < 0 {
+            q_hat -= 1;
+            let mut carry = 0u64;
+            for i in 0..n {
+                let sum = u[i + j] as u64 + v[i] as u64 + carry;
+                u[i + j] = sum as u32;
+                carry = sum >> 32;
+            }
+            u[j + n] = u[j + n].wrapping_add(carry as u32);
+        }
+        q[j] = q_hat as u32;
+    }
+    trim(&mut q);
+    u.truncate(n);
+    let mut r = shr_magnitude(&u, shift);
+    trim(&mut r);
+    (q, r)
+}
+
+
+/// Multiply by 2^exp without overflowing intermediate values.
+pub fn ldexp(value: f64, exp: i64) -> f64 {
+    let mut value = value;
+    let mut exp = exp;
+    while exp > 1000 {
+        value *= 2f64.powi(1000);
+        exp -= 1000;
+    }
+    while exp < -1000 {
+        value *= 2f64.powi(-1000);
+        exp += 1000;
+    }
+    value * 2f64.powi(exp as i32)
+}
+
+
+impl BigInt {
+    fn from_magnitude(negative: bool, magnitude: Vec<u32>) -> BigInt {
+        let mut magnitude = magnitude;
+        trim(&mut magnitude);
+        let negative = negative && !magnitude.is_empty();
+        BigInt { negative, magnitude }
+    }
+
+    pub fn zero() -> BigInt {
+        BigInt { negative: false, magnitude: Vec::new() }
+    }
+
+    pub fn one() -> BigInt {
+        Self::from_u64(1)
+    }
+
+    pub fn from_u64(value: u64) -> BigInt {
+        Self::from_magnitude(false, vec![value as u32, (value >> 32) as u32])
+    }
+
+    pub fn from_i64(value: i64) -> BigInt {
+        let mut n = Self::from_u64(value.unsigned_abs());
+        n.negative = value < 0;
+        n
+    }
+
+    /// Parse an unsigned decimal string such as "0123".
+    pub fn from_decimal_str(string: &str) -> Option<BigInt> {
+        if string.is_empty() || !string.bytes().all(|c| c.is_ascii_digit()) {
+            return None
+        }
+        let mut magnitude = Vec::new();
+        let head = string.len() % DECIMAL_DIGITS;
+        let mut chunks = Vec::new();
+        if head > 0 {
+            chunks.push(&string[..head]);
+        }
+        let mut index = head;
+        while index < string.len() {
+            chunks.push(&string[index..index + DECIMAL_DIGITS]);
+            index += DECIMAL_DIGITS;
+        }
+        for (i, chunk) in chunks.iter().enumerate() {
+            let value: u32 = chunk.parse().ok()?;
+            let mul = if i == 0 {1} else {DECIMAL_BASE};
+            if magnitude.is_empty() {
+                magnitude.push(value);
+            } else {
+                mul_small_add(&mut magnitude, mul, value);
+            }
+        }
+        Some(Self::from_magnitude(false, magnitude))
+    }
+
+    /// Exact conversion of a finite float that has no fractional part.
+    pub fn from_f64_int(value: f64) -> Option<BigInt> {
+        if !value.is_finite() || value.fract() != 0.0 {
+            return None
+        }
+        let bits = value.abs().to_bits();
+        let exponent = ((bits >> 52) & 0x7ff) as i64;
+        if exponent == 0 {
+            return Some(Self::zero())
+        }
+        let mantissa = (bits & ((1u64 << 52) - 1)) | (1u64 << 52);
+        let shift = exponent - 1075;
+        let magnitude = if shift >= 0 {
+            shl_magnitude(&Self::from_u64(mantissa).magnitude, shift as usize)
+        } else {
+            Self::from_u64(mantissa >> (-shift)).magnitude
+        };
+        Some(Self::from_magnitude(value < 0.0, magnitude))
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.magnitude.is_empty()
+    }
+
+    pub fn is_one(&self) -> bool {
+        !self.negative && self.magnitude == [1]
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    pub fn is_even(&self) -> bool {
+        self.magnitude.first().is_none_or(|d| d % 2 == 0)
+    }
+
+    pub fn abs(&self) -> BigInt {
+        BigInt { negative: false, magnitude: self.magnitude.clone() }
+    }
+
+    /// Number of significant bits of the magnitude.
+    pub fn bits(&self) -> u64 {
+        match self.magnitude.last() {
+            None => 0,
+            Some(d) => self.magnitude.len() as u64 * 32 - d.leading_zeros() as u64,
+        }
+    }
+
+    pub fn to_i64(&self) -> Option<i64> {
+        if self.magnitude.len() > 2 {
+            return None
+        }
+        let mut value = 0u64;
+        for (i, d) in self.magnitude.iter().enumerate() {
+            value |= (*d as u64) << (32 * i);
+        }
+        if self.negative {
+            if value <= i64::MAX as u64 + 1 {
+                Some((value as i64).wrapping_neg())
+            } else {
+                None
+            }
+        } else {
+            i64::try_from(value).ok()
+        }
+    }
+
+    /// Leading 64 bits of the magnitude and the power of two they are scaled by.
+    fn leading_u64(&self) -> (u64, i64) {
+        let bits = self.bits();
+        let shift = bits.saturating_sub(64) as usize;
+        let top = shr_magnitude(&self.magnitude, shift);
+        let mut value = 0u64;
+        for (i, d) in top.iter().enumerate() {
+            value |= (*d as u64) << (32 * i);
+        }
+        (value, shift as i64)
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        let (value, shift) = self.leading_u64();
+        let value = ldexp(value as f64, shift);
+        if self.negative {-value} else {value}
+    }
+
+    /// Quotient rounded toward zero and remainder with the sign of self.
+    pub fn divrem(&self, rhs: &BigInt) -> Option<(BigInt, BigInt)> {
+        if rhs.is_zero() {
+            return None
+        }
+        let (q, r) = divrem_magnitude(&self.magnitude, &rhs.magnitude);
+        Some((Self::from_magnitude(self.negative != rhs.negative, q),
+              Self::from_magnitude(self.negative, r)))
+    }
+
+    pub fn shl(&self, bits: usize) -> BigInt {
+        Self::from_magnitude(self.negative, shl_magnitude(&self.magnitude, bits))
+    }
+
+    pub fn shr(&self, bits: usize) -> BigInt {
+        Self::from_magnitude(self.negative, shr_magnitude(&self.magnitude, bits))
+    }
+
+    pub fn gcd(&self, rhs: &BigInt) -> BigInt {
+        let mut a = self.abs();
+        let mut b = rhs.abs();
+        while !b.is_zero() {
+            let (_, r) = a.divrem(&b).unwrap();
+            a = b;
+            b = r;
+        }
+        a
+    }
+
+    pub fn pow(&self, exp: u64) -> BigInt {
+        let mut result = Self::one();
+        let mut base = self.clone();
+        let mut exp = exp;
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = &result * &base;
+            }
+            exp >>= 1;
+            if exp > 0 {
+                base = &base * &base;
+            }
+        }
+        result
+    }
+
+    /// Integer n-th root of a non-negative value, rounded down,
+    /// and whether it is exact.
+    pub fn nth_root(&self, n: u64) -> Option<(BigInt, bool)> {
+        if self.negative || n == 0 {
+            return None
+        }
+        if self.is_zero() || n == 1 {
+            return Some((self.clone(), true))
+        }
+        let n_big = Self::from_u64(n);
+        let n_minus_one = Self::from_u64(n - 1);
+        let mut x = Self::one().shl(self.bits().div_ceil(n) as usize);
+        loop {
+            let (div, _) = self.divrem(&x.pow(n - 1)).unwrap();
+            let (next, _) = (&(&n_minus_one * &x) + &div).divrem(&n_big).unwrap();
+            if next >= x {
+                break
+            }
+            x = next;
+        }
+        let exact = x.pow(n) == *self;
+        Some((x, exact))
+    }
+}
+
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => cmp_magnitude(&self.magnitude, &other.magnitude),
+            (true, true) => cmp_magnitude(&other.magnitude, &self.magnitude),
+        }
+    }
+}
+
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+
+impl Add for &BigInt {
+    type Output = BigInt;
+
+    fn add(self, rhs: &BigInt) -> BigInt {
+        if self.negative == rhs.negative {
+            return BigInt::from_magnitude(self.negative, add_magnitude(&self.magnitude, &rhs.magnitude))
+        }
+        match cmp_magnitude(&self.magnitude, &rhs.magnitude) {
+            Ordering::Less
+                => BigInt::from_magnitude(rhs.negative, sub_magnitude(&rhs.magnitude, &self.magnitude)),
+            _ => BigInt::from_magnitude(self.negative, sub_magnitude(&self.magnitude, &rhs.magnitude)),
+        }
+    }
+}
+
+
+impl Sub for &BigInt {
+    type Output = BigInt;
+
+    fn sub(self, rhs: &BigInt) -> BigInt {
+        self + &(-rhs)
+    }
+}
+
+
+impl Mul for &BigInt {
+    type Output = BigInt;
+
+    fn mul(self, rhs: &BigInt) -> BigInt {
+        BigInt::from_magnitude(self.negative != rhs.negative, mul_magnitude(&self.magnitude, &rhs.magnitude))
+    }
+}
+
+
+impl Neg for &BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> BigInt {
+        BigInt::from_magnitude(!self.negative, self.magnitude.clone())
+    }
+}
+
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0")
+        }
+        let mut chunks = Vec::new();
+        let mut magnitude = self.magnitude.clone();
+        while !magnitude.is_empty() {
+            let (q, r) = divrem_small(&magnitude, DECIMAL_BASE);
+            chunks.push(r);
+            magnitude = q;
+        }
+        let mut string = String::new();
+        if self.negative {
+            string.push('-');
+        }
+        string += format!("{}", chunks.pop().unwrap()).as_str();
+        for chunk in chunks.iter().rev() {
+            string += format!("{:09}", chunk).as_str();
+        }
+        write!(f, "{}", string)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn big(string: &str) -> BigInt {
+        match string.strip_prefix('-') {
+            Some(s) => -&BigInt::from_decimal_str(s).unwrap(),
+            None => BigInt::from_decimal_str(string).unwrap(),
+        }
+    }
+
+    #[test]
+    fn from_decimal_str_and_fmt() {
+        let string = "123456789012345678901234567890";
+        assert_eq!(format!("{}", big(string)), string.to_string());
+        assert_eq!(format!("{}", big("000")), "0".to_string());
+        assert_eq!(format!("{}", big("-1000000000")), "-1000000000".to_string());
+    }
+
+    #[test]
+    fn from_decimal_str_error() {
+        assert_eq!(BigInt::from_decimal_str("1.5"), None);
+        assert_eq!(BigInt::from_decimal_str(""), None);
+    }
+
+    #[test]
+    fn add_sub_sign() {
+        assert_eq!(&big("5") + &big("-8"), big("-3"));
+        assert_eq!(&big("-5") - &big("-5"), BigInt::zero());
+        assert_eq!(&big("4294967295") + &big("1"), big("4294967296"));
+        assert_eq!(&big("4294967296") - &big("1"), big("4294967295"));
+    }
+
+    #[test]
+    fn mul_large() {
+        assert_eq!(&big("123456789123456789") * &big("-987654321987654321"),
+                   big("-121932631356500531347203169112635269"));
+    }
+
+    #[test]
+    fn divrem_truncated() {
+        assert_eq!(big("-7").divrem(&big("2")), Some((big("-3"), big("-1"))));
+        assert_eq!(big("7").divrem(&big("-2")), Some((big("-3"), big("1"))));
+        assert_eq!(big("7").divrem(&BigInt::zero()), None);
+    }
+
+    #[test]
+    fn divrem_large() {
+        let lhs = big("121932631356500531347203169112635269");
+        let rhs = big("987654321987654321");
+        assert_eq!(lhs.divrem(&rhs), Some((big("123456789123456789"), BigInt::zero())));
+        let lhs = &lhs + &big("12345");
+        assert_eq!(lhs.divrem(&rhs), Some((big("123456789123456789"), big("12345"))));
+    }
+
+    #[test]
+    fn pow_two_200() {
+        assert_eq!(format!("{}", big("2").pow(200)),
+                   "1606938044258990275541962092341162602522202993782792835301376".to_string());
+    }
+
+    #[test]
+    fn gcd_normal() {
+        assert_eq!(big("-12").gcd(&big("18")), big("6"));
+        assert_eq!(big("0").gcd(&big("5")), big("5"));
+    }
+
+    #[test]
+    fn nth_root_exact_and_floor() {
+        assert_eq!(big("1000000000000").nth_root(3), Some((big("10000"), true)));
+        assert_eq!(big("10").nth_root(2), Some((big("3"), false)));
+        assert_eq!(big("-8").nth_root(3), None);
+    }
+
+    #[test]
+    fn to_f64_and_from_f64_int() {
+        assert_eq!(big("-1606938044258990275541962092341162602522202993782792835301376").to_f64(),
+                   -(2f64.powi(200)));
+        assert_eq!(BigInt::from_f64_int(2f64.powi(70)), Some(big("1180591620717411303424")));
+        assert_eq!(BigInt::from_f64_int(-3.0), Some(big("-3")));
+        assert_eq!(BigInt::from_f64_int(0.5), None);
+    }
+
+    #[test]
+    fn to_i64_bounds() {
+        assert_eq!(big("-9223372036854775808").to_i64(), Some(i64::MIN));
+        assert_eq!(big("9223372036854775808").to_i64(), None);
+    }
+}
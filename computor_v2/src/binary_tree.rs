@@ -25,8 +25,8 @@ impl<T> BinaryTree<T> {
     pub fn from_element_and_tree(element: T, left: BinaryTree<T>, right: BinaryTree<T>) -> BinaryTree<T> {
         Self::from_node(TreeNode {
             element,
-            left: left,
-            right: right,
+            left,
+            right,
         })
     }
 
@@ -35,10 +35,7 @@ impl<T> BinaryTree<T> {
     }
 
     pub fn is_non_empty(&self) -> bool {
-        match self {
-            BinaryTree::Empty => false,
-            _ => true,
-        }
+        !matches!(self, BinaryTree::Empty)
     }
 
     pub fn left(&self) -> Option<&BinaryTree<T>> {
@@ -149,6 +146,13 @@ impl<T> BinaryTree<T> {
 }
 
 
+impl<T> Default for BinaryTree<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -178,11 +182,8 @@ mod tests {
         tree.add_left_node_from_element("2".to_string());
         let tmp = tree.left_mut();
         assert_eq!(tmp, Some(&mut BinaryTree::from_element("2".to_string())));
-        match tmp {
-            Some(tree) => {
-                tree.add_left_node_from_element("3".to_string());
-            },
-            _ => {}
+        if let Some(tree) = tmp {
+            tree.add_left_node_from_element("3".to_string());
         }
         let tmp = match tree.left_mut() {
             Some(tree) => {
@@ -204,11 +205,8 @@ mod tests {
         tree.add_right_node_from_element("2".to_string());
         let tmp = tree.right_mut();
         assert_eq!(tmp, Some(&mut BinaryTree::from_element("2".to_string())));
-        match tmp {
-            Some(tree) => {
-                tree.add_right_node_from_element("3".to_string());
-            },
-            _ => {}
+        if let Some(tree) = tmp {
+            tree.add_right_node_from_element("3".to_string());
         }
         let tmp = match tree.right_mut() {
             Some(tree) => {
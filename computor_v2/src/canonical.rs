@@ -0,0 +1,328 @@
+use std::cmp::Ordering;
+
+use crate::expression::Expr;
+use crate::num::Num;
+use crate::rational::Rational;
+use crate::parser::Parser;
+
+
+/// Products of sums are expanded only while the result has at most this many terms.
+const MAX_EXPANDED_TERMS: usize = 64;
+const MAX_POWER: i64 = 64;
+
+
+/// Variables come first, then the other factors, each in printed order.
+type FactorKey = (bool, String);
+
+
+/// A variable, or a subexpression that is not expanded, such as a function call.
+#[derive(Debug, Clone)]
+struct Factor {
+    key: FactorKey,
+    expr: Expr,
+}
+
+
+/// A coefficient times powers of factors sorted by key, with nonzero exponents.
+/// A factor can appear twice, once in the numerator and once in the denominator,
+/// so that it is never cancelled.
+#[derive(Debug, Clone)]
+struct Term {
+    coefficient: Num,
+    factors: Vec<(Factor, i64)>,
+}
+
+
+/// Terms with distinct factors and nonzero coefficients.
+type Sum = Vec<Term>;
+
+
+impl Factor {
+    fn new(expr: Expr) -> Result<Factor, String> {
+        let key = match &expr {
+            Expr::Variable(v) => (false, v.clone()),
+            _ => (true, Parser::print_tree(&expr.to_tree())?),
+        };
+        Ok(Factor { key, expr })
+    }
+}
+
+
+impl Term {
+    fn constant(coefficient: Num) -> Term {
+        Term { coefficient, factors: Vec::new() }
+    }
+
+    fn same_factors(&self, rhs: &Term) -> bool {
+        self.factors.len() == rhs.factors.len()
+            && self.factors.iter().zip(&rhs.factors).all(|((a, m), (b, n))| a.key == b.key && m == n)
+    }
+
+    fn degree(&self) -> i64 {
+        self.factors.iter().map(|(_, n)| n).sum()
+    }
+
+    fn mul(&self, rhs: &Term) -> Result<Term, String> {
+        let mut factors = self.factors.clone();
+        for (factor, n) in &rhs.factors {
+            match factors.binary_search_by(|(f, m)| (&f.key, *m < 0).cmp(&(&factor.key, *n < 0))) {
+                Ok(i) => factors[i].1 += n,
+                Err(i) => factors.insert(i, (factor.clone(), *n)),
+            }
+        }
+        factors.retain(|(_, n)| *n != 0);
+        Ok(Term { coefficient: self.coefficient.supported_mul(&rhs.coefficient)?, factors })
+    }
+
+    fn pow(&self, n: i64) -> Result<Term, String> {
+        Ok(Term {
+            coefficient: self.coefficient.supported_pow(&Num::from_i64(n))?.checked_value()?.clone(),
+            factors: self.factors.iter().map(|(f, m)| (f.clone(), m * n)).collect(),
+        })
+    }
+
+    /// Highest degree first, then in the order of the factors.
+    fn order(&self, rhs: &Term) -> Ordering {
+        rhs.degree().cmp(&self.degree())
+            .then_with(|| {
+                self.factors.iter().zip(&rhs.factors)
+                    .map(|((a, m), (b, n))| a.key.cmp(&b.key).then(n.cmp(m)))
+                    .find(|o| o.is_ne())
+                    .unwrap_or(Ordering::Equal)
+            })
+            .then(rhs.factors.len().cmp(&self.factors.len()))
+    }
+
+    /// The term without its sign, and whether it was negative.
+    fn to_expr(&self) -> (Expr, bool) {
+        let negative = self.coefficient.is_real() && self.coefficient.is_sign_negative();
+        let coefficient = if negative {self.coefficient.reverse_sign()} else {self.coefficient.clone()};
+        let product = |factors: Vec<(Expr, i64)>| factors.into_iter()
+            .map(|(e, n)| if n == 1 {e} else {Expr::pow(e, Expr::from_i64(n))})
+            .reduce(Expr::mul);
+        let numerator = product(self.factors.iter().filter(|(_, n)| *n > 0).map(|(f, n)| (f.expr.clone(), *n)).collect());
+        let denominator = product(self.factors.iter().filter(|(_, n)| *n < 0).map(|(f, n)| (f.expr.clone(), -n)).collect());
+        // `1 / (2 * x)` rather than `(1/2) / x`
+        let (coefficient, denominator) = match (&coefficient, denominator) {
+            (Num::Rational(q), Some(d)) if !q.is_integer() => (
+                Num::from_rational(Rational::from_bigint(q.numer().clone())),
+                Some(Expr::mul(Expr::Num(Num::from_rational(Rational::from_bigint(q.denom().clone()))), d)),
+            ),
+            (_, d) => (coefficient, d),
+        };
+        let numerator = match numerator {
+            Some(e) if coefficient.is_one() => e,
+            Some(e) => Expr::mul(Expr::Num(coefficient), e),
+            None => Expr::Num(coefficient),
+        };
+        let expr = match denominator {
+            Some(d) => Expr::div(numerator, d),
+            None => numerator,
+        };
+        (expr, negative)
+    }
+}
+
+
+fn add(a: &Sum, b: &Sum) -> Result<Sum, String> {
+    let mut sum = a.clone();
+    for term in b {
+        match sum.iter_mut().find(|t| t.same_factors(term)) {
+            Some(t) => t.coefficient = t.coefficient.supported_add(&term.coefficient)?,
+            None => sum.push(term.clone()),
+        }
+    }
+    sum.retain(|t| !t.coefficient.is_zero());
+    Ok(sum)
+}
+
+
+fn scale(a: &Sum, coefficient: &Num) -> Result<Sum, String> {
+    mul(a, &vec![Term::constant(coefficient.clone())])
+}
+
+
+fn mul(a: &Sum, b: &Sum) -> Result<Sum, String> {
+    if a.len() * b.len() > MAX_EXPANDED_TERMS {
+        return mul(&as_factor(a, 1)?, &as_factor(b, 1)?)
+    }
+    let mut product = Vec::new();
+    for x in a {
+        for y in b {
+            product = add(&product, &vec![x.mul(y)?])?;
+        }
+    }
+    Ok(product)
+}
+
+
+fn pow(a: &Sum, n: i64) -> Result<Sum, String> {
+    match a.as_slice() {
+        _ if n == 0 => Ok(vec![Term::constant(Num::from_i64(1))]),
+        [] if n < 0 => Err("The calculation resulted in 'inf'.".to_string()),
+        [] => Ok(Vec::new()),
+        [term] => Ok(vec![term.pow(n)?]),
+        _ if n < 0 => as_factor(a, n),
+        _ => {
+            let mut power = a.clone();
+            for _ in 1..n {
+                if power.len() * a.len() > MAX_EXPANDED_TERMS {
+                    return as_factor(a, n)
+                }
+                power = mul(&power, a)?;
+            }
+            Ok(power)
+        },
+    }
+}
+
+
+/// The sum kept whole as a factor, raised to `n`.
+fn as_factor(a: &Sum, n: i64) -> Result<Sum, String> {
+    if a.len() <= 1 {
+        return pow(a, n)
+    }
+    Ok(vec![Term { coefficient: Num::from_i64(1), factors: vec![(Factor::new(to_expr(a))?, n)] }])
+}
+
+
+fn constant_value(a: &Sum) -> Option<Num> {
+    match a.as_slice() {
+        [] => Some(Num::zero()),
+        [term] if term.factors.is_empty() => Some(term.coefficient.clone()),
+        _ => None,
+    }
+}
+
+
+fn from_expr(expr: &Expr) -> Result<Sum, String> {
+    match expr {
+        Expr::Num(n) if n.is_matrix() => Err("error: matrices are not simplified".to_string()),
+        Expr::Num(n) if n.is_zero() => Ok(Vec::new()),
+        Expr::Num(n) => Ok(vec![Term::constant(n.clone())]),
+        Expr::Variable(_) => as_atom(expr.clone()),
+        Expr::Neg(a) => scale(&from_expr(a)?, &Num::from_i64(-1)),
+        Expr::Add(a, b) => add(&from_expr(a)?, &from_expr(b)?),
+        Expr::Sub(a, b) => add(&from_expr(a)?, &scale(&from_expr(b)?, &Num::from_i64(-1))?),
+        Expr::Mul(a, b) => mul(&from_expr(a)?, &from_expr(b)?),
+        Expr::Div(a, b) => {
+            let (numerator, denominator) = (from_expr(a)?, from_expr(b)?);
+            match constant_value(&denominator) {
+                Some(_) => mul(&numerator, &pow(&denominator, -1)?),
+                // a sum over a denominator with unknowns is not split into fractions
+                None => mul(&as_factor(&numerator, 1)?, &pow(&denominator_factors(b)?, -1)?),
+            }
+        },
+        Expr::Pow(a, b) => {
+            let (base, exponent) = (from_expr(a)?, from_expr(b)?);
+            match constant_value(&exponent) {
+                Some(e) => match e.to_int_value() {
+                    Some(n) if n.abs() <= MAX_POWER => pow(&base, n),
+                    _ => match constant_value(&base) {
+                        // as in `Expr::simplify`, a power of exact numbers is folded only when it stays exact
+                        Some(c) => match c.supported_pow(&e).and_then(|n| n.checked_value().cloned()) {
+                            Ok(n) if n.is_exact() || !c.is_exact() || !e.is_exact() => Ok(vec![Term::constant(n)]),
+                            _ => as_atom(Expr::pow(to_expr(&base), to_expr(&exponent))),
+                        },
+                        None => as_atom(Expr::pow(to_expr(&base), to_expr(&exponent))),
+                    },
+                },
+                None => as_atom(Expr::pow(to_expr(&base), to_expr(&exponent))),
+            }
+        },
+        Expr::Func(name, args) => {
+            let args = args.iter().map(|a| Ok(to_expr(&from_expr(a)?))).collect::<Result<Vec<Expr>, String>>()?;
+            as_atom(Expr::Func(name.clone(), args))
+        },
+    }
+}
+
+
+/// A denominator as a product of powers of its factors, without expanding them.
+fn denominator_factors(expr: &Expr) -> Result<Sum, String> {
+    match expr {
+        Expr::Mul(a, b) => mul(&denominator_factors(a)?, &denominator_factors(b)?),
+        Expr::Pow(a, b) => match b.as_ref() {
+            Expr::Num(n) => match n.to_int_value() {
+                Some(n) if n.abs() <= MAX_POWER => pow(&denominator_factors(a)?, n),
+                _ => from_expr(expr),
+            },
+            _ => from_expr(expr),
+        },
+        _ => as_factor(&from_expr(expr)?, 1),
+    }
+}
+
+
+fn as_atom(expr: Expr) -> Result<Sum, String> {
+    Ok(vec![Term { coefficient: Num::from_i64(1), factors: vec![(Factor::new(expr)?, 1)] }])
+}
+
+
+fn to_expr(a: &Sum) -> Expr {
+    let mut terms = a.clone();
+    terms.sort_by(Term::order);
+    let mut expr: Option<Expr> = None;
+    for term in terms {
+        let (e, negative) = term.to_expr();
+        expr = Some(match (expr, negative) {
+            (None, false) => e,
+            (None, true) => Expr::neg(e),
+            (Some(acc), false) => Expr::add(acc, e),
+            (Some(acc), true) => Expr::sub(acc, e),
+        });
+    }
+    expr.unwrap_or(Expr::from_i64(0))
+}
+
+
+impl Expr {
+    /// Canonical form: like terms collected, constants folded, products of sums
+    /// expanded while they stay small, and terms ordered by degree then by name.
+    /// Expressions with matrices are only simplified.
+    pub fn canonical(&self) -> Expr {
+        match from_expr(self) {
+            Ok(sum) => to_expr(&sum),
+            Err(_) => self.simplify(),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_base::DataBase;
+    use crate::lexer::Lexer;
+
+    fn canonical_test(code: &str) -> Result<String, String> {
+        let data_base = DataBase::new();
+        let mut parser = Parser::new(Lexer::new(code).make_token_vec()?);
+        let tree = parser.make_tree(&data_base)?;
+        Parser::print_tree(&Expr::from_tree(&tree)?.canonical().to_tree())
+    }
+
+    #[test]
+    fn canonical_like_terms() {
+        assert_eq!(canonical_test("x + x + 2 * x"), Ok("4 * x".to_string()));
+        assert_eq!(canonical_test("y * x - x * y + 3 - 1"), Ok("2".to_string()));
+    }
+
+    #[test]
+    fn canonical_keeps_denominators() {
+        assert_eq!(canonical_test("x / x"), Ok("x / x".to_string()));
+        assert_eq!(canonical_test("2 * x ^ 2 / x"), Ok("2 * x ^ 2 / x".to_string()));
+        assert_eq!(canonical_test("(x ^ 2 - 1) / (x - 1)"), Ok("( x ^ 2 - 1 ) / ( x - 1 )".to_string()));
+        assert_eq!(canonical_test("x ^ (-3)"), Ok("1 / x ^ 3".to_string()));
+        assert_eq!(canonical_test("(x + 1 - x) / (x + 1) ^ 2"), Ok("1 / ( x + 1 ) ^ 2".to_string()));
+        assert_eq!(canonical_test("(x + 2) / 2"), Ok("( 1/2 ) * x + 1".to_string()));
+    }
+
+    #[test]
+    fn canonical_expand_and_order() {
+        assert_eq!(canonical_test("(x + 1) ^ 2 - 1"), Ok("x ^ 2 + 2 * x".to_string()));
+        assert_eq!(canonical_test("y ^ 2 + 1 + x * y + x ^ 2"), Ok("x ^ 2 + x * y + y ^ 2 + 1".to_string()));
+        assert_eq!(canonical_test("sin(x + x) * 3 - x / 2"), Ok("- ( 1/2 ) * x + 3 * sin ( 2 * x )".to_string()));
+        assert_eq!(canonical_test("1 / (x + 1) + 2 / (1 + x)"), Ok("3 / ( x + 1 )".to_string()));
+    }
+}
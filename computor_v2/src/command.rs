@@ -34,10 +34,14 @@ impl Commands {
         if self.data.len() <= index {
             None
         } else {
-            match self.data.get(self.data.len() - index - 1) {
-                Some(b) => Some(b.0.clone()),
-                None => None,
-            }
+            self.data.get(self.data.len() - index - 1).map(|b| b.0.clone())
         }
     }
 }
+
+
+impl Default for Commands {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,118 +1,190 @@
 use std::collections::HashMap;
 
-use crate::num::Num;
+use crate::num::{Num, ComplexForm};
 use crate::binary_tree::BinaryTree;
 use crate::parser::{Parser, Element};
-use crate::functions;
+use crate::functions::{self, Builtin};
+use crate::lexer::DIGIT_SUFFIX_NAMES;
 
 
 #[derive(Debug, PartialEq)]
 pub enum Data {
     Num(Num),
-    Func(Box<(BinaryTree<Element>, String)>),
+    Func(Box<(BinaryTree<Element>, Vec<String>)>),
 }
 
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug)]
 pub struct DataBase {
     data: HashMap<String, Data>,
     built_in: HashMap<String, Data>,
+    builtin_funcs: HashMap<String, Box<dyn Builtin>>,
+    /// `roots` and `multiplicities` of the last equation, hidden by a user
+    /// definition of the same name.
+    solve_results: HashMap<String, Data>,
+    complex_form: ComplexForm,
+    solve_interval: (f64, f64),
+    solve_steps: bool,
+    exact_roots: bool,
 }
 
 
 impl DataBase {
     pub fn new() -> DataBase {
         let mut built_in = HashMap::new();
-        Self::built_in_insert(&mut built_in, "exp".to_string());
-        Self::built_in_insert(&mut built_in, "sqrt".to_string());
-        Self::built_in_insert(&mut built_in, "abs".to_string());
-        Self::built_in_insert(&mut built_in, "sin".to_string());
-        Self::built_in_insert(&mut built_in, "cos".to_string());
-        Self::built_in_insert(&mut built_in, "tan".to_string());
         built_in.insert("pi".to_string(), Data::Num(Num::Float(std::f64::consts::PI)));
-        DataBase { data: HashMap::new(), built_in }
+        let mut data_base = DataBase {
+            data: HashMap::new(),
+            built_in,
+            builtin_funcs: HashMap::new(),
+            solve_results: HashMap::new(),
+            complex_form: ComplexForm::Rectangular,
+            solve_interval: (-10.0, 10.0),
+            solve_steps: false,
+            exact_roots: false,
+        };
+        for builtin in functions::default_builtins() {
+            data_base.register_builtin(builtin).unwrap();
+        }
+        data_base
     }
 
-    fn built_in_insert(built_in: &mut HashMap<String, Data>, func_name: String) {
-        built_in.insert(func_name.clone(), Data::Func(functions::make_builtin_func_box(func_name)));
+    /// Add a native function, replacing any builtin function of the same name.
+    pub fn register_builtin(&mut self, builtin: Box<dyn Builtin>) -> Result<(), String> {
+        let name = builtin.name().to_lowercase();
+        if name.is_empty() || !(name.chars().all(|c| c.is_ascii_alphabetic()) || DIGIT_SUFFIX_NAMES.contains(&name.as_str())) {
+            return Err(format!("{}: error invalid function name", name))
+        }
+        if self.built_in.contains_key(&name) || self.data.contains_key(&name) {
+            return Err(format!("{}: error the name is already used", name))
+        }
+        self.builtin_funcs.insert(name, builtin);
+        Ok(())
     }
 
-    pub fn register_num(&mut self, name: &String, num: Num) -> Result<(), String> {
-        let name = name.as_str().to_lowercase();
-        match self.built_in.get(&name) {
-            None => {},
-            Some(_) => return Err("The variable cannot be registered".to_string())
+    pub fn register_num(&mut self, name: &str, num: Num) -> Result<(), String> {
+        let name = name.to_lowercase();
+        if self.built_in.contains_key(&name) {
+            return Err("The variable cannot be registered".to_string())
         }
         self.data.insert(name, Data::Num(num));
         Ok(())
     }
 
-    pub fn register_func(&mut self, name: &String, tree: BinaryTree<Element>, variable: String) -> Result<(), String> {
-        let name = name.as_str().to_lowercase();
-        match self.built_in.get(&name) {
-            None => {},
-            Some(_) => return Err("The function cannot be registered".to_string())
+    /// Keep a value found by solving an equation, or forget it when None.
+    /// A variable or function defined by the user is never replaced.
+    pub fn set_solve_result(&mut self, name: &str, num: Option<Num>) {
+        let name = name.to_lowercase();
+        match num {
+            Some(num) => self.solve_results.insert(name, Data::Num(num)),
+            None => self.solve_results.remove(&name),
+        };
+    }
+
+    pub fn register_func(&mut self, name: &str, tree: BinaryTree<Element>, variables: Vec<String>) -> Result<(), String> {
+        let name = name.to_lowercase();
+        if self.built_in.contains_key(&name) {
+            return Err("The function cannot be registered".to_string())
         }
-        self.data.insert(name, Data::Func(Box::new((tree, variable))));
+        self.data.insert(name, Data::Func(Box::new((tree, variables))));
         Ok(())
     }
 
-    pub fn get(&self, name: &String) -> Option<&Data> {
-        let name = name.as_str().to_lowercase();
+    pub fn get(&self, name: &str) -> Option<&Data> {
+        let name = name.to_lowercase();
         match self.built_in.get(&name) {
-            Some(data) => Some(&data),
-            None => self.data.get(&name),
+            Some(data) => Some(data),
+            None => self.data.get(&name).or_else(|| self.solve_results.get(&name)),
         }
     }
 
-    pub fn get_num(&self, name: &String) -> Option<&Num> {
-        let data = match self.get(name) {
-            Some(d) => d,
-            None => return None,
-        };
+    pub fn get_num(&self, name: &str) -> Option<&Num> {
+        let data = self.get(name)?;
         match data {
             Data::Num(n) => Some(n),
             Data::Func(_) => None,
         }
     }
 
-    pub fn get_func(&self, name: &String) -> Option<&Box<(BinaryTree<Element>, String)>> {
-        let data = match self.get(name) {
-            Some(d) => d,
-            None => return None,
-        };
+    pub fn get_func(&self, name: &str) -> Option<&(BinaryTree<Element>, Vec<String>)> {
+        let data = self.get(name)?;
         match data {
             Data::Num(_) => None,
-            Data::Func(f) => Some(f),
+            Data::Func(f) => Some(f.as_ref()),
         }
     }
 
-    pub fn get_builtin_func(&self, name: &String) -> Option<&Box<(BinaryTree<Element>, String)>> {
-        let data = match self.built_in.get(name) {
-            Some(d) => d,
-            None => return None,
-        };
-        match data {
-            Data::Num(_) => None,
-            Data::Func(f) => Some(f),
+    /// None when a user variable or function hides the builtin.
+    pub fn get_builtin_func(&self, name: &str) -> Option<&dyn Builtin> {
+        let name = name.to_lowercase();
+        if self.data.contains_key(&name) {
+            return None
         }
+        self.builtin_funcs.get(&name).map(|b| b.as_ref())
+    }
+
+    pub fn complex_form(&self) -> ComplexForm {
+        self.complex_form
+    }
+
+    pub fn set_complex_form(&mut self, form: ComplexForm) {
+        self.complex_form = form;
+    }
+
+    /// Interval searched when an equation is solved numerically.
+    pub fn solve_interval(&self) -> (f64, f64) {
+        self.solve_interval
+    }
+
+    pub fn set_solve_interval(&mut self, interval: (f64, f64)) {
+        self.solve_interval = interval;
+    }
+
+    /// Whether the working is shown when an equation is solved.
+    pub fn solve_steps(&self) -> bool {
+        self.solve_steps
+    }
+
+    pub fn set_solve_steps(&mut self, steps: bool) {
+        self.solve_steps = steps;
+    }
+
+    /// Whether roots are shown as fractions and radicals instead of decimals.
+    pub fn exact_roots(&self) -> bool {
+        self.exact_roots
+    }
+
+    pub fn set_exact_roots(&mut self, exact: bool) {
+        self.exact_roots = exact;
     }
 
     pub fn show_variable(&self) -> String {
         let mut string = String::new();
-        for (key, value) in self.data.iter() {
+        let solve_results = self.solve_results.iter().filter(|(key, _)| !self.data.contains_key(*key));
+        for (key, value) in self.data.iter().chain(solve_results) {
             match value {
                 Data::Num(n) => string += format!("{}: {}\n", key, n).as_str(),
                 Data::Func(_) => {},
             }
         }
         if string.is_empty() {
-            format!("No variables defined yet\n")
+            "No variables defined yet\n".to_string()
         } else {
             string
         }
     }
 
+    pub fn show_builtin_func(&self) -> String {
+        let mut builtins: Vec<_> = self.builtin_funcs.values().collect();
+        builtins.sort_by(|a, b| a.name().cmp(b.name()));
+        let mut string = String::new();
+        for builtin in builtins {
+            let variables: Vec<String> = ('a'..='z').take(builtin.arity()).map(String::from).collect();
+            string += format!("{}({}): {}\n", builtin.name(), variables.join(", "), builtin.doc()).as_str();
+        }
+        string
+    }
+
     pub fn show_function(&self) -> Result<String, String> {
         let mut string = String::new();
         for (key, value) in self.data.iter() {
@@ -120,16 +192,23 @@ impl DataBase {
                 Data::Num(_) => {},
                 Data::Func(b) => {
                     let func_tree = &b.0;
-                    let variable = &b.1;
+                    let variables = &b.1;
                     let tree_string = Parser::print_tree(func_tree)?;
-                    string += format!("{}({}): {}\n", key, variable, tree_string).as_str()
+                    string += format!("{}({}): {}\n", key, variables.join(", "), tree_string).as_str()
                 },
             }
         }
         if string.is_empty() {
-            Ok(format!("No functions defined yet\n"))
+            Ok("No functions defined yet\n".to_string())
         } else {
             Ok(string)
         }
     }
 }
+
+
+impl Default for DataBase {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,135 @@
+use crate::data_base::DataBase;
+use crate::expression::Expr;
+
+
+/// Derivative of `expr` with respect to `variable`, before simplification.
+pub fn derivative(expr: &Expr, variable: &str) -> Result<Expr, String> {
+    if !expr.contains_variable(variable) {
+        return Ok(Expr::from_i64(0))
+    }
+    let d = |e: &Expr| derivative(e, variable);
+    match expr {
+        Expr::Num(_) => Ok(Expr::from_i64(0)),
+        Expr::Variable(_) => Ok(Expr::from_i64(1)),
+        Expr::Neg(a) => Ok(Expr::neg(d(a)?)),
+        Expr::Add(a, b) => Ok(Expr::add(d(a)?, d(b)?)),
+        Expr::Sub(a, b) => Ok(Expr::sub(d(a)?, d(b)?)),
+        Expr::Mul(a, b) => Ok(Expr::add(
+            Expr::mul(d(a)?, *b.clone()),
+            Expr::mul(*a.clone(), d(b)?),
+        )),
+        Expr::Div(a, b) => Ok(Expr::div(
+            Expr::sub(Expr::mul(d(a)?, *b.clone()), Expr::mul(*a.clone(), d(b)?)),
+            Expr::pow(*b.clone(), Expr::from_i64(2)),
+        )),
+        Expr::Pow(a, b) => {
+            if !b.contains_variable(variable) {
+                Ok(Expr::mul(
+                    Expr::mul(*b.clone(), Expr::pow(*a.clone(), Expr::sub(*b.clone(), Expr::from_i64(1)))),
+                    d(a)?,
+                ))
+            } else if !a.contains_variable(variable) {
+                Ok(Expr::mul(Expr::mul(expr.clone(), Expr::func("ln", *a.clone())), d(b)?))
+            } else {
+                Ok(Expr::mul(expr.clone(), Expr::add(
+                    Expr::mul(d(b)?, Expr::func("ln", *a.clone())),
+                    Expr::div(Expr::mul(*b.clone(), d(a)?), *a.clone()),
+                )))
+            }
+        },
+        Expr::Func(name, args) => {
+            if args.len() != 1 {
+                return Err(format!("error: cannot differentiate {}", name))
+            }
+            Ok(Expr::mul(outer_derivative(name, &args[0])?, d(&args[0])?))
+        },
+    }
+}
+
+
+/// Derivative of the builtin `name` evaluated at `u`.
+fn outer_derivative(name: &str, u: &Expr) -> Result<Expr, String> {
+    let one = || Expr::from_i64(1);
+    let square = |e: Expr| Expr::pow(e, Expr::from_i64(2));
+    let u = u.clone();
+    match name {
+        "exp" => Ok(Expr::func("exp", u)),
+        "sin" => Ok(Expr::func("cos", u)),
+        "cos" => Ok(Expr::neg(Expr::func("sin", u))),
+        "tan" => Ok(Expr::div(one(), square(Expr::func("cos", u)))),
+        "sqrt" => Ok(Expr::div(one(), Expr::mul(Expr::from_i64(2), Expr::func("sqrt", u)))),
+        "ln" => Ok(Expr::div(one(), u)),
+        "log10" => Ok(Expr::div(one(), Expr::mul(u, Expr::func("ln", Expr::from_i64(10))))),
+        "asin" => Ok(Expr::div(one(), Expr::func("sqrt", Expr::sub(one(), square(u))))),
+        "acos" => Ok(Expr::neg(Expr::div(one(), Expr::func("sqrt", Expr::sub(one(), square(u)))))),
+        "atan" => Ok(Expr::div(one(), Expr::add(one(), square(u)))),
+        "sinh" => Ok(Expr::func("cosh", u)),
+        "cosh" => Ok(Expr::func("sinh", u)),
+        "tanh" => Ok(Expr::sub(one(), square(Expr::func("tanh", u)))),
+        "abs" => Ok(Expr::func("sign", u)),
+        _ => Err(format!("error: cannot differentiate {}", name)),
+    }
+}
+
+
+/// Simplified derivative of the function `name` with respect to `variable`,
+/// by default its first parameter, together with the parameters of the function.
+pub fn derivative_func(data_base: &DataBase, name: &String, variable: Option<&String>) -> Result<(Expr, Vec<String>), String> {
+    let (expr, variables) = match data_base.get_func(name) {
+        Some(f) => (Expr::from_tree(&f.0)?, f.1.clone()),
+        None => match data_base.get_builtin_func(name) {
+            Some(b) if b.arity() == 1 => (Expr::func(&name.to_lowercase(), Expr::var("x")), vec!["x".to_string()]),
+            _ => return Err(format!("{}: error undefined function", name)),
+        },
+    };
+    let variable = match variable {
+        None => &variables[0],
+        Some(v) if variables.contains(v) => v,
+        Some(v) => return Err(format!("{}: error variable is not a parameter of {}({})", v, name, variables.join(", "))),
+    };
+    Ok((derivative(&expr, variable)?.canonical(), variables))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn derivative_test(function: &str, variable: &str) -> Result<String, String> {
+        let data_base = DataBase::new();
+        let mut parser = Parser::new(Lexer::new(function).make_token_vec()?);
+        let tree = parser.make_tree(&data_base)?;
+        let expr = derivative(&Expr::from_tree(&tree)?, variable)?.canonical();
+        Parser::print_tree(&expr.to_tree())
+    }
+
+    #[test]
+    fn derivative_polynomial() {
+        assert_eq!(derivative_test("x ^ 3 + 2x", "x"), Ok("3 * x ^ 2 + 2".to_string()));
+        assert_eq!(derivative_test("(x - 1) * (x + 1)", "x"), Ok("2 * x".to_string()));
+        assert_eq!(derivative_test("x * y ^ 2", "y"), Ok("2 * x * y".to_string()));
+    }
+
+    #[test]
+    fn derivative_quotient() {
+        assert_eq!(derivative_test("1 / x", "x"), Ok("- 1 / x ^ 2".to_string()));
+        assert_eq!(derivative_test("x / (x + 1)", "x"), Ok("1 / ( x + 1 ) ^ 2".to_string()));
+    }
+
+    #[test]
+    fn derivative_chain() {
+        assert_eq!(derivative_test("sin(x ^ 2)", "x"), Ok("2 * x * cos ( x ^ 2 )".to_string()));
+        assert_eq!(derivative_test("exp(2x) + cos(x)", "x"), Ok("2 * exp ( 2 * x ) - sin ( x )".to_string()));
+        assert_eq!(derivative_test("sqrt(x) + tan(x)", "x"), Ok("1 / ( 2 * sqrt ( x ) ) + 1 / cos ( x ) ^ 2".to_string()));
+        assert_eq!(derivative_test("tan(3x)", "x"), Ok("3 / cos ( 3 * x ) ^ 2".to_string()));
+        assert_eq!(derivative_test("2 ^ x", "x"), Ok("2 ^ x * ln ( 2 )".to_string()));
+    }
+
+    #[test]
+    fn derivative_error() {
+        assert_eq!(derivative_test("floor(x)", "x"), Err("error: cannot differentiate floor".to_string()));
+        assert_eq!(derivative_test("floor(y)", "x"), Ok("0".to_string()));
+    }
+}
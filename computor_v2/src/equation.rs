@@ -4,6 +4,8 @@ use crate::binary_tree::BinaryTree;
 use crate::parser::Element;
 use crate::operator::Operator;
 use crate::num::Num;
+use crate::expression::Expr;
+use crate::symbolic_solve;
 
 
 const MAX_TERMS: usize = 1000;
@@ -21,6 +23,9 @@ pub struct Equation {
     pub expr: Vec<Term>,
     pub degree: i64,
     pub variable: Option<String>,
+    /// The coefficients as exact fractions, lowest degree first, when every
+    /// number of the equation is one.
+    pub exact_coefficients: Option<Vec<Num>>,
 }
 
 
@@ -37,7 +42,7 @@ impl Term {
 
 impl Equation {
     pub fn new() -> Equation {
-        Equation { expr: vec![Term {coefficient: 0.0, degree: 0 }], degree: 0, variable: None }
+        Equation { expr: vec![Term {coefficient: 0.0, degree: 0 }], degree: 0, variable: None, exact_coefficients: None }
     }
 
     pub fn make_equation(&mut self, lhs: &BinaryTree<Element>, rhs: &BinaryTree<Element>) -> Result<(), String> {
@@ -50,16 +55,28 @@ impl Equation {
         }
         self.degree = self.expr.last().unwrap().degree;
         self.variable = variable;
+        self.exact_coefficients = self.variable.as_ref().and_then(|v| Self::exact_coefficients_of(lhs, rhs, v))
+            .filter(|c| c.len() as i64 == self.degree + 1);
         Ok(())
     }
 
+    fn exact_coefficients_of(lhs: &BinaryTree<Element>, rhs: &BinaryTree<Element>, variable: &str) -> Option<Vec<Num>> {
+        let expr = Expr::sub(Expr::from_tree(lhs).ok()?, Expr::from_tree(rhs).ok()?);
+        symbolic_solve::coefficients(&expr, variable)?.into_iter()
+            .map(|c| match c {
+                Expr::Num(n @ Num::Rational(_)) => Some(n),
+                _ => None,
+            })
+            .collect()
+    }
+
     fn set_one_tree(&mut self, tree: &BinaryTree<Element>, right_side: bool, variable: &mut Option<String>) -> Result<(), String> {
         match tree {
-            BinaryTree::Empty => return Err(format!("syntax error")),
+            BinaryTree::Empty => return Err("syntax error".to_string()),
             BinaryTree::NonEmpty(node_box) => {
                 match &node_box.element {
                     Element::Dummy => {},
-                    Element::Func(_) => return Err(format!("syntax error")),
+                    Element::Func(_) => return Err("syntax error".to_string()),
                     Element::Variable(_) | Element::Num(_) => self.set_one_terms(tree, right_side, variable)?,
                     Element::Operator(op) => {
                         match op {
@@ -93,21 +110,24 @@ impl Equation {
 
     fn set_one_term(&mut self, tree: &BinaryTree<Element>, variable: &mut Option<String>) -> Result<Vec<Term>, String> {
         match tree {
-            BinaryTree::Empty => return Err(format!("syntax error")),
+            BinaryTree::Empty => Err("syntax error".to_string()),
             BinaryTree::NonEmpty(node_box) => {
                 match &node_box.element {
-                    Element::Func(_) => return Err(format!("syntax error")),
+                    Element::Func(_) => Err("syntax error".to_string()),
                     Element::Num(n)
                         => Ok(vec![Term::from_coefficient(Self::check_and_get_num_float(n)?)]),
                     Element::Dummy => Ok(vec![Term::from_coefficient(0.0)]),
                     Element::Variable(v) => {
-                        Self::check_variable(variable, &v)?;
+                        Self::check_variable(variable, v)?;
                         Ok(vec![Term::from_degree(1)])
                     },
                     Element::Operator(op) => {
                         match op {
-                            Operator::Plus | Operator::Minus | Operator::RParen => Err(format!("syntax error")),
-                            Operator::MatrixMul => Err(format!("Unsupported matrix product error.")),
+                            Operator::Plus | Operator::Minus | Operator::RParen | Operator::Comma
+                                => Err("syntax error".to_string()),
+                            Operator::MatrixMul => Err("Unsupported matrix product error.".to_string()),
+                            Operator::MatrixDiv | Operator::MatrixLeftDiv
+                                => Err("Unsupported matrix division error.".to_string()),
                             Operator::Mul | Operator::Div | Operator::Rem => {
                                 let expr_left = self.set_one_term(tree.left().unwrap(), variable)?;
                                 let expr_right = self.set_one_term(tree.right().unwrap(), variable)?;
@@ -131,14 +151,15 @@ impl Equation {
         }
     }
 
-    fn check_and_get_num_float(num: &Num) -> Result<f64, String> {
+    pub fn check_and_get_num_float(num: &Num) -> Result<f64, String> {
         match num {
             Num::Float(n) => Ok(*n),
-            _ => return Err(format!("{}: syntax error: Not a real number.", num)),
+            Num::Rational(q) => Ok(q.to_f64()),
+            _ => Err(format!("{}: syntax error: Not a real number.", num)),
         }
     }
 
-    fn check_variable(variable: &mut Option<String>, new_variable: &String) -> Result<(), String> {
+    pub fn check_variable(variable: &mut Option<String>, new_variable: &String) -> Result<(), String> {
         match variable {
             Some(v) => {
                 if v != new_variable {
@@ -153,9 +174,9 @@ impl Equation {
     fn operate_two_expr(expr_left: &Vec<Term>, expr_right: &Vec<Term>, op: &Operator) -> Result<Vec<Term>, String> {
         let mut expr = Vec::new();
         match expr_left.len().checked_mul(expr_right.len()) {
-            None => return Err(format!("too many terms error")),
+            None => return Err("too many terms error".to_string()),
             Some(n) => if n > MAX_TERMS {
-                return Err(format!("too many terms error"))
+                return Err("too many terms error".to_string())
             },
         }
         for left_term in expr_left {
@@ -164,7 +185,7 @@ impl Equation {
                     Operator::Mul => Self::operate_two_term_mul(left_term, right_term, &mut expr)?,
                     Operator::Div => Self::operate_two_term_div(left_term, right_term, &mut expr)?,
                     Operator::Rem => Self::operate_two_term_rem(left_term, right_term, &mut expr)?,
-                    _ => return Err(format!("syntax error")),
+                    _ => return Err("syntax error".to_string()),
                 }
             }
         }
@@ -183,7 +204,7 @@ impl Equation {
             return Err(format!("The calculation resulted in '{}'.", coefficient))
         }
         let degree = match left_term.degree.checked_add(right_term.degree) {
-            None => return Err(format!("overflow error")),
+            None => return Err("overflow error".to_string()),
             Some(n) => n,
         };
         expr.push(Term { coefficient, degree});
@@ -192,7 +213,7 @@ impl Equation {
 
     fn operate_two_term_div(left_term: &Term, right_term: &Term, expr: &mut Vec<Term>) -> Result<(), String> {
         if right_term.degree > 0 {
-            return Err(format!("error: cannot be divided by variable"))
+            return Err("error: cannot be divided by variable".to_string())
         }
         let coefficient = left_term.coefficient / right_term.coefficient;
         if coefficient == 0.0 {
@@ -207,10 +228,10 @@ impl Equation {
 
     fn operate_two_term_rem(left_term: &Term, right_term: &Term, expr: &mut Vec<Term>) -> Result<(), String> {
         if right_term.degree > 0 {
-            return Err(format!("error: cannot be divided by variable"))
+            return Err("error: cannot be divided by variable".to_string())
         }
         if left_term.degree > 0 {
-            return Err(format!("error: variable remainders cannot be calculated"))
+            return Err("error: variable remainders cannot be calculated".to_string())
         }
         let coefficient = left_term.coefficient.rem_euclid(right_term.coefficient);
         if coefficient == 0.0 {
@@ -226,14 +247,14 @@ impl Equation {
     fn operate_two_term_pow(expr_left: &Vec<Term>, expr_right: &Vec<Term>) -> Result<Vec<Term>, String> {
         let mut expr = Vec::new();
         let mut expr_right = Self::sort_expr(expr_right)?;
-        if expr_right.len() == 0 {
+        if expr_right.is_empty() {
             expr_right.push(Term { coefficient: 0.0, degree: 0 });
         }
         if expr_right.len() != 1
                 || expr_right[0].degree != 0
                 || !Num::is_int_value(expr_right[0].coefficient)
                 || (expr_right[0].coefficient.is_sign_negative() && expr_right[0].coefficient != 0.0) {
-            return Err(format!("error: only integers greater than or equal to 0 are allowed for exponents"))
+            return Err("error: only integers greater than or equal to 0 are allowed for exponents".to_string())
         }
         let coefficient = expr_right[0].coefficient as u32;
         if coefficient == 0 {
@@ -241,9 +262,9 @@ impl Equation {
             return Ok(expr)
         }
         match expr_left.len().checked_pow(coefficient) {
-            None => return Err(format!("too many terms error")),
+            None => return Err("too many terms error".to_string()),
             Some(n) => if n > MAX_TERMS {
-                return Err(format!("too many terms error"))
+                return Err("too many terms error".to_string())
             },
         }
         for term in expr_left {
@@ -278,7 +299,7 @@ impl Equation {
             }
         }
         let mut vec: Vec<Term> = terms.into_values().collect();
-        vec.sort_by(|a, b| a.degree.cmp(&b.degree));
+        vec.sort_by_key(|a| a.degree);
         Ok(vec)
     }
 
@@ -287,13 +308,13 @@ impl Equation {
         for (i, term) in self.expr.iter().enumerate() {
             if i > 0 {
                 if term.coefficient.is_sign_negative() {
-                    string += format!("- ").as_str()
+                    string += "- "
                 } else {
-                    string += format!("+ ").as_str()
+                    string += "+ "
                 }
             } else {
                 if term.coefficient.is_sign_negative() {
-                    string += format!("-").as_str()
+                    string += "-"
                 }
             }
             if term.degree == 0 {
@@ -311,12 +332,19 @@ impl Equation {
     fn get_variable_string(&self) -> Result<&String, String> {
         match &self.variable {
             Some(s) => Ok(s),
-            None => Err(format!("error: get variable string")),
+            None => Err("error: get variable string".to_string()),
         }
     }
 }
 
 
+impl Default for Equation {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -332,11 +360,11 @@ mod tests {
         };
 
         let mut parser = Parser::new(vec);
-        let mut tree = match parser.make_tree(&data_base) {
+        let mut tree = match parser.make_tree(data_base) {
             Ok(v) => v,
             Err(e) => return Err(format!("error parser: {}", e))
         };
-        match parser.calculation(&mut tree, &data_base, None) {
+        match parser.calculation(&mut tree, data_base, None) {
             Ok(_) => {}
             Err(e) => return Err(format!("error calculation: {}", e))
         }
@@ -517,4 +545,137 @@ mod tests {
         }, format!("-2 - 4x^1 - 8x^2 - 5x^3 - 6x^4 - 3x^5 - x^6"));
         Ok(())
     }
-}
\ No newline at end of file
+
+    fn solution_test(code: &str) -> String {
+        match make_equation_test(code.to_string(), "0".to_string()).and_then(|e| e.solution()) {
+            Ok(string) => string,
+            Err(string) => string,
+        }
+    }
+
+    #[test]
+    fn solution_quadratic() {
+        assert_eq!(solution_test("x^2 - 2"), format!("Two solutions on R:\n-1.4142135623730951\n1.4142135623730951"));
+        assert_eq!(solution_test("x^2 - 3x + 2"), format!("Two solutions on R:\n1\n2"));
+    }
+
+    #[test]
+    fn solution_cubic() {
+        assert_eq!(solution_test("x^3 - 6x^2 + 11x - 6"), format!("Three solutions on R:\n1\n2\n3"));
+        assert_eq!(solution_test("x^3 - 3x + 2"), format!("Two solutions on R:\n-2\n1 (multiplicity 2)"));
+        assert_eq!(solution_test("(x - 1)^3"), format!("Only one solution on R:\n1 (multiplicity 3)"));
+        assert_eq!(solution_test("x^3 + 1"), format!("Three solutions on C:\n-1\n0.5 ± 0.8660254037844386i"));
+        assert_eq!(solution_test("x^3 - x^2"), format!("Two solutions on R:\n0 (multiplicity 2)\n1"));
+    }
+
+    #[test]
+    fn solution_quartic() {
+        assert_eq!(solution_test("x^4 - 10x^3 + 35x^2 - 50x + 24"), format!("Four solutions on R:\n1\n2\n3\n4"));
+        assert_eq!(solution_test("x^4 - 1"), format!("Four solutions on C:\n-1\n1\n± i"));
+        assert_eq!(solution_test("(x^2 + 1)^2"), format!("Two solutions on C:\n± i (multiplicity 2)"));
+        assert_eq!(solution_test("(x - 1)^2 * (x - 2) * (x - 3)"), format!("Three solutions on R:\n1 (multiplicity 2)\n2\n3"));
+    }
+
+    #[test]
+    fn solution_numeric() {
+        assert_eq!(solution_test("x^6 - 1"),
+                   format!("6 solutions on C:\n-1\n1\n-0.5 ± 0.8660254037844386i\n0.5 ± 0.8660254037844386i\nPrecision: 8.3e-17"));
+        assert_eq!(solution_test("(x^2 + 1)^3"), format!("Two solutions on C:\n± i (multiplicity 3)\nPrecision: 1.1e-16"));
+        assert!(solution_test("x^7 - 3x + 1").starts_with("7 solutions on C:\n-1.249223292555673"));
+    }
+
+    fn rational_solution_test(code1: &str, code2: &str) -> Result<String, String> {
+        let data_base = DataBase::new();
+        let tree1 = prepare_tree(code1.to_string(), &data_base)?;
+        let tree2 = prepare_tree(code2.to_string(), &data_base)?;
+        let mut equation = Equation::new();
+        let denominators = equation.make_rational_equation(&tree1, &tree2)?;
+        Ok(format!("{} = 0\n{}", equation.to_string()?, equation.rational_solution(&denominators)?))
+    }
+
+    #[test]
+    fn solution_rational() {
+        assert_eq!(rational_solution_test("1/x + x", "2"), Ok("1 - 2x^1 + x^2 = 0\nOnly one solution on R:\n1 (multiplicity 2)".to_string()));
+        assert_eq!(rational_solution_test("1/x + 1/x^2", "0"), Ok("1 + x^1 = 0\nSolution:\n-1".to_string()));
+        assert_eq!(rational_solution_test("(x - 2) / (x^2 - 4)", "1"),
+                   Ok("2 + x^1 - x^2 = 0\nOnly one solution on R:\n-1\nExcluded 2, which makes a denominator zero.".to_string()));
+        assert_eq!(rational_solution_test("x / x", "1"), Ok("0 = 0\nThe solution is an arbitrary real number except 0.".to_string()));
+    }
+
+    #[test]
+    fn solution_steps_quadratic() -> Result<(), String> {
+        let steps = make_equation_test("x^2 - 3x + 2".to_string(), "0".to_string())?.solution_steps()?;
+        assert_eq!(steps, vec![
+            format!("Reduced form: 2 - 3x^1 + x^2 = 0"),
+            format!("Degree: 2"),
+            format!("Coefficients: a = 1, b = -3, c = 2"),
+            format!("Discriminant: Δ = b^2 - 4ac = (-3)^2 - 4 * 1 * 2 = 1"),
+            format!("Δ > 0, so there are two real roots: x = (-b ± √Δ) / 2a"),
+            format!("√Δ = 1"),
+            format!("x1 = (-(-3) + 1) / (2 * 1) = 2"),
+            format!("x2 = (-(-3) - 1) / (2 * 1) = 1"),
+        ]);
+        let steps = make_equation_test("x^2 + 3x + 1".to_string(), "0".to_string())?.solution_steps()?;
+        assert_eq!(steps[6], "x1 = (-3 + 2.23606797749979) / (2 * 1) = -0.38196601125010515");
+        assert!(solution_test("x^2 + 3x + 1").ends_with("\n-0.38196601125010515"));
+        let steps = make_equation_test("2x".to_string(), "-3".to_string())?.solution_steps()?;
+        assert_eq!(steps[2..], ["Formula: x = -b / a with a = 2, b = 3".to_string(), "x = -3 / 2 = -1.5".to_string()]);
+        Ok(())
+    }
+
+    #[test]
+    fn exact_solution_normal() -> Result<(), String> {
+        let exact_solution_test = |code: &str| make_equation_test(code.to_string(), "0".to_string())?.exact_solution();
+        assert_eq!(exact_solution_test("x^2 + 3x + 1"), Ok("Two solutions on R:\n(-3 ± sqrt(5))/2".to_string()));
+        assert_eq!(exact_solution_test("x^2 - 2x + 5"), Ok("Two solutions on C:\n1 ± 2i".to_string()));
+        assert_eq!(exact_solution_test("2x^2 - x - 1"), Ok("Two solutions on R:\n-1/2\n1".to_string()));
+        assert_eq!(exact_solution_test("x / 3 + 1 / 4"), Ok("Solution:\n-3/4".to_string()));
+        assert_eq!(exact_solution_test("x - 0.3333333333333333"), Ok("Solution:\n3333333333333333/10000000000000000".to_string()));
+        assert_eq!(exact_solution_test("3x - 1 / 1234567"), Ok("Solution:\n1/3703701".to_string()));
+        assert_eq!(exact_solution_test("x^2 - pi"), Ok("Two solutions on R:\n-1.7724538509055159\n1.7724538509055159".to_string()));
+        Ok(())
+    }
+
+    #[test]
+    fn factor_string_normal() -> Result<(), String> {
+        let factor_test = |code: &str| make_equation_test(code.to_string(), "0".to_string())?.factor_string();
+        assert_eq!(factor_test("x^2 - 5x + 6"), Ok("(x - 2)(x - 3)".to_string()));
+        assert_eq!(factor_test("2x^3 - 2x"), Ok("2x(x + 1)(x - 1)".to_string()));
+        assert_eq!(factor_test("x^4 - 1"), Ok("(x + 1)(x - 1)(x^2 + 1)".to_string()));
+        assert_eq!(factor_test("x^2 / 2 - 2"), Ok("(1/2)(x + 2)(x - 2)".to_string()));
+        assert_eq!(factor_test("(x + 1)^3"), Ok("(x + 1)^3".to_string()));
+        assert_eq!(factor_test("x^2 + x + 1"), Ok("x^2 + x + 1".to_string()));
+        assert_eq!(factor_test("x^4 + 4"), Ok("(x^2 - 2x + 2)(x^2 + 2x + 2)".to_string()));
+        assert_eq!(factor_test("x^4 + 2x^2 + 1"), Ok("(x^2 + 1)^2".to_string()));
+        assert_eq!(factor_test("x^6 - 1"), Ok("(x + 1)(x - 1)(x^2 - x + 1)(x^2 + x + 1)".to_string()));
+        Ok(())
+    }
+
+    #[test]
+    fn polynomial_string_normal() -> Result<(), String> {
+        let expand_test = |code: &str| make_equation_test(code.to_string(), "0".to_string()).map(|e| e.polynomial_string());
+        assert_eq!(expand_test("(x + 1)^3"), Ok(Some("x^3 + 3x^2 + 3x + 1".to_string())));
+        assert_eq!(expand_test("x / 2 - 3 / 4"), Ok(Some("(1/2)x - 3/4".to_string())));
+        assert_eq!(expand_test("x^2 - pi"), Ok(None));
+        Ok(())
+    }
+
+    #[test]
+    fn solution_set_normal() -> Result<(), String> {
+        use crate::solution::SolutionKind;
+        let set = make_equation_test("x^2 + 2x + 5".to_string(), "0".to_string())?.solution_set()?;
+        assert_eq!(set.kind, SolutionKind::Finite);
+        assert_eq!(set.roots, vec![(Num::from_two_float(-1.0, 2.0), 1), (Num::from_two_float(-1.0, -2.0), 1)]);
+        let set = make_equation_test("(x - 1)^2".to_string(), "0".to_string())?.solution_set()?;
+        assert_eq!(set.roots, vec![(Num::Float(1.0), 2)]);
+        let set = make_equation_test("x".to_string(), "x + 1".to_string())?.solution_set()?;
+        assert_eq!((set.kind, set.to_vector()), (SolutionKind::Empty, None));
+        let equation = make_equation_test("x^2".to_string(), "1".to_string())?;
+        let (string, set) = equation.solve(false)?;
+        assert_eq!(string, "Two solutions on R:\n-1\n1".to_string());
+        assert_eq!(set.map(|set| set.roots), Some(vec![(Num::Float(-1.0), 1), (Num::Float(1.0), 1)]));
+        let set = make_equation_test("x^3 - x^2".to_string(), "0".to_string())?.solution_set()?;
+        assert_eq!(set.multiplicities(), Num::from_num_vec(vec![vec![Num::from_i64(2), Num::from_i64(1)]]).ok());
+        Ok(())
+    }
+}
@@ -0,0 +1,203 @@
+use crate::equation::Equation;
+use crate::num::Num;
+
+
+/// Largest denominator looked for when a coefficient is read back as a fraction.
+const MAX_DENOMINATOR: i128 = 1_000_000;
+/// Largest discriminant whose square factors are extracted.
+const MAX_DISCRIMINANT: i128 = 1_000_000_000_000_000_000;
+
+
+pub fn gcd(a: i128, b: i128) -> i128 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+
+/// The fraction `n / d` equal to `x` up to rounding, found by continued fractions.
+pub fn to_ratio(x: f64) -> Option<(i128, i128)> {
+    if !x.is_finite() || x.abs() >= 1e18 {
+        return None
+    }
+    let (mut h, mut h_prev, mut k, mut k_prev) = (1i128, 0i128, 0i128, 1i128);
+    let mut rest = x;
+    loop {
+        let a = rest.floor();
+        (h, h_prev) = ((a as i128).checked_mul(h)?.checked_add(h_prev)?, h);
+        (k, k_prev) = ((a as i128).checked_mul(k)?.checked_add(k_prev)?, k);
+        if k > MAX_DENOMINATOR {
+            return None
+        }
+        if (h as f64 / k as f64 - x).abs() <= 4.0 * f64::EPSILON * x.abs() {
+            return Some((h, k))
+        }
+        rest = 1.0 / (rest - a);
+        if !rest.is_finite() {
+            return None
+        }
+    }
+}
+
+
+/// Coprime integers proportional to the coefficients, with a positive leading one.
+pub fn integer_coefficients(coefficients: &[f64]) -> Option<Vec<i128>> {
+    let ratios = coefficients.iter().map(|c| to_ratio(*c)).collect::<Option<Vec<_>>>()?;
+    integers_from_ratios(&ratios)
+}
+
+
+/// `integer_coefficients` for exact fractions.
+fn exact_integer_coefficients(coefficients: &[Num]) -> Option<Vec<i128>> {
+    let ratios = coefficients.iter()
+        .map(|c| match c {
+            Num::Rational(q) => Some((q.numer().to_i64()? as i128, q.denom().to_i64()? as i128)),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()?;
+    integers_from_ratios(&ratios)
+}
+
+
+fn integers_from_ratios(ratios: &[(i128, i128)]) -> Option<Vec<i128>> {
+    let mut lcm = 1i128;
+    for (_, d) in ratios {
+        lcm = (lcm / gcd(lcm, *d)).checked_mul(*d)?;
+    }
+    let mut integers = ratios.iter().map(|(n, d)| n.checked_mul(lcm / d)).collect::<Option<Vec<_>>>()?;
+    let common = integers.iter().fold(0, |g, n| gcd(g, *n));
+    let sign = if integers[integers.len() - 1] < 0 {-1} else {1};
+    integers.iter_mut().for_each(|n| *n = sign * *n / common);
+    Some(integers)
+}
+
+
+fn isqrt(n: i128) -> i128 {
+    let mut root = (n as f64).sqrt() as i128;
+    while root * root > n {
+        root -= 1;
+    }
+    while (root + 1) * (root + 1) <= n {
+        root += 1;
+    }
+    root
+}
+
+
+/// `(s, d)` with `n = s^2 d` and `d` squarefree, for a positive `n`. After the
+/// primes up to the cube root are divided out, what is left is 1, a prime, a
+/// product of two distinct primes or the square of a prime.
+fn square_part(n: i128) -> (i128, i128) {
+    let (mut rest, mut s, mut d) = (n, 1, 1);
+    let mut p = 2;
+    while p * p * p <= rest {
+        while rest % (p * p) == 0 {
+            rest /= p * p;
+            s *= p;
+        }
+        if rest % p == 0 {
+            rest /= p;
+            d *= p;
+        }
+        p += 1;
+    }
+    let root = isqrt(rest);
+    if root * root == rest {(s * root, d)} else {(s, d * rest)}
+}
+
+
+pub fn ratio_string(n: i128, d: i128) -> String {
+    let g = gcd(n, d);
+    let (n, d) = if d < 0 {(-n / g, -d / g)} else {(n / g, d / g)};
+    if d == 1 {format!("{}", n)} else {format!("{}/{}", n, d)}
+}
+
+
+/// `(p ± r sqrt(d)) / q`, times `i` on the radical when `imaginary`.
+fn radical_string(p: i128, r: i128, d: i128, q: i128, imaginary: bool) -> String {
+    let g = gcd(gcd(p, r), q);
+    let (p, r, q) = (p / g, r / g, q / g);
+    let unit = if d == 1 {String::new()} else {format!("sqrt({})", d)};
+    let coefficient = if r == 1 && (d != 1 || imaginary) {String::new()} else {format!("{}", r)};
+    let radical = coefficient + &unit + if imaginary {"i"} else {""};
+    match (p, q) {
+        (0, 1) => format!("± {}", radical),
+        (_, 1) => format!("{} ± {}", p, radical),
+        (0, _) => format!("± {}/{}", radical, q),
+        _ => format!("({} ± {})/{}", p, radical, q),
+    }
+}
+
+
+impl Equation {
+    /// Solution of a linear or quadratic equation with rational coefficients,
+    /// with roots as fractions and radicals. Other equations are solved as in `solution`.
+    pub fn exact_solution(&self) -> Result<String, String> {
+        Ok(self.solve(true)?.0)
+    }
+
+    /// The roots of a linear or quadratic equation with rational coefficients
+    /// as fractions and radicals, in the order of the solution set.
+    pub fn exact_roots_string(&self) -> Option<String> {
+        if self.degree != 1 && self.degree != 2 {
+            return None
+        }
+        let coefficients = self.exact_coefficients.as_ref()?;
+        Self::integer_roots_string(&exact_integer_coefficients(coefficients)?)
+    }
+
+    fn integer_roots_string(integers: &[i128]) -> Option<String> {
+        if integers.len() == 2 {
+            return Some(format!("Solution:\n{}", ratio_string(-integers[0], integers[1])))
+        }
+        let (c, b, a) = (integers[0], integers[1], integers[2]);
+        let discriminant = b.checked_mul(b)?.checked_sub(a.checked_mul(c)?.checked_mul(4)?)?;
+        if discriminant.abs() > MAX_DISCRIMINANT {
+            return None
+        }
+        if discriminant == 0 {
+            return Some(format!("Only one solution on R:\n{}", ratio_string(-b, 2 * a)))
+        }
+        let (s, d) = square_part(discriminant.abs());
+        Some(if discriminant < 0 {
+            format!("Two solutions on C:\n{}", radical_string(-b, s, d, 2 * a, true))
+        } else if d == 1 {
+            let (low, high) = if a > 0 {(-b - s, -b + s)} else {(-b + s, -b - s)};
+            format!("Two solutions on R:\n{}\n{}", ratio_string(low, 2 * a), ratio_string(high, 2 * a))
+        } else {
+            format!("Two solutions on R:\n{}", radical_string(-b, s, d, 2 * a, false))
+        })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn to_ratio_normal() {
+        assert_eq!(to_ratio(0.75), Some((3, 4)));
+        assert_eq!(to_ratio(-1.0 / 3.0), Some((-1, 3)));
+        assert_eq!(to_ratio(0.1 + 0.2), Some((3, 10)));
+        assert_eq!(to_ratio(std::f64::consts::PI), None);
+    }
+
+    #[test]
+    fn square_part_normal() {
+        assert_eq!(square_part(8), (2, 2));
+        assert_eq!(square_part(5), (1, 5));
+        assert_eq!(square_part(16), (4, 1));
+        assert_eq!(square_part(2 * 2 * 3 * 1_000_003 * 1_000_003), (2 * 1_000_003, 3));
+    }
+
+    #[test]
+    fn radical_string_normal() {
+        assert_eq!(radical_string(-3, 1, 5, 2, false), "(-3 ± sqrt(5))/2");
+        assert_eq!(radical_string(2, 4, 1, 2, true), "1 ± 2i");
+        assert_eq!(radical_string(0, 2, 2, 2, false), "± sqrt(2)");
+        assert_eq!(radical_string(0, 1, 3, 2, true), "± sqrt(3)i/2");
+    }
+}
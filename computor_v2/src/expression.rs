@@ -0,0 +1,512 @@
+use crate::binary_tree::BinaryTree;
+use crate::parser::Element;
+use crate::operator::Operator;
+use crate::num::Num;
+
+
+const MAX_DEGREE: usize = 1000;
+
+
+/// Expression tree without parentheses, used for symbolic manipulation.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Expr {
+    Num(Num),
+    Variable(String),
+    Neg(Box<Expr>),
+    Add(Box<Expr>, Box<Expr>),
+    Sub(Box<Expr>, Box<Expr>),
+    Mul(Box<Expr>, Box<Expr>),
+    Div(Box<Expr>, Box<Expr>),
+    Pow(Box<Expr>, Box<Expr>),
+    Func(String, Vec<Expr>),
+}
+
+
+#[allow(clippy::should_implement_trait)]
+impl Expr {
+    pub fn from_i64(n: i64) -> Expr {
+        Expr::Num(Num::from_i64(n))
+    }
+
+    pub fn var(name: &str) -> Expr {
+        Expr::Variable(name.to_string())
+    }
+
+    pub fn func(name: &str, arg: Expr) -> Expr {
+        Expr::Func(name.to_string(), vec![arg])
+    }
+
+    pub fn neg(a: Expr) -> Expr {
+        Expr::Neg(Box::new(a))
+    }
+
+    pub fn add(a: Expr, b: Expr) -> Expr {
+        Expr::Add(Box::new(a), Box::new(b))
+    }
+
+    pub fn sub(a: Expr, b: Expr) -> Expr {
+        Expr::Sub(Box::new(a), Box::new(b))
+    }
+
+    pub fn mul(a: Expr, b: Expr) -> Expr {
+        Expr::Mul(Box::new(a), Box::new(b))
+    }
+
+    pub fn div(a: Expr, b: Expr) -> Expr {
+        Expr::Div(Box::new(a), Box::new(b))
+    }
+
+    pub fn pow(a: Expr, b: Expr) -> Expr {
+        Expr::Pow(Box::new(a), Box::new(b))
+    }
+
+    pub fn from_tree(tree: &BinaryTree<Element>) -> Result<Expr, String> {
+        let node_box = match tree {
+            BinaryTree::Empty => return Err("syntax error".to_string()),
+            BinaryTree::NonEmpty(node_box) => node_box,
+        };
+        let left = tree.left().unwrap();
+        let right = tree.right().unwrap();
+        match &node_box.element {
+            Element::Num(n) => Ok(Expr::Num(n.clone())),
+            Element::Variable(v) => Ok(Expr::Variable(*v.clone())),
+            Element::Dummy => Ok(Expr::from_i64(0)),
+            Element::Func(name) => {
+                let mut args = Vec::new();
+                Self::push_args(left.left().unwrap(), &mut args)?;
+                Ok(Expr::Func(*name.clone(), args))
+            },
+            Element::Operator(op) => {
+                if let Operator::Paren = op {
+                    return Self::from_tree(left)
+                }
+                let is_unary = matches!(left, BinaryTree::NonEmpty(b) if b.element == Element::Dummy);
+                let rhs = Self::from_tree(right)?;
+                match op {
+                    Operator::Plus if is_unary => Ok(rhs),
+                    Operator::Minus if is_unary => Ok(Expr::neg(rhs)),
+                    Operator::Plus => Ok(Expr::add(Self::from_tree(left)?, rhs)),
+                    Operator::Minus => Ok(Expr::sub(Self::from_tree(left)?, rhs)),
+                    Operator::Mul => Ok(Expr::mul(Self::from_tree(left)?, rhs)),
+                    Operator::Div => Ok(Expr::div(Self::from_tree(left)?, rhs)),
+                    Operator::Pow => Ok(Expr::pow(Self::from_tree(left)?, rhs)),
+                    _ => Err(format!("error: unsupported operator {} in a symbolic expression", op)),
+                }
+            },
+        }
+    }
+
+    fn push_args(tree: &BinaryTree<Element>, args: &mut Vec<Expr>) -> Result<(), String> {
+        match tree {
+            BinaryTree::NonEmpty(node_box) if node_box.element == Element::Operator(Operator::Comma) => {
+                Self::push_args(tree.left().unwrap(), args)?;
+                Self::push_args(tree.right().unwrap(), args)
+            },
+            _ => {
+                args.push(Self::from_tree(tree)?);
+                Ok(())
+            },
+        }
+    }
+
+    /// Tree in the form built by the parser, with the parentheses needed to read it back.
+    pub fn to_tree(&self) -> BinaryTree<Element> {
+        let binary = |op: Operator, a: &Expr, b: &Expr| {
+            let level = self.level();
+            let non_associative = matches!(op, Operator::Minus | Operator::Div | Operator::Pow);
+            let left_paren = if a.level() == 0 && level == 1 {false} else {a.level() < level};
+            let right_paren = b.level() < level || (b.level() == level && non_associative)
+                || (level == 1 && b.starts_with_sign());
+            BinaryTree::from_element_and_tree(
+                Element::Operator(op),
+                a.to_tree_paren(left_paren),
+                b.to_tree_paren(right_paren),
+            )
+        };
+        match self {
+            Expr::Num(n) => BinaryTree::from_element(Element::Num(n.clone())),
+            Expr::Variable(v) => BinaryTree::from_element(Element::Variable(Box::new(v.clone()))),
+            Expr::Neg(a) => BinaryTree::from_element_and_tree(
+                Element::Operator(Operator::Minus),
+                BinaryTree::from_element(Element::Dummy),
+                a.to_tree_paren(a.level() <= 1),
+            ),
+            Expr::Add(a, b) => binary(Operator::Plus, a, b),
+            Expr::Sub(a, b) => binary(Operator::Minus, a, b),
+            Expr::Mul(a, b) => binary(Operator::Mul, a, b),
+            Expr::Div(a, b) => binary(Operator::Div, a, b),
+            Expr::Pow(a, b) => binary(Operator::Pow, a, b),
+            Expr::Func(name, args) => {
+                let args_tree = args.iter().map(|a| a.to_tree())
+                    .reduce(|acc, a| BinaryTree::from_element_and_tree(
+                        Element::Operator(Operator::Comma), acc, a))
+                    .unwrap_or(BinaryTree::Empty);
+                BinaryTree::from_element_and_tree(
+                    Element::Func(Box::new(name.clone())),
+                    Self::paren(args_tree),
+                    BinaryTree::from_element(Element::Operator(Operator::RParen)),
+                )
+            },
+        }
+    }
+
+    fn to_tree_paren(&self, paren: bool) -> BinaryTree<Element> {
+        if paren {
+            Self::paren(self.to_tree())
+        } else {
+            self.to_tree()
+        }
+    }
+
+    fn paren(tree: BinaryTree<Element>) -> BinaryTree<Element> {
+        BinaryTree::from_element_and_tree(
+            Element::Operator(Operator::Paren),
+            tree,
+            BinaryTree::from_element(Element::Operator(Operator::RParen)),
+        )
+    }
+
+    /// Binding strength when printed; 0 for values starting with a sign.
+    fn level(&self) -> u8 {
+        match self {
+            Expr::Num(n) => if n.is_need_paren_to_display() {0} else {4},
+            Expr::Neg(_) => 0,
+            Expr::Add(..) | Expr::Sub(..) => 1,
+            Expr::Mul(..) | Expr::Div(..) => 2,
+            Expr::Pow(..) => 3,
+            Expr::Variable(_) | Expr::Func(..) => 4,
+        }
+    }
+
+    /// Printed with a leading sign, which cannot follow a binary operator.
+    fn starts_with_sign(&self) -> bool {
+        match self {
+            Expr::Add(a, _) | Expr::Sub(a, _) => a.starts_with_sign(),
+            _ => self.level() == 0,
+        }
+    }
+
+    pub fn contains_variable(&self, variable: &str) -> bool {
+        match self {
+            Expr::Num(_) => false,
+            Expr::Variable(v) => v == variable,
+            Expr::Neg(a) => a.contains_variable(variable),
+            Expr::Add(a, b) | Expr::Sub(a, b) | Expr::Mul(a, b) | Expr::Div(a, b) | Expr::Pow(a, b)
+                => a.contains_variable(variable) || b.contains_variable(variable),
+            Expr::Func(_, args) => args.iter().any(|a| a.contains_variable(variable)),
+        }
+    }
+
+    /// Replace each variable found in `names` by the expression at the same index of `values`.
+    pub fn substitute(&self, names: &[String], values: &[Expr]) -> Expr {
+        let sub = |e: &Expr| e.substitute(names, values);
+        match self {
+            Expr::Num(_) => self.clone(),
+            Expr::Variable(v) => match names.iter().position(|n| n == v) {
+                Some(i) => values[i].clone(),
+                None => self.clone(),
+            },
+            Expr::Neg(a) => Expr::neg(sub(a)),
+            Expr::Add(a, b) => Expr::add(sub(a), sub(b)),
+            Expr::Sub(a, b) => Expr::sub(sub(a), sub(b)),
+            Expr::Mul(a, b) => Expr::mul(sub(a), sub(b)),
+            Expr::Div(a, b) => Expr::div(sub(a), sub(b)),
+            Expr::Pow(a, b) => Expr::pow(sub(a), sub(b)),
+            Expr::Func(name, args) => Expr::Func(name.clone(), args.iter().map(sub).collect()),
+        }
+    }
+
+    /// Exact coefficients, lowest degree first, if `self` is a polynomial in `variable`.
+    pub fn polynomial(&self, variable: &str) -> Option<Vec<Num>> {
+        let coefficients = match self {
+            Expr::Num(n) if !n.is_matrix() => vec![n.clone()],
+            Expr::Num(_) => return None,
+            Expr::Variable(v) if v == variable => vec![Num::zero(), Num::from_i64(1)],
+            Expr::Variable(_) | Expr::Func(..) => return None,
+            Expr::Neg(a) => a.polynomial(variable)?.iter().map(|c| c.reverse_sign()).collect(),
+            Expr::Add(a, b) => Self::polynomial_add(&a.polynomial(variable)?, &b.polynomial(variable)?, Num::supported_add)?,
+            Expr::Sub(a, b) => Self::polynomial_add(&a.polynomial(variable)?, &b.polynomial(variable)?, Num::supported_sub)?,
+            Expr::Mul(a, b) => Self::polynomial_mul(&a.polynomial(variable)?, &b.polynomial(variable)?)?,
+            Expr::Div(a, b) => {
+                let divisor = match b.polynomial(variable)?.as_slice() {
+                    [c] if !c.is_zero() => c.clone(),
+                    _ => return None,
+                };
+                a.polynomial(variable)?.iter().map(|c| c.supported_div(&divisor).ok()).collect::<Option<_>>()?
+            },
+            Expr::Pow(a, b) => {
+                let exp = match b.as_num()?.to_int_value() {
+                    Some(n) if (0..=MAX_DEGREE as i64).contains(&n) => n,
+                    _ => return None,
+                };
+                let base = a.polynomial(variable)?;
+                let mut coefficients = vec![Num::from_i64(1)];
+                for _ in 0..exp {
+                    coefficients = Self::polynomial_mul(&coefficients, &base)?;
+                }
+                coefficients
+            },
+        };
+        let mut coefficients = coefficients;
+        while coefficients.len() > 1 && coefficients.last().unwrap().is_zero() {
+            coefficients.pop();
+        }
+        Some(coefficients)
+    }
+
+    fn polynomial_add(a: &[Num], b: &[Num], op: fn(&Num, &Num) -> Result<Num, String>) -> Option<Vec<Num>> {
+        let zero = Num::zero();
+        (0..a.len().max(b.len()))
+            .map(|i| op(a.get(i).unwrap_or(&zero), b.get(i).unwrap_or(&zero)).ok())
+            .collect()
+    }
+
+    fn polynomial_mul(a: &[Num], b: &[Num]) -> Option<Vec<Num>> {
+        if a.len() + b.len() > MAX_DEGREE + 2 {
+            return None
+        }
+        let mut coefficients = vec![Num::zero(); a.len() + b.len() - 1];
+        for (i, x) in a.iter().enumerate() {
+            for (j, y) in b.iter().enumerate() {
+                coefficients[i + j] = coefficients[i + j].supported_add(&x.supported_mul(y).ok()?).ok()?;
+            }
+        }
+        Some(coefficients)
+    }
+
+    /// The polynomial with the given coefficients, highest degree first.
+    pub fn from_polynomial(coefficients: &[Num], variable: &str) -> Expr {
+        let mut expr: Option<Expr> = None;
+        for (degree, c) in coefficients.iter().enumerate().rev() {
+            if c.is_zero() {
+                continue
+            }
+            let power = Self::simplify_pow(Expr::var(variable), Expr::from_i64(degree as i64));
+            let term = Self::simplify_mul(Expr::Num(c.clone()), power);
+            expr = Some(match expr {
+                None => term,
+                Some(e) => Self::simplify_add(e, term),
+            });
+        }
+        expr.unwrap_or(Expr::from_i64(0))
+    }
+
+    fn as_num(&self) -> Option<&Num> {
+        match self {
+            Expr::Num(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    fn is_num_zero(&self) -> bool {
+        self.as_num().is_some_and(|n| n.is_zero())
+    }
+
+    fn is_num_one(&self) -> bool {
+        self.as_num().is_some_and(|n| n.is_one())
+    }
+
+    /// The negated expression, if `self` is written with a leading minus sign.
+    fn negated(&self) -> Option<Expr> {
+        match self {
+            Expr::Num(n) if n.is_real() && n.is_sign_negative() && !n.is_zero() => Some(Expr::Num(n.reverse_sign())),
+            Expr::Neg(a) => Some(*a.clone()),
+            _ => None,
+        }
+    }
+
+    fn fold(a: &Expr, b: &Expr, op: fn(&Num, &Num) -> Result<Num, String>) -> Option<Expr> {
+        let n = op(a.as_num()?, b.as_num()?).ok()?;
+        n.checked_value().ok()?;
+        Some(Expr::Num(n))
+    }
+
+    /// Apply constant folding and the neutral and absorbing element rules, bottom up.
+    pub fn simplify(&self) -> Expr {
+        match self {
+            Expr::Num(_) | Expr::Variable(_) => self.clone(),
+            Expr::Func(name, args) => Expr::Func(name.clone(), args.iter().map(|a| a.simplify()).collect()),
+            Expr::Neg(a) => Self::simplify_neg(a.simplify()),
+            Expr::Add(a, b) => Self::simplify_add(a.simplify(), b.simplify()),
+            Expr::Sub(a, b) => Self::simplify_sub(a.simplify(), b.simplify()),
+            Expr::Mul(a, b) => Self::simplify_mul(a.simplify(), b.simplify()),
+            Expr::Div(a, b) => Self::simplify_div(a.simplify(), b.simplify()),
+            Expr::Pow(a, b) => Self::simplify_pow(a.simplify(), b.simplify()),
+        }
+    }
+
+    fn simplify_neg(a: Expr) -> Expr {
+        match a {
+            Expr::Num(n) => Expr::Num(if n.is_zero() {n} else {n.reverse_sign()}),
+            Expr::Neg(b) => *b,
+            _ => Expr::neg(a),
+        }
+    }
+
+    fn simplify_add(a: Expr, b: Expr) -> Expr {
+        if a.as_num().is_some() && b.as_num().is_some() {
+            return Self::fold(&a, &b, Num::supported_add).unwrap_or(Expr::add(a, b))
+        }
+        if a.is_num_zero() {
+            return b
+        }
+        if b.is_num_zero() {
+            return a
+        }
+        if let Some(b) = b.negated() {
+            return Self::simplify_sub(a, b)
+        }
+        if let Some(a) = a.negated() {
+            if !matches!(a, Expr::Num(_)) {
+                return Self::simplify_sub(b, a)
+            }
+        }
+        Expr::add(a, b)
+    }
+
+    fn simplify_sub(a: Expr, b: Expr) -> Expr {
+        if a.as_num().is_some() && b.as_num().is_some() {
+            return Self::fold(&a, &b, Num::supported_sub).unwrap_or(Expr::sub(a, b))
+        }
+        if a == b {
+            return Expr::from_i64(0)
+        }
+        if b.is_num_zero() {
+            return a
+        }
+        if a.is_num_zero() {
+            return Self::simplify_neg(b)
+        }
+        if let Some(b) = b.negated() {
+            return Self::simplify_add(a, b)
+        }
+        Expr::sub(a, b)
+    }
+
+    fn simplify_mul(a: Expr, b: Expr) -> Expr {
+        if a.as_num().is_some() && b.as_num().is_some() {
+            return Self::fold(&a, &b, Num::supported_mul).unwrap_or(Expr::mul(a, b))
+        }
+        if a.is_num_zero() || b.is_num_zero() {
+            return Expr::from_i64(0)
+        }
+        if a.is_num_one() {
+            return b
+        }
+        if b.is_num_one() {
+            return a
+        }
+        if let Some(a) = a.negated() {
+            return Self::simplify_neg(Self::simplify_mul(a, b))
+        }
+        if let Some(b) = b.negated() {
+            return Self::simplify_neg(Self::simplify_mul(a, b))
+        }
+        match (a, b) {
+            (a, Expr::Num(n)) if !matches!(a, Expr::Num(_)) => Self::simplify_mul(Expr::Num(n), a),
+            (Expr::Num(n), Expr::Mul(c, d)) if matches!(*c, Expr::Num(_)) => {
+                match Self::fold(&Expr::Num(n.clone()), &c, Num::supported_mul) {
+                    Some(m) => Self::simplify_mul(m, *d),
+                    None => Expr::mul(Expr::Num(n), Expr::Mul(c, d)),
+                }
+            },
+            (a, Expr::Mul(c, d)) if matches!(*c, Expr::Num(_)) && !matches!(a, Expr::Num(_))
+                => Self::simplify_mul(*c, Self::simplify_mul(a, *d)),
+            (Expr::Mul(c, d), b) if matches!(*c, Expr::Num(_))
+                => Self::simplify_mul(*c, Self::simplify_mul(*d, b)),
+            (a, b) if a == b => Self::simplify_pow(a, Expr::from_i64(2)),
+            (a, b) => Expr::mul(a, b),
+        }
+    }
+
+    fn simplify_div(a: Expr, b: Expr) -> Expr {
+        if !b.is_num_zero() {
+            if let Some(n) = Self::fold(&a, &b, Num::supported_div) {
+                return n
+            }
+            if a.is_num_zero() {
+                return Expr::from_i64(0)
+            }
+            if a == b {
+                return Expr::from_i64(1)
+            }
+        }
+        if b.is_num_one() {
+            return a
+        }
+        if let Some(a) = a.negated() {
+            return Self::simplify_neg(Self::simplify_div(a, b))
+        }
+        if let Some(b) = b.negated() {
+            return Self::simplify_neg(Self::simplify_div(a, b))
+        }
+        Expr::div(a, b)
+    }
+
+    fn simplify_pow(a: Expr, b: Expr) -> Expr {
+        if b.is_num_zero() {
+            return Expr::from_i64(1)
+        }
+        if b.is_num_one() || a.is_num_one() {
+            return a
+        }
+        if let Some(n) = Self::fold(&a, &b, Num::supported_pow) {
+            if n.as_num().unwrap().is_exact() || !a.as_num().unwrap().is_exact() || !b.as_num().unwrap().is_exact() {
+                return n
+            }
+        }
+        match a {
+            Expr::Pow(c, d) if matches!((&*d, &b), (Expr::Num(_), Expr::Num(_)))
+                    && b.as_num().unwrap().to_int_value().is_some() => {
+                Self::simplify_pow(*c, Self::simplify_mul(*d, b))
+            },
+            a => Expr::pow(a, b),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_base::DataBase;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn round_trip_test(code: &str) -> Result<String, String> {
+        let data_base = DataBase::new();
+        let mut parser = Parser::new(Lexer::new(code).make_token_vec()?);
+        let tree = parser.make_tree(&data_base)?;
+        Parser::print_tree(&Expr::from_tree(&tree)?.to_tree())
+    }
+
+    fn simplify_test(code: &str) -> Result<String, String> {
+        let data_base = DataBase::new();
+        let mut parser = Parser::new(Lexer::new(code).make_token_vec()?);
+        let tree = parser.make_tree(&data_base)?;
+        Parser::print_tree(&Expr::from_tree(&tree)?.simplify().to_tree())
+    }
+
+    #[test]
+    fn round_trip_paren() {
+        assert_eq!(round_trip_test("(a + b) * (c - d) / (e * f)"), Ok("( a + b ) * ( c - d ) / ( e * f )".to_string()));
+        assert_eq!(round_trip_test("a - (b - c) + (d + e) + (-f + g)"), Ok("a - ( b - c ) + d + e + ( - f + g )".to_string()));
+        assert_eq!(round_trip_test("(a ^ b) ^ c + a ^ (b ^ c)"), Ok("a ^ b ^ c + a ^ ( b ^ c )".to_string()));
+        assert_eq!(round_trip_test("-a * b + sin(a, -b)"), Ok("- a * b + sin ( a , - b )".to_string()));
+        assert_eq!(round_trip_test("(-a) ^ 2 - (-(a + b))"), Ok("( - a ) ^ 2 - ( - ( a + b ) )".to_string()));
+    }
+
+    #[test]
+    fn simplify_neutral() {
+        assert_eq!(simplify_test("0 + a * 1 - 0 + b ^ 1 * c ^ 0"), Ok("a + b".to_string()));
+        assert_eq!(simplify_test("0 * a + 0 / b - (c - c)"), Ok("0".to_string()));
+        assert_eq!(simplify_test("a + (-(2 * b)) - (-c)"), Ok("a - 2 * b + c".to_string()));
+    }
+
+    #[test]
+    fn simplify_coefficient() {
+        assert_eq!(simplify_test("a * 2 * (3 * b)"), Ok("6 * a * b".to_string()));
+        assert_eq!(simplify_test("(a ^ 2) ^ 3 + b * b"), Ok("a ^ 6 + b ^ 2".to_string()));
+        assert_eq!(simplify_test("1 / 3 * a - 2 ^ (1 / 2)"), Ok("( 1/3 ) * a - 2 ^ ( 1/2 )".to_string()));
+    }
+}
@@ -0,0 +1,275 @@
+use crate::equation::Equation;
+use crate::exact_roots::{gcd, integer_coefficients, ratio_string, to_ratio};
+
+
+/// Largest coefficient whose divisors are tried as rational roots.
+const MAX_DIVISOR_SEARCH: i128 = 1_000_000_000_000;
+/// Largest number of quadratics tried as factors of one polynomial.
+const MAX_QUADRATIC_CANDIDATES: usize = 1_000_000;
+
+
+/// Positive divisors of a nonzero `n`.
+fn divisors(n: i128) -> Vec<i128> {
+    let n = n.abs();
+    let mut small = Vec::new();
+    let mut large = Vec::new();
+    let mut d = 1;
+    while d * d <= n {
+        if n % d == 0 {
+            small.push(d);
+            if d * d != n {
+                large.push(n / d);
+            }
+        }
+        d += 1;
+    }
+    small.extend(large.into_iter().rev());
+    small
+}
+
+
+/// Whether `p / q` is a root of the polynomial, lowest degree first.
+fn is_root(coefficients: &[i128], p: i128, q: i128) -> bool {
+    let n = coefficients.len() - 1;
+    let value = coefficients.iter().enumerate().try_fold(0i128, |sum, (i, c)| {
+        let term = c.checked_mul(p.checked_pow(i as u32)?)?.checked_mul(q.checked_pow((n - i) as u32)?)?;
+        sum.checked_add(term)
+    });
+    value == Some(0)
+}
+
+
+/// The quotient of the polynomial by `q x - p`, which divides it.
+fn divide(coefficients: &[i128], p: i128, q: i128) -> Vec<i128> {
+    let n = coefficients.len() - 1;
+    let mut quotient = vec![0; n];
+    quotient[n - 1] = coefficients[n] / q;
+    for k in (1..n).rev() {
+        quotient[k - 1] = (coefficients[k] + p * quotient[k]) / q;
+    }
+    quotient
+}
+
+
+/// A rational root `p / q` in lowest terms with `q > 0`, if there is one.
+fn rational_root(coefficients: &[i128]) -> Option<(i128, i128)> {
+    let (constant, lead) = (coefficients[0], coefficients[coefficients.len() - 1]);
+    if constant.abs() > MAX_DIVISOR_SEARCH || lead.abs() > MAX_DIVISOR_SEARCH {
+        return None
+    }
+    let mut candidates: Vec<(i128, i128)> = divisors(constant).into_iter()
+        .flat_map(|p| divisors(lead).into_iter().map(move |q| (p, q)))
+        .filter(|(p, q)| gcd(*p, *q) == 1)
+        .flat_map(|(p, q)| [(-p, q), (p, q)])
+        .collect();
+    candidates.sort_by(|(a, b), (c, d)| (a * d).cmp(&(c * b)));
+    candidates.into_iter().find(|(p, q)| is_root(coefficients, *p, *q))
+}
+
+
+/// The quotient of the polynomial by the quadratic `c + b x + a x^2`, if it divides it.
+fn divide_quadratic(coefficients: &[i128], [c, b, a]: [i128; 3]) -> Option<Vec<i128>> {
+    let n = coefficients.len() - 1;
+    let mut remainder = coefficients.to_vec();
+    let mut quotient = vec![0; n - 1];
+    for k in (0..n - 1).rev() {
+        if remainder[k + 2] % a != 0 {
+            return None
+        }
+        let q = remainder[k + 2] / a;
+        quotient[k] = q;
+        remainder[k + 1] = remainder[k + 1].checked_sub(q.checked_mul(b)?)?;
+        remainder[k] = remainder[k].checked_sub(q.checked_mul(c)?)?;
+    }
+    if remainder[0] == 0 && remainder[1] == 0 {Some(quotient)} else {None}
+}
+
+
+/// A primitive quadratic factor `c + b x + a x^2` of a polynomial without
+/// rational roots, with the quotient. `a` divides the leading coefficient, `c`
+/// the constant, and the values of the quadratic at 1 and -1 divide those of
+/// the polynomial.
+fn quadratic_factor(coefficients: &[i128]) -> Option<([i128; 3], Vec<i128>)> {
+    let (constant, lead) = (coefficients[0], coefficients[coefficients.len() - 1]);
+    let at_one = coefficients.iter().try_fold(0i128, |sum, c| sum.checked_add(*c))?;
+    let at_minus_one = coefficients.iter().enumerate()
+        .try_fold(0i128, |sum, (i, c)| sum.checked_add(if i % 2 == 0 {*c} else {-c}))?;
+    if [constant, lead, at_one, at_minus_one].iter().any(|v| *v == 0 || v.abs() > MAX_DIVISOR_SEARCH) {
+        return None
+    }
+    let (leads, constants, values) = (divisors(lead), divisors(constant), divisors(at_one));
+    if leads.len() * constants.len() * values.len() * 4 > MAX_QUADRATIC_CANDIDATES {
+        return None
+    }
+    for a in &leads {
+        for c in constants.iter().flat_map(|c| [*c, -c]) {
+            for value in values.iter().flat_map(|v| [*v, -v]) {
+                let b = value - a - c;
+                let value_at_minus_one = a - b + c;
+                if value_at_minus_one == 0 || at_minus_one % value_at_minus_one != 0 || gcd(gcd(*a, b), c) != 1 {
+                    continue
+                }
+                if let Some(quotient) = divide_quadratic(coefficients, [c, b, *a]) {
+                    return Some(([c, b, *a], quotient))
+                }
+            }
+        }
+    }
+    None
+}
+
+
+/// `3x^2 - x + 1`, from coefficients lowest degree first.
+fn polynomial_string(coefficients: &[i128], variable: &str) -> String {
+    let ratios: Vec<(i128, i128)> = coefficients.iter().map(|c| (*c, 1)).collect();
+    rational_polynomial_string(&ratios, variable)
+}
+
+
+/// `(1/2)x^2 - x + 3/4`, from coefficients `n / d` lowest degree first.
+fn rational_polynomial_string(coefficients: &[(i128, i128)], variable: &str) -> String {
+    let mut string = String::new();
+    for (degree, (n, d)) in coefficients.iter().enumerate().rev().filter(|(_, (n, _))| *n != 0) {
+        string += match (string.is_empty(), n.is_negative() != d.is_negative()) {
+            (true, true) => "-",
+            (true, false) => "",
+            (false, true) => " - ",
+            (false, false) => " + ",
+        };
+        let c = ratio_string(n.abs(), d.abs());
+        string += &match degree {
+            0 => c,
+            _ if c == "1" => monomial_string(variable, degree),
+            _ if c.contains('/') => format!("({}){}", c, monomial_string(variable, degree)),
+            _ => format!("{}{}", c, monomial_string(variable, degree)),
+        };
+    }
+    if string.is_empty() {"0".to_string()} else {string}
+}
+
+
+fn monomial_string(variable: &str, degree: usize) -> String {
+    if degree == 1 {variable.to_string()} else {format!("{}^{}", variable, degree)}
+}
+
+
+impl Equation {
+    /// The polynomial in the compact form of `factor_string`, such as
+    /// `x^3 + 3x^2 + 3x + 1`, or None when a coefficient is not rational.
+    pub fn polynomial_string(&self) -> Option<String> {
+        if self.expr.iter().any(|term| term.degree < 0) {
+            return None
+        }
+        let variable = self.variable.clone().unwrap_or("x".to_string());
+        let mut coefficients = vec![0.0; self.degree as usize + 1];
+        for term in &self.expr {
+            coefficients[term.degree as usize] += term.coefficient;
+        }
+        let ratios = coefficients.iter().map(|c| to_ratio(*c)).collect::<Option<Vec<_>>>()?;
+        Some(rational_polynomial_string(&ratios, &variable))
+    }
+
+    /// The polynomial as a rational constant times linear factors with rational
+    /// roots, then the quadratic factors of what is left when it has degree 4 or
+    /// more, then the rest. Factors of degree 3 or more of a rest of degree 6 or
+    /// more are not searched, so such a rest may still be reducible.
+    pub fn factor_string(&self) -> Result<String, String> {
+        let variable = self.variable.clone().unwrap_or("x".to_string());
+        let mut coefficients = vec![0.0; self.degree as usize + 1];
+        for term in &self.expr {
+            coefficients[term.degree as usize] += term.coefficient;
+        }
+        if coefficients.iter().all(|c| *c == 0.0) {
+            return Ok("0".to_string())
+        }
+        let mut integers = integer_coefficients(&coefficients)
+            .ok_or("error: factor needs rational coefficients".to_string())?;
+        let (n, d) = to_ratio(coefficients[coefficients.len() - 1] / integers[integers.len() - 1] as f64)
+            .ok_or("error: factor needs rational coefficients".to_string())?;
+
+        let zero_roots = integers.iter().take_while(|c| **c == 0).count();
+        integers.drain(..zero_roots);
+        let mut roots: Vec<((i128, i128), usize)> = Vec::new();
+        while integers.len() > 1 {
+            let Some((p, q)) = rational_root(&integers) else { break };
+            integers = divide(&integers, p, q);
+            match roots.iter_mut().find(|(root, _)| *root == (p, q)) {
+                Some((_, m)) => *m += 1,
+                None => roots.push(((p, q), 1)),
+            }
+        }
+
+        let mut quadratics: Vec<([i128; 3], usize)> = Vec::new();
+        while integers.len() > 4 {
+            let Some((quadratic, quotient)) = quadratic_factor(&integers) else { break };
+            integers = quotient;
+            match quadratics.iter_mut().find(|(q, _)| *q == quadratic) {
+                Some((_, m)) => *m += 1,
+                None => quadratics.push((quadratic, 1)),
+            }
+        }
+        if let Some((_, m)) = quadratics.iter_mut().find(|(q, _)| *q == integers.as_slice()) {
+            *m += 1;
+            integers = vec![1];
+        }
+
+        let mut factors = Vec::new();
+        if zero_roots > 0 {
+            factors.push(monomial_string(&variable, zero_roots));
+        }
+        for ((p, q), m) in roots {
+            let x = if q == 1 {variable.clone()} else {format!("{}{}", q, variable)};
+            let sign = if p < 0 {"+"} else {"-"};
+            let power = if m == 1 {String::new()} else {format!("^{}", m)};
+            factors.push(format!("({} {} {}){}", x, sign, p.abs(), power));
+        }
+        for (quadratic, m) in quadratics {
+            let power = if m == 1 {String::new()} else {format!("^{}", m)};
+            factors.push(format!("({}){}", polynomial_string(&quadratic, &variable), power));
+        }
+        if integers.len() > 1 {
+            factors.push(format!("({})", polynomial_string(&integers, &variable)));
+        }
+
+        let constant = ratio_string(n, d);
+        let string = match (constant.as_str(), factors.as_slice()) {
+            (_, []) => constant,
+            ("1", [f]) if f.starts_with('(') && f.ends_with(')') => f[1..f.len() - 1].to_string(),
+            ("1", _) => factors.concat(),
+            ("-1", _) => format!("-{}", factors.concat()),
+            (c, _) if d != 1 => format!("({}){}", c, factors.concat()),
+            (c, _) => format!("{}{}", c, factors.concat()),
+        };
+        Ok(string)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rational_root_normal() {
+        assert_eq!(rational_root(&[6, -5, 1]), Some((2, 1)));
+        assert_eq!(rational_root(&[-1, 0, 4]), Some((-1, 2)));
+        assert_eq!(rational_root(&[1, 0, 1]), None);
+        assert_eq!(divide(&[6, -5, 1], 2, 1), vec![-3, 1]);
+    }
+
+    #[test]
+    fn quadratic_factor_normal() {
+        assert_eq!(quadratic_factor(&[4, 0, 0, 0, 1]), Some(([2, -2, 1], vec![2, 2, 1])));
+        assert_eq!(quadratic_factor(&[1, 0, 2, 0, 1]), Some(([1, 0, 1], vec![1, 0, 1])));
+        assert_eq!(quadratic_factor(&[2, 0, 0, 0, 1]), None);
+        assert_eq!(divide_quadratic(&[4, 0, 0, 0, 1], [1, 0, 1]), None);
+    }
+
+    #[test]
+    fn polynomial_string_normal() {
+        assert_eq!(polynomial_string(&[1, 0, 1], "x"), "x^2 + 1");
+        assert_eq!(polynomial_string(&[-2, -1, 3], "y"), "3y^2 - y - 2");
+        assert_eq!(rational_polynomial_string(&[(3, 4), (-1, 1), (1, 2)], "x"), "(1/2)x^2 - x + 3/4");
+        assert_eq!(rational_polynomial_string(&[(0, 1)], "x"), "0");
+    }
+}
@@ -0,0 +1,248 @@
+use crate::binary_tree::BinaryTree;
+use crate::parser::Element;
+use crate::operator::Operator;
+use crate::equation::{Equation, Term};
+use crate::num::Num;
+
+
+const MAX_DEGREE: usize = 1000;
+/// Relative size under which a remainder coefficient is treated as zero.
+const REMAINDER_TOLERANCE: f64 = 1e-10;
+
+
+/// Polynomial coefficients, lowest degree first.
+pub type Poly = Vec<f64>;
+
+
+/// Quotient of two polynomials in one variable.
+#[derive(Debug, PartialEq, Clone)]
+struct Fraction {
+    numerator: Poly,
+    denominator: Poly,
+}
+
+
+fn trim(mut p: Poly) -> Poly {
+    while p.len() > 1 && p[p.len() - 1] == 0.0 {
+        p.pop();
+    }
+    p
+}
+
+
+fn is_zero(p: &Poly) -> bool {
+    p.iter().all(|c| *c == 0.0)
+}
+
+
+fn is_constant(p: &Poly) -> bool {
+    p.len() == 1
+}
+
+
+fn add(a: &Poly, b: &Poly, sign: f64) -> Poly {
+    let mut sum = vec![0.0; a.len().max(b.len())];
+    for (i, c) in a.iter().enumerate() {
+        sum[i] += c;
+    }
+    for (i, c) in b.iter().enumerate() {
+        sum[i] += sign * c;
+    }
+    trim(sum)
+}
+
+
+fn mul(a: &Poly, b: &Poly) -> Result<Poly, String> {
+    if a.len() + b.len() - 2 > MAX_DEGREE {
+        return Err("too many terms error".to_string())
+    }
+    let mut product = vec![0.0; a.len() + b.len() - 1];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            product[i + j] += x * y;
+        }
+    }
+    Ok(trim(product))
+}
+
+
+/// Quotient and remainder, with remainder coefficients lost in rounding set to zero.
+fn divrem(a: &Poly, b: &Poly) -> (Poly, Poly) {
+    let scale = a.iter().fold(0.0, |max: f64, c| max.max(c.abs()));
+    let mut remainder = a.clone();
+    if a.len() < b.len() {
+        return (vec![0.0], remainder)
+    }
+    let mut quotient = vec![0.0; a.len() - b.len() + 1];
+    let lead = b[b.len() - 1];
+    for i in (0..quotient.len()).rev() {
+        let q = remainder[i + b.len() - 1] / lead;
+        quotient[i] = q;
+        for (j, c) in b.iter().enumerate() {
+            remainder[i + j] -= q * c;
+        }
+        remainder[i + b.len() - 1] = 0.0;
+    }
+    remainder.iter_mut().for_each(|c| if c.abs() <= REMAINDER_TOLERANCE * scale {*c = 0.0});
+    (trim(quotient), trim(remainder))
+}
+
+
+/// Monic greatest common divisor by Euclid's algorithm.
+fn gcd(a: &Poly, b: &Poly) -> Poly {
+    let (mut a, mut b) = (a.clone(), b.clone());
+    while !is_zero(&b) {
+        let (_, r) = divrem(&a, &b);
+        a = b;
+        b = r;
+    }
+    let lead = a[a.len() - 1];
+    a.iter().map(|c| c / lead).collect()
+}
+
+
+impl Fraction {
+    fn from_poly(numerator: Poly) -> Fraction {
+        Fraction { numerator, denominator: vec![1.0] }
+    }
+
+    fn constant_value(&self) -> Option<f64> {
+        if is_constant(&self.numerator) && is_constant(&self.denominator) {
+            Some(self.numerator[0] / self.denominator[0])
+        } else {
+            None
+        }
+    }
+
+    /// Sum over the least common denominator.
+    fn add(&self, rhs: &Fraction, sign: f64) -> Result<Fraction, String> {
+        let common = gcd(&self.denominator, &rhs.denominator);
+        let (left_factor, _) = divrem(&rhs.denominator, &common);
+        let (right_factor, _) = divrem(&self.denominator, &common);
+        Ok(Fraction {
+            numerator: add(&mul(&self.numerator, &left_factor)?, &mul(&rhs.numerator, &right_factor)?, sign),
+            denominator: mul(&self.denominator, &left_factor)?,
+        })
+    }
+
+    fn mul(&self, rhs: &Fraction) -> Result<Fraction, String> {
+        Ok(Fraction {
+            numerator: mul(&self.numerator, &rhs.numerator)?,
+            denominator: mul(&self.denominator, &rhs.denominator)?,
+        })
+    }
+
+    fn recip(&self, denominators: &mut Vec<Poly>) -> Result<Fraction, String> {
+        if is_zero(&self.numerator) {
+            return Err("The calculation resulted in 'inf'.".to_string())
+        }
+        if !is_constant(&self.numerator) {
+            denominators.push(self.numerator.clone());
+        }
+        Ok(Fraction { numerator: self.denominator.clone(), denominator: self.numerator.clone() })
+    }
+
+    fn pow(&self, exponent: f64, denominators: &mut Vec<Poly>) -> Result<Fraction, String> {
+        if !Num::is_int_value(exponent) {
+            return Err("error: only integers are allowed for exponents".to_string())
+        }
+        let base = if exponent < 0.0 {self.recip(denominators)?} else {self.clone()};
+        let mut power = Fraction::from_poly(vec![1.0]);
+        for _ in 0..exponent.abs() as u32 {
+            power = power.mul(&base)?;
+        }
+        Ok(power)
+    }
+
+    /// `denominators` collects every polynomial the expression divides by.
+    fn from_tree(tree: &BinaryTree<Element>, variable: &mut Option<String>, denominators: &mut Vec<Poly>) -> Result<Fraction, String> {
+        let node_box = match tree {
+            BinaryTree::Empty => return Err("syntax error".to_string()),
+            BinaryTree::NonEmpty(node_box) => node_box,
+        };
+        match &node_box.element {
+            Element::Func(_) => Err("syntax error".to_string()),
+            Element::Dummy => Ok(Fraction::from_poly(vec![0.0])),
+            Element::Num(n) => Ok(Fraction::from_poly(vec![Equation::check_and_get_num_float(n)?])),
+            Element::Variable(v) => {
+                Equation::check_variable(variable, v)?;
+                Ok(Fraction::from_poly(vec![0.0, 1.0]))
+            },
+            Element::Operator(op) => {
+                if let Operator::Paren = op {
+                    return Self::from_tree(tree.left().unwrap(), variable, denominators)
+                }
+                let lhs = Self::from_tree(tree.left().unwrap(), variable, denominators)?;
+                let rhs = Self::from_tree(tree.right().unwrap(), variable, denominators)?;
+                match op {
+                    Operator::Plus => lhs.add(&rhs, 1.0),
+                    Operator::Minus => lhs.add(&rhs, -1.0),
+                    Operator::Mul => lhs.mul(&rhs),
+                    Operator::Div => lhs.mul(&rhs.recip(denominators)?),
+                    Operator::Pow => match rhs.constant_value() {
+                        Some(exponent) => lhs.pow(exponent, denominators),
+                        None => Err("error: only integers are allowed for exponents".to_string()),
+                    },
+                    Operator::Rem => match (lhs.constant_value(), rhs.constant_value()) {
+                        (Some(a), Some(b)) => Ok(Fraction::from_poly(vec![a.rem_euclid(b)])),
+                        _ => Err("error: variable remainders cannot be calculated".to_string()),
+                    },
+                    Operator::MatrixMul => Err("Unsupported matrix product error.".to_string()),
+                    Operator::MatrixDiv | Operator::MatrixLeftDiv
+                        => Err("Unsupported matrix division error.".to_string()),
+                    _ => Err("syntax error".to_string()),
+                }
+            },
+        }
+    }
+}
+
+
+impl Equation {
+    /// Equation with rational functions on each side, multiplied through by the
+    /// least common denominator. Returns the polynomials divided by in `lhs` and `rhs`.
+    pub fn make_rational_equation(&mut self, lhs: &BinaryTree<Element>, rhs: &BinaryTree<Element>) -> Result<Vec<Poly>, String> {
+        let mut variable = None;
+        let mut denominators = Vec::new();
+        let lhs = Fraction::from_tree(lhs, &mut variable, &mut denominators)?;
+        let rhs = Fraction::from_tree(rhs, &mut variable, &mut denominators)?;
+        let fraction = lhs.add(&rhs, -1.0)?;
+        if let Some(c) = fraction.numerator.iter().find(|c| !c.is_finite()) {
+            return Err(format!("The calculation resulted in '{}'.", c))
+        }
+        self.expr = fraction.numerator.iter().enumerate()
+            .filter(|(_, c)| **c != 0.0)
+            .map(|(degree, coefficient)| Term { coefficient: *coefficient, degree: degree as i64 })
+            .collect();
+        if self.expr.is_empty() {
+            self.expr.push(Term { coefficient: 0.0, degree: 0 })
+        }
+        self.degree = self.expr.last().unwrap().degree;
+        self.variable = variable;
+        self.exact_coefficients = None;
+        Ok(denominators)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gcd_normal() {
+        // (x - 1)(x + 2) and (x - 1)(x - 3)
+        assert_eq!(gcd(&vec![-2.0, 1.0, 1.0], &vec![3.0, -4.0, 1.0]), vec![-1.0, 1.0]);
+        assert_eq!(gcd(&vec![0.0, 0.0, 2.0], &vec![0.0, 4.0]), vec![0.0, 1.0]);
+        assert_eq!(gcd(&vec![1.0, 1.0], &vec![2.0]), vec![1.0]);
+    }
+
+    #[test]
+    fn add_least_common_denominator() -> Result<(), String> {
+        // 1/x + 1/x^2 = (x + 1)/x^2
+        let lhs = Fraction { numerator: vec![1.0], denominator: vec![0.0, 1.0] };
+        let rhs = Fraction { numerator: vec![1.0], denominator: vec![0.0, 0.0, 1.0] };
+        assert_eq!(lhs.add(&rhs, 1.0)?, Fraction { numerator: vec![1.0, 1.0], denominator: vec![0.0, 0.0, 1.0] });
+        Ok(())
+    }
+}
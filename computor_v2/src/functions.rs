@@ -1,46 +1,166 @@
-use crate::binary_tree::BinaryTree;
-use crate::parser::Element;
-use crate::num::Num;
-use crate::operator::Operator;
-
-
-pub fn make_builtin_func_box(func_name: String) -> Box<(BinaryTree<Element>, String)> {
-    Box::new((BinaryTree::from_element_and_tree(
-        Element::Func(Box::new(func_name)),
-        BinaryTree::from_element_and_tree(
-            Element::Operator(Operator::Paren),
-            BinaryTree::from_element(Element::Variable(Box::new("x".to_string()))),
-            BinaryTree::from_element(Element::Operator(Operator::RParen))),
-        BinaryTree::from_element(Element::Operator(Operator::RParen))
-    ), "x".to_string()))
-}
-
-
-pub fn builtin_func(func_name: String, num: &Num) -> Result<Num, String> {
-    let n = if func_name == "exp" {
-        exp(num)?
-    } else if func_name == "sqrt" {
-        sqrt(num)?
-    } else if func_name == "abs" {
-        abs(num)?
-    } else if func_name == "sin" {
-        sin(num)?
-    } else if func_name == "cos" {
-        cos(num)?
-    } else if func_name == "tan" {
-        tan(num)?
-    } else {
-        return Err(format!("error: unsupported {}", func_name))
-    };
+use std::cmp::Ordering;
+use std::f64::consts::PI;
+use std::fmt;
+
+use crate::bigint::BigInt;
+use crate::num::{Num, MAX_BITS};
+use crate::rational::Rational;
+
+
+/// A native function that can be called from expressions.
+///
+/// Implement it and pass it to `DataBase::register_builtin` to add a function.
+///
+/// ```
+/// use computor_v2::{Builtin, DataBase, Num};
+/// use computor_v2::lexer::Lexer;
+/// use computor_v2::parser::Parser;
+///
+/// #[derive(Debug)]
+/// struct Double;
+///
+/// impl Builtin for Double {
+///     fn name(&self) -> &str { "double" }
+///     fn arity(&self) -> usize { 1 }
+///     fn doc(&self) -> &str { "twice the argument" }
+///     fn call(&self, args: &[Num]) -> Result<Num, String> {
+///         args[0].supported_add(&args[0])
+///     }
+/// }
+///
+/// let mut data_base = DataBase::new();
+/// data_base.register_builtin(Box::new(Double))?;
+/// let mut parser = Parser::new(Lexer::new("double(21)").make_token_vec()?);
+/// let mut tree = parser.make_tree(&data_base)?;
+/// assert_eq!(parser.calculation(&mut tree, &data_base, None)?, Some(Num::from_i64(42)));
+/// # Ok::<(), String>(())
+/// ```
+pub trait Builtin: fmt::Debug {
+    fn name(&self) -> &str;
+    fn arity(&self) -> usize;
+    fn doc(&self) -> &str;
+    /// Called with exactly `arity()` arguments.
+    fn call(&self, args: &[Num]) -> Result<Num, String>;
+}
+
+
+#[derive(Debug)]
+pub struct NativeFunc {
+    name: String,
+    arity: usize,
+    doc: String,
+    func: fn(&[Num]) -> Result<Num, String>,
+}
+
+
+impl NativeFunc {
+    pub fn new(name: &str, arity: usize, doc: &str, func: fn(&[Num]) -> Result<Num, String>) -> NativeFunc {
+        NativeFunc { name: name.to_string(), arity, doc: doc.to_string(), func }
+    }
+}
+
+
+impl Builtin for NativeFunc {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn arity(&self) -> usize {
+        self.arity
+    }
+
+    fn doc(&self) -> &str {
+        &self.doc
+    }
+
+    fn call(&self, args: &[Num]) -> Result<Num, String> {
+        (self.func)(args)
+    }
+}
+
+
+fn native(name: &str, arity: usize, doc: &str, func: fn(&[Num]) -> Result<Num, String>) -> Box<dyn Builtin> {
+    Box::new(NativeFunc::new(name, arity, doc, func))
+}
+
+
+/// The functions every `DataBase` starts with.
+pub fn default_builtins() -> Vec<Box<dyn Builtin>> {
+    vec![
+        native("exp", 1, "exponential function", |a| exp(&a[0])),
+        native("sqrt", 1, "principal square root", |a| sqrt(&a[0])),
+        native("abs", 1, "absolute value or modulus", |a| abs(&a[0])),
+        native("sin", 1, "sine", |a| sin(&a[0])),
+        native("cos", 1, "cosine", |a| cos(&a[0])),
+        native("tan", 1, "tangent", |a| tan(&a[0])),
+        native("re", 1, "real part", |a| re(&a[0])),
+        native("im", 1, "imaginary part", |a| im(&a[0])),
+        native("conj", 1, "complex conjugate", |a| conj(&a[0])),
+        native("arg", 1, "argument of a complex number", |a| arg(&a[0])),
+        native("polar", 2, "complex number from modulus and argument", |a| polar(&a[0], &a[1])),
+        native("det", 1, "matrix determinant", |a| det(&a[0])),
+        native("inv", 1, "matrix inverse", |a| inv(&a[0])),
+        native("ln", 1, "natural logarithm", |a| ln(&a[0])),
+        native("log", 2, "logarithm of the second argument to the base of the first", |a| log(&a[0], &a[1])),
+        native("log10", 1, "common logarithm", |a| log(&Num::from_i64(10), &a[0])),
+        native("asin", 1, "arcsine", |a| real_func(&a[0], "asin", f64::asin)),
+        native("acos", 1, "arccosine", |a| real_func(&a[0], "acos", f64::acos)),
+        native("atan", 1, "arctangent", |a| real_func(&a[0], "atan", f64::atan)),
+        native("atan2", 2, "angle of the point (x, y), called as atan2(y, x)", |a| real_func2(&a[0], &a[1], "atan2", f64::atan2)),
+        native("sinh", 1, "hyperbolic sine", |a| sinh(&a[0])),
+        native("cosh", 1, "hyperbolic cosine", |a| cosh(&a[0])),
+        native("tanh", 1, "hyperbolic tangent", |a| tanh(&a[0])),
+        native("floor", 1, "largest integer not greater than the argument", |a| floor(&a[0], "floor")),
+        native("ceil", 1, "smallest integer not less than the argument", |a| ceil(&a[0], "ceil")),
+        native("round", 1, "nearest integer, halves away from zero", |a| round(&a[0], "round")),
+        native("sign", 1, "sign of a real number", |a| sign(&a[0], "sign")),
+        native("min", 2, "smaller of two real numbers", |a| min_max(&a[0], &a[1], "min", Ordering::Less)),
+        native("max", 2, "larger of two real numbers", |a| min_max(&a[0], &a[1], "max", Ordering::Greater)),
+        native("factorial", 1, "factorial of a non-negative integer", |a| factorial(&a[0])),
+        native("gamma", 1, "gamma function", |a| gamma(&a[0], "gamma")),
+    ]
+}
+
+
+/// Call `builtin`, checking the number of arguments and the result.
+pub fn call_builtin(builtin: &dyn Builtin, args: &[Num]) -> Result<Num, String> {
+    if args.len() != builtin.arity() {
+        return Err(format!("error: {} takes {} argument(s)", builtin.name(), builtin.arity()))
+    }
+    let n = builtin.call(args)?;
     n.checked_value()?;
     Ok(n)
 }
 
 
+fn complex_parts(num: &Num) -> (f64, f64) {
+    match num {
+        Num::Complex(c) => (c.r.float_value(), c.z.float_value()),
+        _ => (num.float_value(), 0.0),
+    }
+}
+
+
+/// tanh(a + bi), with numerator and denominator divided by cosh(2a), written
+/// with exp(-2|a|) so that it does not overflow for large |a|.
+fn complex_tanh(a: f64, b: f64) -> (f64, f64) {
+    let e = (-2.0 * a.abs()).exp();
+    let sech = 2.0 * e / (1.0 + e * e);
+    let denominator = 1.0 + (2.0 * b).cos() * sech;
+    ((2.0 * a).tanh() / denominator, (2.0 * b).sin() * sech / denominator)
+}
+
+
 fn exp(num: &Num) -> Result<Num, String> {
     match num {
         Num::Float(n) => Ok(Num::Float(n.exp())),
-        _ => Err(format!("error: unsupported non float exp"))
+        Num::Rational(q) => Ok(Num::Float(q.to_f64().exp())),
+        Num::Complex(_) => {
+            let (a, b) = complex_parts(num);
+            let r = a.exp();
+            Ok(Num::from_two_float_trimmed(r * b.cos(), r * b.sin()))
+        },
+        Num::Matrix(m) => Ok(Num::Matrix(Box::new(m.try_apply_all_terms(exp)?))),
     }
 }
 
@@ -54,7 +174,26 @@ fn sqrt(num: &Num) -> Result<Num, String> {
                 Ok(Num::from_two_float(0.0, n.abs().sqrt()))
             }
         }
-        _ => Err(format!("error: unsupported non float sqrt"))
+        Num::Rational(q) => {
+            match q.abs().exact_root(2) {
+                None => sqrt(&Num::Float(q.to_f64())),
+                Some(root) => {
+                    if q.is_negative() {
+                        Ok(Num::from_two_num(Num::zero(), Num::from_rational(root)))
+                    } else {
+                        Ok(Num::from_rational(root))
+                    }
+                },
+            }
+        }
+        Num::Complex(_) => {
+            let (a, b) = complex_parts(num);
+            let modulus = a.hypot(b);
+            let r = ((modulus + a) / 2.0).sqrt();
+            let z = ((modulus - a) / 2.0).sqrt();
+            Ok(Num::from_two_float_trimmed(r, if b.is_sign_negative() {-z} else {z}))
+        },
+        Num::Matrix(m) => Ok(Num::Matrix(Box::new(m.try_apply_all_terms(sqrt)?))),
     }
 }
 
@@ -62,7 +201,15 @@ fn sqrt(num: &Num) -> Result<Num, String> {
 fn abs(num: &Num) -> Result<Num, String> {
     match num {
         Num::Float(n) => Ok(Num::Float(n.abs())),
-        _ => Err(format!("error: unsupported non float abs"))
+        Num::Rational(q) => Ok(Num::from_rational(q.abs())),
+        Num::Complex(c) => {
+            let square = c.r.supported_mul(&c.r)?.supported_add(&c.z.supported_mul(&c.z)?)?;
+            match &square {
+                Num::Rational(_) => sqrt(&square),
+                _ => Ok(Num::Float(num.magnitude())),
+            }
+        },
+        Num::Matrix(m) => Ok(Num::Matrix(Box::new(m.try_apply_all_terms(abs)?))),
     }
 }
 
@@ -70,7 +217,12 @@ fn abs(num: &Num) -> Result<Num, String> {
 fn sin(num: &Num) -> Result<Num, String> {
     match num {
         Num::Float(n) => Ok(Num::Float(n.sin())),
-        _ => Err(format!("error: unsupported non float sin"))
+        Num::Rational(q) => Ok(Num::Float(q.to_f64().sin())),
+        Num::Complex(_) => {
+            let (a, b) = complex_parts(num);
+            Ok(Num::from_two_float_trimmed(a.sin() * b.cosh(), a.cos() * b.sinh()))
+        },
+        Num::Matrix(m) => Ok(Num::Matrix(Box::new(m.try_apply_all_terms(sin)?))),
     }
 }
 
@@ -78,7 +230,12 @@ fn sin(num: &Num) -> Result<Num, String> {
 fn cos(num: &Num) -> Result<Num, String> {
     match num {
         Num::Float(n) => Ok(Num::Float(n.cos())),
-        _ => Err(format!("error: unsupported non float cos"))
+        Num::Rational(q) => Ok(Num::Float(q.to_f64().cos())),
+        Num::Complex(_) => {
+            let (a, b) = complex_parts(num);
+            Ok(Num::from_two_float_trimmed(a.cos() * b.cosh(), -a.sin() * b.sinh()))
+        },
+        Num::Matrix(m) => Ok(Num::Matrix(Box::new(m.try_apply_all_terms(cos)?))),
     }
 }
 
@@ -86,6 +243,290 @@ fn cos(num: &Num) -> Result<Num, String> {
 fn tan(num: &Num) -> Result<Num, String> {
     match num {
         Num::Float(n) => Ok(Num::Float(n.tan())),
-        _ => Err(format!("error: unsupported non float tan"))
+        Num::Rational(q) => Ok(Num::Float(q.to_f64().tan())),
+        Num::Complex(_) => {
+            // tan(a + bi) = -i tanh(-b + ai)
+            let (a, b) = complex_parts(num);
+            let (r, z) = complex_tanh(-b, a);
+            Ok(Num::from_two_float_trimmed(z, -r))
+        },
+        Num::Matrix(m) => Ok(Num::Matrix(Box::new(m.try_apply_all_terms(tan)?))),
+    }
+}
+
+
+fn re(num: &Num) -> Result<Num, String> {
+    match num {
+        Num::Float(_) | Num::Rational(_) => Ok(num.clone()),
+        Num::Complex(c) => Ok(c.r.clone()),
+        Num::Matrix(m) => Ok(Num::Matrix(Box::new(m.try_apply_all_terms(re)?))),
+    }
+}
+
+
+fn im(num: &Num) -> Result<Num, String> {
+    match num {
+        Num::Float(_) => Ok(Num::Float(0.0)),
+        Num::Rational(_) => Ok(Num::zero()),
+        Num::Complex(c) => Ok(c.z.clone()),
+        Num::Matrix(m) => Ok(Num::Matrix(Box::new(m.try_apply_all_terms(im)?))),
+    }
+}
+
+
+fn conj(num: &Num) -> Result<Num, String> {
+    match num {
+        Num::Float(_) | Num::Rational(_) => Ok(num.clone()),
+        Num::Complex(c) => Ok(Num::from_two_num(c.r.clone(), c.z.reverse_sign())),
+        Num::Matrix(m) => Ok(Num::Matrix(Box::new(m.try_apply_all_terms(conj)?))),
+    }
+}
+
+
+fn arg(num: &Num) -> Result<Num, String> {
+    match num {
+        Num::Matrix(m) => Ok(Num::Matrix(Box::new(m.try_apply_all_terms(arg)?))),
+        _ => {
+            let (a, b) = complex_parts(num);
+            Ok(Num::Float(b.atan2(a)))
+        },
+    }
+}
+
+
+fn polar(r: &Num, theta: &Num) -> Result<Num, String> {
+    if !r.is_real() || !theta.is_real() {
+        return Err("error: polar requires real arguments".to_string())
+    }
+    let (r, theta) = (r.float_value(), theta.float_value());
+    Ok(Num::from_two_float_trimmed(r * theta.cos(), r * theta.sin()))
+}
+
+
+fn det(num: &Num) -> Result<Num, String> {
+    match num {
+        Num::Matrix(m) => m.determinant(),
+        _ => Err("error: unsupported non matrix det".to_string())
+    }
+}
+
+
+fn inv(num: &Num) -> Result<Num, String> {
+    match num {
+        Num::Matrix(m) => Ok(Num::Matrix(Box::new(m.inverse()?))),
+        _ => Err("error: unsupported non matrix inv".to_string())
+    }
+}
+
+
+/// Apply a real function, element-wise on matrices.
+fn real_func(num: &Num, func_name: &str, f: fn(f64) -> f64) -> Result<Num, String> {
+    match num {
+        Num::Float(_) | Num::Rational(_) => Ok(Num::Float(f(num.float_value()))),
+        Num::Complex(_) => Err(format!("error: {} requires real arguments", func_name)),
+        Num::Matrix(m) => Ok(Num::Matrix(Box::new(m.try_apply_all_terms(|n| real_func(n, func_name, f))?))),
+    }
+}
+
+
+fn real_func2(lhs: &Num, rhs: &Num, func_name: &str, f: fn(f64, f64) -> f64) -> Result<Num, String> {
+    if !lhs.is_real() || !rhs.is_real() {
+        return Err(format!("error: {} requires real arguments", func_name))
+    }
+    Ok(Num::Float(f(lhs.float_value(), rhs.float_value())))
+}
+
+
+fn ln(num: &Num) -> Result<Num, String> {
+    match num {
+        Num::Matrix(m) => Ok(Num::Matrix(Box::new(m.try_apply_all_terms(ln)?))),
+        _ if num.is_real() && !num.is_sign_negative() => Ok(Num::Float(num.float_value().ln())),
+        _ => {
+            let (a, b) = complex_parts(num);
+            Ok(Num::from_two_float_trimmed(a.hypot(b).ln(), b.atan2(a)))
+        },
+    }
+}
+
+
+/// Logarithm of `num` to `base`. Exact when `num` is an integer power of a rational `base`.
+fn log(base: &Num, num: &Num) -> Result<Num, String> {
+    if base.is_matrix() {
+        return Err("error: unsupported matrix log base".to_string())
+    }
+    let value = ln(num)?.supported_div(&ln(base)?)?;
+    if let (Num::Rational(_), Num::Rational(_), Num::Float(v)) = (base, num, &value) {
+        let exp = Num::from_i64(v.round() as i64);
+        if v.is_finite() && base.supported_pow(&exp)? == *num {
+            return Ok(exp)
+        }
+    }
+    Ok(value)
+}
+
+
+fn sinh(num: &Num) -> Result<Num, String> {
+    match num {
+        Num::Float(_) | Num::Rational(_) => Ok(Num::Float(num.float_value().sinh())),
+        Num::Complex(_) => {
+            let (a, b) = complex_parts(num);
+            Ok(Num::from_two_float_trimmed(a.sinh() * b.cos(), a.cosh() * b.sin()))
+        },
+        Num::Matrix(m) => Ok(Num::Matrix(Box::new(m.try_apply_all_terms(sinh)?))),
+    }
+}
+
+
+fn cosh(num: &Num) -> Result<Num, String> {
+    match num {
+        Num::Float(_) | Num::Rational(_) => Ok(Num::Float(num.float_value().cosh())),
+        Num::Complex(_) => {
+            let (a, b) = complex_parts(num);
+            Ok(Num::from_two_float_trimmed(a.cosh() * b.cos(), a.sinh() * b.sin()))
+        },
+        Num::Matrix(m) => Ok(Num::Matrix(Box::new(m.try_apply_all_terms(cosh)?))),
+    }
+}
+
+
+fn tanh(num: &Num) -> Result<Num, String> {
+    match num {
+        Num::Float(_) | Num::Rational(_) => Ok(Num::Float(num.float_value().tanh())),
+        Num::Complex(_) => {
+            let (a, b) = complex_parts(num);
+            let (r, z) = complex_tanh(a, b);
+            Ok(Num::from_two_float_trimmed(r, z))
+        },
+        Num::Matrix(m) => Ok(Num::Matrix(Box::new(m.try_apply_all_terms(tanh)?))),
+    }
+}
+
+
+fn floor(num: &Num, func_name: &str) -> Result<Num, String> {
+    match num {
+        Num::Rational(q) => Ok(Num::from_rational(Rational::from_bigint(q.floor()))),
+        Num::Matrix(m) => Ok(Num::Matrix(Box::new(m.try_apply_all_terms(|n| floor(n, func_name))?))),
+        _ => real_func(num, func_name, f64::floor),
+    }
+}
+
+
+fn ceil(num: &Num, func_name: &str) -> Result<Num, String> {
+    match num {
+        Num::Rational(q) => Ok(Num::from_rational(Rational::from_bigint(-&q.neg().floor()))),
+        Num::Matrix(m) => Ok(Num::Matrix(Box::new(m.try_apply_all_terms(|n| ceil(n, func_name))?))),
+        _ => real_func(num, func_name, f64::ceil),
+    }
+}
+
+
+/// Round half away from zero, like `f64::round`.
+fn round(num: &Num, func_name: &str) -> Result<Num, String> {
+    match num {
+        Num::Rational(q) => {
+            let half = Rational::new(BigInt::one(), BigInt::from_i64(2)).unwrap();
+            let value = Rational::from_bigint(q.abs().add(&half).floor());
+            Ok(Num::from_rational(if q.is_negative() {value.neg()} else {value}))
+        },
+        Num::Matrix(m) => Ok(Num::Matrix(Box::new(m.try_apply_all_terms(|n| round(n, func_name))?))),
+        _ => real_func(num, func_name, f64::round),
+    }
+}
+
+
+fn sign(num: &Num, func_name: &str) -> Result<Num, String> {
+    match num {
+        Num::Rational(q) => Ok(Num::from_i64(if q.is_zero() {0} else if q.is_negative() {-1} else {1})),
+        Num::Matrix(m) => Ok(Num::Matrix(Box::new(m.try_apply_all_terms(|n| sign(n, func_name))?))),
+        _ => real_func(num, func_name, |n| if n == 0.0 {0.0} else {n.signum()}),
+    }
+}
+
+
+/// The argument for which the comparison gives `order`, exact values kept as they are.
+fn min_max(lhs: &Num, rhs: &Num, func_name: &str, order: Ordering) -> Result<Num, String> {
+    let ordering = match (lhs, rhs) {
+        (Num::Rational(l), Num::Rational(r)) => Some(l.cmp(r)),
+        (l, r) if l.is_real() && r.is_real() => l.float_value().partial_cmp(&r.float_value()),
+        _ => return Err(format!("error: {} requires real arguments", func_name)),
+    };
+    match ordering {
+        Some(o) if o == order => Ok(lhs.clone()),
+        _ => Ok(rhs.clone()),
+    }
+}
+
+
+/// 171! overflows f64.
+const MAX_FLOAT_FACTORIAL: i64 = 170;
+
+
+fn factorial(num: &Num) -> Result<Num, String> {
+    if let Num::Matrix(m) = num {
+        return Ok(Num::Matrix(Box::new(m.try_apply_all_terms(factorial)?)))
+    }
+    let n = match num.to_int_value() {
+        Some(n) if n >= 0 => n,
+        _ => return Err("error: factorial requires a non-negative integer".to_string()),
+    };
+    match num {
+        Num::Rational(_) => {
+            let mut value = BigInt::one();
+            for i in 2..=n {
+                value = &value * &BigInt::from_i64(i);
+                if value.bits() > MAX_BITS {
+                    return Ok(Num::Float(f64::INFINITY))
+                }
+            }
+            Ok(Num::from_rational(Rational::from_bigint(value)))
+        },
+        _ if n > MAX_FLOAT_FACTORIAL => Ok(Num::Float(f64::INFINITY)),
+        _ => Ok(Num::Float((2..=n).fold(1.0, |acc, i| acc * i as f64))),
+    }
+}
+
+
+fn gamma(num: &Num, func_name: &str) -> Result<Num, String> {
+    match num.to_int_value() {
+        Some(n) if n <= 0 => Ok(Num::Float(f64::NAN)),
+        Some(n) => factorial(&match num {
+            Num::Rational(_) => Num::from_i64(n - 1),
+            _ => Num::Float((n - 1) as f64),
+        }),
+        None => match num {
+            Num::Matrix(m) => Ok(Num::Matrix(Box::new(m.try_apply_all_terms(|n| gamma(n, func_name))?))),
+            _ => real_func(num, func_name, gamma_float),
+        },
+    }
+}
+
+
+const LANCZOS_G: f64 = 7.0;
+const LANCZOS_COEFFICIENTS: [f64; 9] = [
+    0.999_999_999_999_809_9,
+    676.520_368_121_885_1,
+    -1_259.139_216_722_402_8,
+    771.323_428_777_653_1,
+    -176.615_029_162_140_6,
+    12.507_343_278_686_905,
+    -0.138_571_095_265_720_12,
+    9.984_369_578_019_572e-6,
+    1.505_632_735_149_311_6e-7,
+];
+
+
+/// Lanczos approximation, with the reflection formula below 1/2.
+fn gamma_float(x: f64) -> f64 {
+    if x > (MAX_FLOAT_FACTORIAL + 2) as f64 {
+        return f64::INFINITY
+    }
+    if x < 0.5 {
+        return PI / ((PI * x).sin() * gamma_float(1.0 - x))
     }
+    let x = x - 1.0;
+    let sum = LANCZOS_COEFFICIENTS[1..].iter().enumerate()
+        .fold(LANCZOS_COEFFICIENTS[0], |acc, (i, c)| acc + c / (x + i as f64 + 1.0));
+    let t = x + LANCZOS_G + 0.5;
+    let half_pow = t.powf((x + 0.5) / 2.0);
+    (2.0 * PI).sqrt() * half_pow * (-t).exp() * half_pow * sum
 }
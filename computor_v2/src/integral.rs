@@ -0,0 +1,312 @@
+use crate::binary_tree::BinaryTree;
+use crate::data_base::DataBase;
+use crate::expression::Expr;
+use crate::num::Num;
+use crate::parser::{Parser, Element};
+
+
+/// Antiderivative of `expr` with respect to `variable`, before simplification.
+pub fn antiderivative(expr: &Expr, variable: &str) -> Result<Expr, String> {
+    let x = || Expr::var(variable);
+    if !expr.contains_variable(variable) {
+        return Ok(Expr::mul(expr.clone(), x()))
+    }
+    if let Some(coefficients) = expr.polynomial(variable) {
+        let mut integrated = vec![Num::zero()];
+        for (degree, c) in coefficients.iter().enumerate() {
+            integrated.push(c.supported_div(&Num::from_i64(degree as i64 + 1))?);
+        }
+        return Ok(Expr::from_polynomial(&integrated, variable))
+    }
+    let error = || Err(format!("error: cannot integrate {}", Parser::print_tree(&expr.to_tree()).unwrap_or_default()));
+    match expr {
+        Expr::Neg(a) => Ok(Expr::neg(antiderivative(a, variable)?)),
+        Expr::Add(a, b) => Ok(Expr::add(antiderivative(a, variable)?, antiderivative(b, variable)?)),
+        Expr::Sub(a, b) => Ok(Expr::sub(antiderivative(a, variable)?, antiderivative(b, variable)?)),
+        Expr::Mul(a, b) if !a.contains_variable(variable) => Ok(Expr::mul(*a.clone(), antiderivative(b, variable)?)),
+        Expr::Mul(a, b) if !b.contains_variable(variable) => Ok(Expr::mul(antiderivative(a, variable)?, *b.clone())),
+        Expr::Div(a, b) if !b.contains_variable(variable) => Ok(Expr::div(antiderivative(a, variable)?, *b.clone())),
+        Expr::Div(a, b) if !a.contains_variable(variable) => {
+            match linear_slope(b, variable) {
+                Some(p) => Ok(Expr::mul(Expr::div(*a.clone(), p), ln_abs(*b.clone()))),
+                None => error(),
+            }
+        },
+        Expr::Pow(a, b) if !b.contains_variable(variable) => {
+            let p = match linear_slope(a, variable) {
+                Some(p) => p,
+                None => return error(),
+            };
+            if b.polynomial(variable).is_some_and(|c| c[0] == Num::from_i64(-1)) {
+                return Ok(Expr::div(ln_abs(*a.clone()), p))
+            }
+            let exp = Expr::add(*b.clone(), Expr::from_i64(1));
+            Ok(Expr::div(Expr::pow(*a.clone(), exp.clone()), Expr::mul(exp, p)))
+        },
+        Expr::Pow(a, b) if !a.contains_variable(variable) => {
+            match linear_slope(b, variable) {
+                Some(p) => Ok(Expr::div(expr.clone(), Expr::mul(p, Expr::func("ln", *a.clone())))),
+                None => error(),
+            }
+        },
+        Expr::Func(name, args) if args.len() == 1 => {
+            let u = args[0].clone();
+            let p = match linear_slope(&u, variable) {
+                Some(p) => p,
+                None => return error(),
+            };
+            let outer = match name.as_str() {
+                "exp" => Expr::func("exp", u),
+                "sin" => Expr::neg(Expr::func("cos", u)),
+                "cos" => Expr::func("sin", u),
+                "sinh" => Expr::func("cosh", u),
+                "cosh" => Expr::func("sinh", u),
+                _ => return error(),
+            };
+            Ok(Expr::div(outer, p))
+        },
+        _ => error(),
+    }
+}
+
+
+fn ln_abs(u: Expr) -> Expr {
+    Expr::func("ln", Expr::func("abs", u))
+}
+
+
+/// The slope `p` if `expr` is `p * variable + q` with `p` non zero.
+fn linear_slope(expr: &Expr, variable: &str) -> Option<Expr> {
+    match expr.polynomial(variable)?.as_slice() {
+        [_, p] => Some(Expr::Num(p.clone())),
+        _ => None,
+    }
+}
+
+
+/// Simplified antiderivative of the function `name` with respect to its first parameter,
+/// together with the parameters of the function.
+pub fn antiderivative_func(data_base: &DataBase, name: &String) -> Result<(Expr, Vec<String>), String> {
+    let (expr, variables) = match data_base.get_func(name) {
+        Some(f) => (Expr::from_tree(&f.0)?, f.1.clone()),
+        None => match data_base.get_builtin_func(name) {
+            Some(b) if b.arity() == 1 => (Expr::func(&name.to_lowercase(), Expr::var("x")), vec!["x".to_string()]),
+            _ => return Err(format!("{}: error undefined function", name)),
+        },
+    };
+    Ok((antiderivative(&expr, &variables[0])?.simplify(), variables))
+}
+
+
+/// Definite integral of the function `name` over its first parameter from `a` to `b`.
+pub fn definite_integral(data_base: &DataBase, name: &String, a: &Num, b: &Num) -> Result<Num, String> {
+    let (expr, variables) = antiderivative_func(data_base, name)?;
+    if variables.len() != 1 {
+        return Err(format!("error: {} must take 1 argument", name))
+    }
+    let integrand = match data_base.get_func(name) {
+        Some(f) => Expr::from_tree(&f.0)?,
+        None => Expr::func(&name.to_lowercase(), Expr::var(&variables[0])),
+    };
+    let mut poles = Vec::new();
+    push_poles(&integrand, &variables[0], &mut poles);
+    let (low, high) = (a.float_value().min(b.float_value()), a.float_value().max(b.float_value()));
+    if let Some(pole) = poles.iter().find(|p| p.is_real() && low <= p.float_value() && p.float_value() <= high) {
+        return Err(format!("error: {} is not defined at {} = {}", name, variables[0], pole))
+    }
+    let upper = evaluate(&expr, &variables[0], b, data_base)?;
+    let lower = evaluate(&expr, &variables[0], a, data_base)?;
+    let value = upper.supported_sub(&lower)?;
+    value.checked_value()?;
+    Ok(value)
+}
+
+
+const SIMPSON_TOLERANCE: f64 = 1e-10;
+const SIMPSON_MAX_DEPTH: usize = 50;
+const SIMPSON_MAX_EVALUATIONS: usize = 200_000;
+
+
+/// Integrand evaluated through the parser, counting the evaluations.
+struct Integrand<'a> {
+    name: &'a String,
+    tree: BinaryTree<Element>,
+    variable: String,
+    data_base: &'a DataBase,
+    evaluations: usize,
+}
+
+
+impl Integrand<'_> {
+    fn call(&mut self, x: f64) -> Result<f64, String> {
+        self.evaluations += 1;
+        if self.evaluations > SIMPSON_MAX_EVALUATIONS {
+            return Err("error: nintegrate did not converge".to_string())
+        }
+        let n = Parser::calculation_at(&self.tree, &self.variable, &Num::Float(x), self.data_base)
+            .map_err(|e| format!("error: {} cannot be evaluated at {} = {}: {}", self.name, self.variable, x, e))?;
+        if !n.is_real() {
+            return Err(format!("error: {} is not real at {} = {}", self.name, self.variable, x))
+        }
+        Ok(n.float_value())
+    }
+}
+
+
+/// Integral of the function `name` from `a` to `b` by adaptive Simpson quadrature,
+/// with an estimate of the absolute error.
+pub fn numeric_integral(data_base: &DataBase, name: &String, a: f64, b: f64) -> Result<(f64, f64), String> {
+    let (tree, variables) = match data_base.get_func(name) {
+        Some(f) => (f.0.clone(), f.1.clone()),
+        None => match data_base.get_builtin_func(name) {
+            Some(b) if b.arity() == 1 => (Expr::func(&name.to_lowercase(), Expr::var("x")).to_tree(), vec!["x".to_string()]),
+            _ => return Err(format!("{}: error undefined function", name)),
+        },
+    };
+    if variables.len() != 1 {
+        return Err(format!("error: {} must take 1 argument", name))
+    }
+    if !a.is_finite() || !b.is_finite() {
+        return Err("error: the bounds must be finite".to_string())
+    }
+    let mut f = Integrand { name, tree, variable: variables[0].clone(), data_base, evaluations: 0 };
+    let (fa, fm, fb) = (f.call(a)?, f.call((a + b) / 2.0)?, f.call(b)?);
+    let whole = (b - a) / 6.0 * (fa + 4.0 * fm + fb);
+    let tolerance = SIMPSON_TOLERANCE * whole.abs().max(1.0);
+    adaptive_simpson(&mut f, (a, b), (fa, fm, fb), whole, tolerance, SIMPSON_MAX_DEPTH)
+}
+
+
+fn adaptive_simpson(f: &mut Integrand, (a, b): (f64, f64), (fa, fm, fb): (f64, f64, f64), whole: f64, tolerance: f64, depth: usize) -> Result<(f64, f64), String> {
+    let m = (a + b) / 2.0;
+    let (flm, frm) = (f.call((a + m) / 2.0)?, f.call((m + b) / 2.0)?);
+    let left = (m - a) / 6.0 * (fa + 4.0 * flm + fm);
+    let right = (b - m) / 6.0 * (fm + 4.0 * frm + fb);
+    let delta = left + right - whole;
+    if !delta.is_finite() {
+        return Err(format!("error: the integral of {} is not finite", f.name))
+    }
+    if delta.abs() <= 15.0 * tolerance {
+        return Ok((left + right + delta / 15.0, delta.abs() / 15.0))
+    }
+    if depth == 0 {
+        return Err("error: nintegrate did not converge".to_string())
+    }
+    let (left, left_error) = adaptive_simpson(f, (a, m), (fa, flm, fm), left, tolerance / 2.0, depth - 1)?;
+    let (right, right_error) = adaptive_simpson(f, (m, b), (fm, frm, fb), right, tolerance / 2.0, depth - 1)?;
+    Ok((left + right, left_error + right_error))
+}
+
+
+/// Push the zeros of the linear denominators in `expr`.
+fn push_poles(expr: &Expr, variable: &str, poles: &mut Vec<Num>) {
+    let mut push_zero = |u: &Expr| {
+        if let Some([q, p]) = u.polynomial(variable).as_deref() {
+            if let Ok(zero) = q.reverse_sign().supported_div(p) {
+                poles.push(zero);
+            }
+        }
+    };
+    match expr {
+        Expr::Num(_) | Expr::Variable(_) => {},
+        Expr::Div(a, b) => {
+            push_zero(b);
+            push_poles(a, variable, poles);
+            push_poles(b, variable, poles);
+        },
+        Expr::Pow(a, b) => {
+            if b.polynomial(variable).is_some_and(|c| c.len() == 1 && c[0].is_real() && c[0].is_sign_negative()) {
+                push_zero(a);
+            }
+            push_poles(a, variable, poles);
+            push_poles(b, variable, poles);
+        },
+        Expr::Neg(a) => push_poles(a, variable, poles),
+        Expr::Add(a, b) | Expr::Sub(a, b) | Expr::Mul(a, b) => {
+            push_poles(a, variable, poles);
+            push_poles(b, variable, poles);
+        },
+        Expr::Func(_, args) => args.iter().for_each(|a| push_poles(a, variable, poles)),
+    }
+}
+
+
+fn evaluate(expr: &Expr, variable: &String, value: &Num, data_base: &DataBase) -> Result<Num, String> {
+    Parser::calculation_at(&expr.to_tree(), variable, value, data_base)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+
+    fn antiderivative_test(function: &str) -> Result<String, String> {
+        let data_base = DataBase::new();
+        let mut parser = Parser::new(Lexer::new(function).make_token_vec()?);
+        let tree = parser.make_tree(&data_base)?;
+        let expr = antiderivative(&Expr::from_tree(&tree)?, "x")?.simplify();
+        Parser::print_tree(&expr.to_tree())
+    }
+
+    #[test]
+    fn antiderivative_polynomial() {
+        assert_eq!(antiderivative_test("3x ^ 2 + 2x - 1"), Ok("x ^ 3 + x ^ 2 - x".to_string()));
+        assert_eq!(antiderivative_test("(x + 1) ^ 2"), Ok("( 1/3 ) * x ^ 3 + x ^ 2 + x".to_string()));
+        assert_eq!(antiderivative_test("a"), Ok("a * x".to_string()));
+    }
+
+    #[test]
+    fn antiderivative_linear_argument() {
+        assert_eq!(antiderivative_test("exp(2x + 1)"), Ok("exp ( 2 * x + 1 ) / 2".to_string()));
+        assert_eq!(antiderivative_test("sin(x) + 3 * cos(-x)"), Ok("- cos ( x ) - 3 * sin ( - x )".to_string()));
+        assert_eq!(antiderivative_test("1 / (2x - 1)"), Ok("( 1/2 ) * ln ( abs ( 2 * x - 1 ) )".to_string()));
+        assert_eq!(antiderivative_test("x ^ (-2)"), Ok("- x ^ ( -1 )".to_string()));
+    }
+
+    #[test]
+    fn definite_integral_pole() -> Result<(), String> {
+        let mut data_base = DataBase::new();
+        let code = "1 / (2x - 1)".to_string();
+        let tree = Parser::new(Lexer::new(&code).make_token_vec()?).make_tree(&data_base)?;
+        data_base.register_func("f", tree, vec!["x".to_string()])?;
+        let name = "f".to_string();
+        assert_eq!(definite_integral(&data_base, &name, &Num::from_i64(0), &Num::from_i64(1)),
+                   Err("error: f is not defined at x = 1/2".to_string()));
+        assert!(definite_integral(&data_base, &name, &Num::from_i64(1), &Num::from_i64(2))
+            .is_ok_and(|n| (n.float_value() - 3.0f64.ln() / 2.0).abs() < 1e-12));
+        Ok(())
+    }
+
+    fn numeric_integral_test(function: &str, a: f64, b: f64) -> Result<(f64, f64), String> {
+        let mut data_base = DataBase::new();
+        let tree = Parser::new(Lexer::new(function).make_token_vec()?).make_tree(&data_base)?;
+        data_base.register_func("f", tree, vec!["x".to_string()])?;
+        numeric_integral(&data_base, &"f".to_string(), a, b)
+    }
+
+    #[test]
+    fn numeric_integral_normal() -> Result<(), String> {
+        let (value, error) = numeric_integral_test("exp(x ^ 2)", 0.0, 1.0)?;
+        assert!((value - 1.4626517459071816).abs() < 1e-9 && error < 1e-9);
+        let (value, _) = numeric_integral_test("sin(x)", std::f64::consts::PI, 0.0)?;
+        assert!((value + 2.0).abs() < 1e-9);
+        Ok(())
+    }
+
+    #[test]
+    fn numeric_integral_error() {
+        assert_eq!(numeric_integral_test("1 / x", 0.0, 1.0),
+                   Err("error: f cannot be evaluated at x = 0: The calculation resulted in 'inf'.".to_string()));
+        assert_eq!(numeric_integral_test("sqrt(x)", -1.0, 1.0),
+                   Err("error: f is not real at x = -1".to_string()));
+        assert_eq!(numeric_integral_test("1 / (x - 0.3)", 0.0, 1.0),
+                   Err("error: nintegrate did not converge".to_string()));
+    }
+
+    #[test]
+    fn antiderivative_unsupported() {
+        assert_eq!(antiderivative_test("exp(x ^ 2)"), Err("error: cannot integrate exp ( x ^ 2 )".to_string()));
+        assert_eq!(antiderivative_test("x * sin(x)"), Err("error: cannot integrate x * sin ( x )".to_string()));
+    }
+}
@@ -2,18 +2,23 @@ use std::str::Chars;
 use std::iter::Peekable;
 
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[allow(clippy::box_collection)]
 pub enum Token {
     LParen, // (
     RParen, // )
     LBracket, // [
     RBracket, // ]
+    LBrace, // {
+    RBrace, // }
     Comma, // ,
     SemiColon, // ;
     Caret, // ^
     TwoAsterisk, // **
     Asterisk, // *
+    TwoSlash, // //
     Slash, // /
+    BackSlash, // \
     Percent, // %
     Plus, // +
     Minus, // -
@@ -25,18 +30,23 @@ pub enum Token {
 }
 
 
+/// Function names ending with digits. Other names are letters only.
+pub const DIGIT_SUFFIX_NAMES: [&str; 2] = ["log10", "atan2"];
+
+
 pub struct Lexer<'a> {
     iter: Peekable<Chars<'a>>,
 }
 
 enum PendingType {
     Asterisk,
+    Slash,
     NumString,
     String,
 }
 
 impl<'a> Lexer<'a> {
-    pub fn new(input: &'a String) -> Lexer<'a> {
+    pub fn new(input: &'a str) -> Lexer<'a> {
         Lexer { iter: input.chars().peekable() }
     }
 
@@ -65,10 +75,12 @@ impl<'a> Lexer<'a> {
                         ')' => return Ok(Some(Token::RParen)),
                         '[' => return Ok(Some(Token::LBracket)),
                         ']' => return Ok(Some(Token::RBracket)),
+                        '{' => return Ok(Some(Token::LBrace)),
+                        '}' => return Ok(Some(Token::RBrace)),
                         ',' => return Ok(Some(Token::Comma)),
                         ';' => return Ok(Some(Token::SemiColon)),
                         '^' => return Ok(Some(Token::Caret)),
-                        '/' => return Ok(Some(Token::Slash)),
+                        '\\' => return Ok(Some(Token::BackSlash)),
                         '%' => return Ok(Some(Token::Percent)),
                         '+' => return Ok(Some(Token::Plus)),
                         '-' => return Ok(Some(Token::Minus)),
@@ -78,6 +90,10 @@ impl<'a> Lexer<'a> {
                             pending_string.push(c);
                             break PendingType::Asterisk;
                         },
+                        '/' => {
+                            pending_string.push(c);
+                            break PendingType::Slash;
+                        },
                         '0'..='9' => {
                             pending_string.push(c);
                             break PendingType::NumString;
@@ -96,6 +112,7 @@ impl<'a> Lexer<'a> {
                 None => {
                     match pending_flag {
                         PendingType::Asterisk => return Ok(Some(Token::Asterisk)),
+                        PendingType::Slash => return Ok(Some(Token::Slash)),
                         PendingType::NumString => return Ok(Some(Token::NumString(Box::new(pending_string)))),
                         PendingType::String => return Ok(Some(Self::string_to_token(pending_string))),
                     }
@@ -104,14 +121,16 @@ impl<'a> Lexer<'a> {
                     if c.is_ascii_whitespace() {
                         match pending_flag {
                             PendingType::Asterisk => return Ok(Some(Token::Asterisk)),
+                            PendingType::Slash => return Ok(Some(Token::Slash)),
                             PendingType::NumString => return Ok(Some(Token::NumString(Box::new(pending_string)))),
                             PendingType::String => return Ok(Some(Self::string_to_token(pending_string))),
                         }
                     }
                     match c {
-                        '(' | ')' | '[' | ']' | ',' | ';' | '^' | '/' | '%' | '+' | '-' | '=' | '?' => {
+                        '(' | ')' | '[' | ']' | '{' | '}' | ',' | ';' | '^' | '\\' | '%' | '+' | '-' | '=' | '?' => {
                             match pending_flag {
                                 PendingType::Asterisk => return Ok(Some(Token::Asterisk)),
+                                PendingType::Slash => return Ok(Some(Token::Slash)),
                                 PendingType::NumString => return Ok(Some(Token::NumString(Box::new(pending_string)))),
                                 PendingType::String => return Ok(Some(Self::string_to_token(pending_string))),
                             }
@@ -122,6 +141,18 @@ impl<'a> Lexer<'a> {
                                     self.next();
                                     return Ok(Some(Token::TwoAsterisk))
                                 },
+                                PendingType::Slash => return Ok(Some(Token::Slash)),
+                                PendingType::NumString => return Ok(Some(Token::NumString(Box::new(pending_string)))),
+                                PendingType::String => return Ok(Some(Self::string_to_token(pending_string))),
+                            }
+                        },
+                        '/' => {
+                            match pending_flag {
+                                PendingType::Asterisk => return Ok(Some(Token::Asterisk)),
+                                PendingType::Slash => {
+                                    self.next();
+                                    return Ok(Some(Token::TwoSlash))
+                                },
                                 PendingType::NumString => return Ok(Some(Token::NumString(Box::new(pending_string)))),
                                 PendingType::String => return Ok(Some(Self::string_to_token(pending_string))),
                             }
@@ -142,16 +173,26 @@ impl<'a> Lexer<'a> {
                         '0'..='9' => {
                             match pending_flag {
                                 PendingType::Asterisk => return Ok(Some(Token::Asterisk)),
+                                PendingType::Slash => return Ok(Some(Token::Slash)),
                                 PendingType::NumString => {
                                     let c = self.next().unwrap();
                                     pending_string.push(c);
                                 },
-                                PendingType::String => return Ok(Some(Self::string_to_token(pending_string))),
+                                PendingType::String => {
+                                    if let Some(suffix) = self.digit_suffix(&pending_string) {
+                                        for _ in 0..suffix.len() {
+                                            self.next();
+                                        }
+                                        pending_string.push_str(suffix);
+                                    }
+                                    return Ok(Some(Self::string_to_token(pending_string)))
+                                },
                             }
                         }
                         'a'..='z' | 'A'..='Z' => {
                             match pending_flag {
                                 PendingType::Asterisk => return Ok(Some(Token::Asterisk)),
+                                PendingType::Slash => return Ok(Some(Token::Slash)),
                                 PendingType::NumString => return Ok(Some(Token::NumString(Box::new(pending_string)))),
                                 PendingType::String => {
                                     let c = self.next().unwrap();
@@ -166,6 +207,16 @@ impl<'a> Lexer<'a> {
         }
     }
 
+    fn digit_suffix(&self, pending_string: &str) -> Option<&'static str> {
+        let rest = self.iter.clone().collect::<String>();
+        DIGIT_SUFFIX_NAMES.iter()
+            .filter(|name| name.len() > pending_string.len()
+                && name[..pending_string.len()].eq_ignore_ascii_case(pending_string))
+            .map(|name| &name[pending_string.len()..])
+            .find(|suffix| rest.starts_with(suffix)
+                && !rest[suffix.len()..].starts_with(|c: char| c.is_ascii_alphanumeric() || c == '.'))
+    }
+
     fn string_to_token(string: String) -> Token {
         if string.len() == 1 && (string == "i" || string == "I") {
             Token::I
@@ -174,7 +225,7 @@ impl<'a> Lexer<'a> {
         }
     }
 
-    pub fn next(&mut self) -> Option<char> {
+    fn next(&mut self) -> Option<char> {
         self.iter.next()
     }
 
@@ -191,12 +242,12 @@ mod tests {
     #[test]
     fn lexer_all() {
         use Token::*;
-        let s = "()^*/%**+-i=?[],;a1A2zz ZZ123.098^A".to_string();
+        let s = "()^*/%**+-i=?[]{},;a1A2zz ZZ123.098^A".to_string();
         let mut lexer = Lexer::new(&s);
         let vec = lexer.make_token_vec();
         assert_eq!(vec, Ok(vec![LParen, RParen, Caret, Asterisk, Slash, Percent,
                                 TwoAsterisk, Plus, Minus, I, Equal, Question,
-                                LBracket, RBracket, Comma, SemiColon,
+                                LBracket, RBracket, LBrace, RBrace, Comma, SemiColon,
                                 String(Box::new("a".to_string())),
                                 NumString(Box::new("1".to_string())),
                                 String(Box::new("A".to_string())),
@@ -209,6 +260,34 @@ mod tests {
         ]));
     }
 
+    #[test]
+    fn lexer_log10() {
+        use Token::*;
+        let s = "log10(x) + log102 + x10 + ATAN2".to_string();
+        let mut lexer = Lexer::new(&s);
+        let vec = lexer.make_token_vec();
+        assert_eq!(vec, Ok(vec![String(Box::new("log10".to_string())), LParen,
+                                String(Box::new("x".to_string())), RParen, Plus,
+                                String(Box::new("log".to_string())),
+                                NumString(Box::new("102".to_string())), Plus,
+                                String(Box::new("x".to_string())),
+                                NumString(Box::new("10".to_string())), Plus,
+                                String(Box::new("ATAN2".to_string()))
+        ]));
+    }
+
+    #[test]
+    fn lexer_matrix_division() {
+        use Token::*;
+        let s = "a//b\\c/ /d".to_string();
+        let mut lexer = Lexer::new(&s);
+        let vec = lexer.make_token_vec();
+        assert_eq!(vec, Ok(vec![String(Box::new("a".to_string())), TwoSlash,
+                                String(Box::new("b".to_string())), BackSlash,
+                                String(Box::new("c".to_string())), Slash, Slash,
+                                String(Box::new("d".to_string()))]));
+    }
+
     #[test]
     fn lexer_empty() {
         let s = "".to_string();
@@ -281,10 +360,10 @@ mod tests {
 
     #[test]
     fn lexer_unsupported_character() {
-        let s = "aa}aa".to_string();
+        let s = "aa$aa".to_string();
         let mut lexer = Lexer::new(&s);
         let vec = lexer.make_token_vec();
-        assert_eq!(vec, Err("Unsupported character: }".to_string()));
+        assert_eq!(vec, Err("Unsupported character: $".to_string()));
     }
 
     #[test]
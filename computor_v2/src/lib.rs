@@ -0,0 +1,28 @@
+pub mod lexer;
+pub mod num;
+mod bigint;
+mod rational;
+pub mod binary_tree;
+pub mod parser;
+pub mod data_base;
+mod operator;
+pub mod equation;
+pub mod derivative;
+pub mod integral;
+mod numeric_roots;
+mod numeric_solve;
+pub mod linear_system;
+mod fraction;
+mod exact_roots;
+mod factor;
+pub mod symbolic_solve;
+pub mod expression;
+mod canonical;
+pub mod solution;
+pub mod command;
+pub mod functions;
+pub mod terminal;
+
+pub use data_base::DataBase;
+pub use functions::{Builtin, NativeFunc};
+pub use num::Num;
@@ -0,0 +1,260 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::binary_tree::BinaryTree;
+use crate::parser::Element;
+use crate::operator::Operator;
+use crate::num::{Matrix, Num};
+use crate::solution::{SolutionKind, SolutionSet};
+
+
+/// Linear combination of unknowns plus a constant, with exact coefficients
+/// while the input is exact.
+#[derive(Debug, PartialEq, Clone)]
+struct Linear {
+    terms: BTreeMap<String, Num>,
+    constant: Num,
+}
+
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct LinearSystem {
+    variables: Vec<String>,
+    /// Augmented rows `a_1 ... a_n | b` of `a_1 x_1 + ... + a_n x_n = b`.
+    rows: Vec<Vec<Num>>,
+}
+
+
+impl Linear {
+    fn from_constant(constant: Num) -> Linear {
+        Linear { terms: BTreeMap::new(), constant }
+    }
+
+    fn from_variable(variable: &str) -> Linear {
+        Linear { terms: BTreeMap::from([(variable.to_string(), Num::from_i64(1))]), constant: Num::zero() }
+    }
+
+    fn constant_value(&self) -> Option<&Num> {
+        if self.terms.is_empty() {Some(&self.constant)} else {None}
+    }
+
+    fn add(&self, rhs: &Linear, sign: &Num) -> Result<Linear, String> {
+        let mut terms = self.terms.clone();
+        for (variable, coefficient) in &rhs.terms {
+            let term = sign.supported_mul(coefficient)?;
+            let sum = match terms.get(variable) {
+                Some(c) => c.supported_add(&term)?,
+                None => term,
+            };
+            terms.insert(variable.clone(), sum);
+        }
+        terms.retain(|_, c| !c.is_zero());
+        Ok(Linear { terms, constant: self.constant.supported_add(&sign.supported_mul(&rhs.constant)?)? })
+    }
+
+    fn scale(&self, factor: &Num) -> Result<Linear, String> {
+        let mut terms = BTreeMap::new();
+        for (variable, coefficient) in &self.terms {
+            terms.insert(variable.clone(), coefficient.supported_mul(factor)?);
+        }
+        terms.retain(|_, c| !c.is_zero());
+        Ok(Linear { terms, constant: self.constant.supported_mul(factor)? })
+    }
+
+    fn from_tree(tree: &BinaryTree<Element>) -> Result<Linear, String> {
+        let node_box = match tree {
+            BinaryTree::Empty => return Err("syntax error".to_string()),
+            BinaryTree::NonEmpty(node_box) => node_box,
+        };
+        let linear = match &node_box.element {
+            Element::Dummy => Linear::from_constant(Num::zero()),
+            Element::Num(n) if n.is_matrix() => return Err("error: the system is not linear".to_string()),
+            Element::Num(n) => Linear::from_constant(n.clone()),
+            Element::Variable(v) => Linear::from_variable(v),
+            Element::Func(_) => return Err("error: the system is not linear".to_string()),
+            Element::Operator(op) => {
+                if let Operator::Paren = op {
+                    return Self::from_tree(tree.left().unwrap())
+                }
+                let lhs = Self::from_tree(tree.left().unwrap())?;
+                let rhs = Self::from_tree(tree.right().unwrap())?;
+                match (op, lhs.constant_value(), rhs.constant_value()) {
+                    (Operator::Plus, _, _) => lhs.add(&rhs, &Num::from_i64(1))?,
+                    (Operator::Minus, _, _) => lhs.add(&rhs, &Num::from_i64(-1))?,
+                    (Operator::Mul, Some(c), _) => rhs.scale(c)?,
+                    (Operator::Mul, _, Some(c)) => lhs.scale(c)?,
+                    (Operator::Div, _, Some(c)) if !c.is_zero() => lhs.scale(&Num::from_i64(1).supported_div(c)?)?,
+                    (Operator::Div, _, Some(_)) => return Err("The calculation resulted in 'inf'.".to_string()),
+                    (Operator::Pow, Some(a), Some(b)) => Linear::from_constant(a.supported_pow(b)?),
+                    (Operator::Pow, _, Some(b)) if b.is_one() => lhs,
+                    (Operator::Rem, Some(a), Some(b)) => Linear::from_constant(a.supported_rem(b)?),
+                    _ => return Err("error: the system is not linear".to_string()),
+                }
+            },
+        };
+        linear.constant.checked_value()?;
+        Ok(linear)
+    }
+}
+
+
+impl LinearSystem {
+    /// The system of equations `lhs = rhs`.
+    pub fn new(equations: &[(BinaryTree<Element>, BinaryTree<Element>)]) -> Result<LinearSystem, String> {
+        let mut linears = Vec::new();
+        for (lhs, rhs) in equations {
+            linears.push(Linear::from_tree(lhs)?.add(&Linear::from_tree(rhs)?, &Num::from_i64(-1))?);
+        }
+        let mut variables: Vec<String> = linears.iter().flat_map(|l| l.terms.keys().cloned()).collect();
+        variables.sort();
+        variables.dedup();
+        if variables.is_empty() {
+            return Err("error: the system has no unknowns".to_string())
+        }
+        let rows = linears.iter().map(|linear| {
+            let mut row: Vec<Num> = variables.iter()
+                .map(|v| linear.terms.get(v).cloned().unwrap_or(Num::zero()))
+                .collect();
+            row.push(linear.constant.reverse_sign());
+            row
+        }).collect();
+        Ok(LinearSystem { variables, rows })
+    }
+
+    /// Gauss–Jordan elimination on the augmented matrix. The solution set holds
+    /// the values of the unknowns in order when the solution is unique.
+    pub fn solution(&self) -> Result<(String, SolutionSet), String> {
+        let columns = self.variables.len();
+        let matrix = Matrix::from_vec(self.rows.clone()).ok_or("error: the system has no equations".to_string())?;
+        let (reduced, pivots) = matrix.reduced_row_echelon(columns)?;
+        let at = |row: usize, column: usize| reduced.at(row, column).cloned().unwrap_or(Num::zero());
+        if (pivots.len()..self.rows.len()).any(|row| !at(row, columns).is_zero()) {
+            return Ok(("There is no solution, the equations are inconsistent.".to_string(), SolutionSet::from_kind(SolutionKind::Empty)))
+        }
+        let free: Vec<String> = (0..columns)
+            .filter(|column| !pivots.contains(column))
+            .map(|column| self.variables[column].clone())
+            .collect();
+        let set = match free.len() {
+            0 => SolutionSet::from_values((0..columns).map(|row| at(row, columns)).collect()),
+            _ => SolutionSet::from_kind(SolutionKind::Every),
+        };
+        let mut string = match free.len() {
+            0 => "The system has a unique solution:".to_string(),
+            1 => format!("Infinitely many solutions, with {} as a parameter:", free[0]),
+            _ => format!("Infinitely many solutions, with {} as parameters:", free.join(", ")),
+        };
+        for (row, column) in pivots.iter().enumerate() {
+            // x_pivot = b - sum of a_k x_k over the free columns
+            let coefficients: Vec<Num> = (0..columns)
+                .map(|k| if pivots.contains(&k) {Num::zero()} else {at(row, k).reverse_sign()})
+                .collect();
+            string += &format!("\n{} = {}", self.variables[*column],
+                               Self::combination_string(&self.variables, &coefficients, Some(&at(row, columns))));
+        }
+        Ok((string, set))
+    }
+
+    /// `a_1 * x_1 + ... + a_n * x_n`, starting with `constant` when given.
+    fn combination_string(variables: &[String], coefficients: &[Num], constant: Option<&Num>) -> String {
+        let mut string = match constant {
+            Some(c) if !c.is_zero() => format!("{}", c),
+            _ => String::new(),
+        };
+        for (variable, a) in variables.iter().zip(coefficients) {
+            if a.is_zero() {
+                continue
+            }
+            let negative = a.is_real() && a.is_sign_negative();
+            let a = if negative {a.reverse_sign()} else {a.clone()};
+            let term = match a {
+                _ if a.is_one() => variable.clone(),
+                _ if a.is_real() => format!("{} * {}", a, variable),
+                _ => format!("({}) * {}", a, variable),
+            };
+            string += &match (string.is_empty(), negative) {
+                (true, false) => term,
+                (true, true) => format!("-{}", term),
+                (false, false) => format!(" + {}", term),
+                (false, true) => format!(" - {}", term),
+            };
+        }
+        if string.is_empty() {"0".to_string()} else {string}
+    }
+}
+
+
+impl fmt::Display for LinearSystem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let lines: Vec<String> = self.rows.iter()
+            .map(|row| format!("  {} = {}", Self::combination_string(&self.variables, row, None), row[row.len() - 1]))
+            .collect();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_base::DataBase;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn solution_test(equations: &[(&str, &str)]) -> Result<String, String> {
+        let data_base = DataBase::new();
+        let mut trees = Vec::new();
+        for (lhs, rhs) in equations {
+            let mut sides = Vec::new();
+            for code in [lhs, rhs] {
+                let mut parser = Parser::new(Lexer::new(code).make_token_vec()?);
+                let mut tree = parser.make_tree(&data_base)?;
+                parser.calculation(&mut tree, &data_base, None)?;
+                sides.push(tree);
+            }
+            let rhs = sides.pop().unwrap();
+            trees.push((sides.pop().unwrap(), rhs));
+        }
+        Ok(LinearSystem::new(&trees)?.solution()?.0)
+    }
+
+    #[test]
+    fn solution_unique() {
+        assert_eq!(solution_test(&[("2x + y", "3"), ("x - y", "0")]),
+                   Ok("The system has a unique solution:\nx = 1\ny = 1".to_string()));
+        assert_eq!(solution_test(&[("x + y + z", "6"), ("2y", "z + 1"), ("x", "(z - y) / 1")]),
+                   Ok("The system has a unique solution:\nx = 1\ny = 2\nz = 3".to_string()));
+        assert_eq!(solution_test(&[("x + y", "1"), ("x - y", "0")]),
+                   Ok("The system has a unique solution:\nx = 1/2\ny = 1/2".to_string()));
+        assert_eq!(solution_test(&[("x + i * y", "1 + i"), ("x", "y")]),
+                   Ok("The system has a unique solution:\nx = 1\ny = 1".to_string()));
+    }
+
+    #[test]
+    fn solution_parametric() {
+        assert_eq!(solution_test(&[("x + y + z", "3"), ("x - y", "1")]),
+                   Ok("Infinitely many solutions, with z as a parameter:\nx = 2 - 1/2 * z\ny = 1 - 1/2 * z".to_string()));
+        assert_eq!(solution_test(&[("x + 2y", "0"), ("2x + 4y", "0")]),
+                   Ok("Infinitely many solutions, with y as a parameter:\nx = -2 * y".to_string()));
+    }
+
+    #[test]
+    fn solution_set_values() -> Result<(), String> {
+        let data_base = DataBase::new();
+        let tree = |code: &str| Parser::new(Lexer::new(code).make_token_vec()?).make_tree(&data_base);
+        let system = LinearSystem::new(&[(tree("x + y")?, tree("1")?), (tree("x - y")?, tree("2")?)])?;
+        let (_, set) = system.solution()?;
+        assert_eq!(set.to_vector(), Num::from_num_vec(vec![vec![Num::from_string("1.5")?, Num::from_string("-0.5")?]]).ok());
+        let system = LinearSystem::new(&[(tree("x + y")?, tree("1")?)])?;
+        assert_eq!(system.solution()?.1.kind, SolutionKind::Every);
+        Ok(())
+    }
+
+    #[test]
+    fn solution_inconsistent() {
+        assert_eq!(solution_test(&[("x + y", "1"), ("2x + 2y", "3")]),
+                   Ok("There is no solution, the equations are inconsistent.".to_string()));
+        assert_eq!(solution_test(&[("x * y", "1"), ("x", "y")]),
+                   Err("error: the system is not linear".to_string()));
+    }
+}
@@ -1,41 +1,56 @@
 use std::io::{self, Write};
 use std::env;
 
-mod lexer;
-mod num;
-mod binary_tree;
-mod parser;
-mod data_base;
-mod operator;
-mod equation;
-mod solution;
-mod command;
-mod functions;
-mod terminal;
-
-use lexer::{Lexer, Token};
-use parser::Parser;
-use data_base::DataBase;
-use equation::Equation;
-use command::Commands;
-use terminal::{TerminalController, TerminalEvent};
+use computor_v2::{derivative, integral, symbolic_solve};
+use computor_v2::lexer::{Lexer, Token};
+use computor_v2::parser::{Parser, Element};
+use computor_v2::binary_tree::BinaryTree;
+use computor_v2::data_base::DataBase;
+use computor_v2::num::{Num, ComplexForm};
+use computor_v2::equation::Equation;
+use computor_v2::solution::{SolutionSet, SolutionKind};
+use computor_v2::linear_system::LinearSystem;
+use computor_v2::expression::Expr;
+use computor_v2::command::Commands;
+use computor_v2::terminal::{TerminalController, TerminalEvent};
 
 
 fn show_variable(data_base: &DataBase) -> Result<(String, String), String> {
     let string = data_base.show_variable();
-    Ok((format!(""), string))
+    Ok((String::new(), string))
 }
 
 
 fn show_function(data_base: &DataBase) -> Result<(String, String), String> {
     let string = data_base.show_function()?;
-    Ok((format!(""), string))
+    Ok((String::new(), string))
+}
+
+
+fn show_builtin_function(data_base: &DataBase) -> (String, String) {
+    let string = data_base.show_builtin_func();
+    (String::new(), string)
 }
 
 
 fn show_commands(commands: &Commands) -> (String, String) {
     let string = commands.show();
-    (format!(""), string)
+    (String::new(), string)
+}
+
+
+fn set_complex_form(vec: &[Token], data_base: &mut DataBase) -> Result<(String, String), String> {
+    if vec.len() == 2 {
+        let form = match Parser::get_string_token_string(&vec[1])?.as_str() {
+            "rectangular" => ComplexForm::Rectangular,
+            "polar" => ComplexForm::Polar,
+            "exponential" => ComplexForm::Exponential,
+            s => return Err(format!("error: unknown display form {}", s)),
+        };
+        data_base.set_complex_form(form);
+    }
+    let string = format!("display: {}", data_base.complex_form());
+    Ok((string.clone(), format!("  {}\n", string)))
 }
 
 
@@ -46,9 +61,11 @@ fn calculate(left_vec: Vec<Token>, data_base: &DataBase) -> Result<(String, Stri
     let left_value = parser.calculation(&mut tree, data_base, None)?;
     let (result, output) = match left_value {
         Some(v) => {
-            (format!("{}", v), format!("{}\n", v.to_show_value_string()))
+            let form = data_base.complex_form();
+            (v.to_string_form(form), format!("{}\n", v.to_show_value_string(form)))
         },
         None => {
+            let tree = Expr::from_tree(&tree).map(|e| e.canonical().to_tree()).unwrap_or(tree);
             let s = Parser::print_tree(&tree)?;
             (s.clone(), format!("  {}\n", s))
         },
@@ -57,6 +74,104 @@ fn calculate(left_vec: Vec<Token>, data_base: &DataBase) -> Result<(String, Stri
 }
 
 
+fn show_derivative(left_vec: Vec<Token>, data_base: &DataBase) -> Result<(String, String), String> {
+    let (name, variable) = Parser::get_diff_args(&left_vec)?;
+    let (expr, _) = derivative::derivative_func(data_base, &name, variable.as_ref())?;
+    let string = Parser::print_tree(&expr.to_tree())?;
+    Ok((string.clone(), format!("  {}\n", string)))
+}
+
+
+fn show_integral(left_vec: Vec<Token>, data_base: &DataBase) -> Result<(String, String), String> {
+    let (name, bounds) = Parser::get_integrate_args(&left_vec)?;
+    match bounds {
+        None => {
+            let (expr, _) = integral::antiderivative_func(data_base, &name)?;
+            let string = Parser::print_tree(&expr.to_tree())?;
+            Ok((string.clone(), format!("  {}\n", string)))
+        },
+        Some((a, b)) => {
+            let a = calculate_bound(a, data_base)?;
+            let b = calculate_bound(b, data_base)?;
+            let value = integral::definite_integral(data_base, &name, &a, &b)?;
+            let form = data_base.complex_form();
+            Ok((value.to_string_form(form), format!("{}\n", value.to_show_value_string(form))))
+        },
+    }
+}
+
+
+fn show_numeric_integral(left_vec: Vec<Token>, data_base: &DataBase) -> Result<(String, String), String> {
+    let (name, bounds) = Parser::get_integrate_args(&left_vec)?;
+    let (a, b) = bounds.ok_or("error: nintegrate takes a function and two bounds".to_string())?;
+    let a = calculate_bound(a, data_base)?;
+    let b = calculate_bound(b, data_base)?;
+    if !a.is_real() || !b.is_real() {
+        return Err("error: the bounds must be real".to_string())
+    }
+    let (value, error) = integral::numeric_integral(data_base, &name, a.float_value(), b.float_value())?;
+    let value = Num::Float(value);
+    let form = data_base.complex_form();
+    Ok((value.to_string_form(form), format!("{}
+  error estimate: {:.1e}
+", value.to_show_value_string(form), error)))
+}
+
+
+/// The tree of a registered function given by name, or of the expression,
+/// evaluated as far as the known variables allow.
+fn call_argument_tree(left_vec: &[Token], data_base: &DataBase) -> Result<BinaryTree<Element>, String> {
+    let argument = Parser::get_call_argument(left_vec);
+    if let [Token::String(name)] = argument.as_slice() {
+        if let Some(f) = data_base.get_func(name) {
+            return Ok(f.0.clone())
+        }
+    }
+    let mut parser = Parser::new(argument);
+    let mut tree = parser.make_tree(data_base)?;
+    parser.calculation(&mut tree, data_base, None)?;
+    Ok(tree)
+}
+
+
+fn show_expand(left_vec: Vec<Token>, data_base: &DataBase) -> Result<(String, String), String> {
+    let tree = call_argument_tree(&left_vec, data_base)?;
+    let expanded = Expr::from_tree(&tree)?.canonical().to_tree();
+    let string = match polynomial_equation(&expanded, data_base).ok().and_then(|e| e.polynomial_string()) {
+        Some(string) => string,
+        None => Parser::print_tree(&expanded)?,
+    };
+    Ok((string.clone(), format!("  {}\n", string)))
+}
+
+
+fn show_factor(left_vec: Vec<Token>, data_base: &DataBase) -> Result<(String, String), String> {
+    let tree = call_argument_tree(&left_vec, data_base)?;
+    let string = polynomial_equation(&tree, data_base)?.factor_string()?;
+    Ok((string.clone(), format!("  {}\n", string)))
+}
+
+
+/// The polynomial equation `tree = 0`.
+fn polynomial_equation(tree: &BinaryTree<Element>, data_base: &DataBase) -> Result<Equation, String> {
+    let zero = Parser::new(vec![Token::NumString(Box::new("0".to_string()))]).make_tree(data_base)?;
+    let mut equation = Equation::new();
+    equation.make_equation(tree, &zero)?;
+    Ok(equation)
+}
+
+
+fn calculate_bound(vec: Vec<Token>, data_base: &DataBase) -> Result<Num, String> {
+    let mut parser = Parser::new(vec);
+    let mut tree = parser.make_tree(data_base)?;
+    match parser.calculation(&mut tree, data_base, None)? {
+        Some(n) if !n.is_matrix() => Ok(n),
+        Some(_) => Err("error: the bounds must be numbers".to_string()),
+        None => Err("Undefined Variables".to_string()),
+    }
+}
+
+
 fn solution_equation(left_vec: Vec<Token>, right_vec: Vec<Token>, data_base: &mut DataBase) -> Result<(String, String), String> {
     let mut parser = Parser::new(left_vec);
     let mut left_tree = parser.make_tree(data_base)?;
@@ -69,14 +184,205 @@ fn solution_equation(left_vec: Vec<Token>, right_vec: Vec<Token>, data_base: &mu
     parser.calculation(&mut right_tree, data_base, None)?;
 
     let mut equation = Equation::new();
-    equation.make_equation(&left_tree, &right_tree)?;
-    let string = format!("  {} = 0\n", equation.to_string()?);
-    let solution_string = equation.solution()?;
+    if let Err(e) = equation.make_equation(&left_tree, &right_tree) {
+        return match equation.make_rational_equation(&left_tree, &right_tree) {
+            Ok(denominators) => rational_equation(&equation, &denominators, data_base),
+            Err(_) => non_polynomial_equation(&left_tree, &right_tree, data_base, e),
+        }
+    }
+    let steps = if data_base.solve_steps() {equation.solution_steps()?} else {Vec::new()};
+    let (solution_string, set) = equation.solve(data_base.exact_roots())?;
+    store_roots(set, data_base);
+    Ok(solution_output(equation.to_string()?, steps, solution_string))
+}
+
+
+fn rational_equation(equation: &Equation, denominators: &[Vec<f64>], data_base: &mut DataBase) -> Result<(String, String), String> {
+    let steps = if data_base.solve_steps() {equation.rational_solution_steps(denominators)?} else {Vec::new()};
+    let (solution_string, set) = equation.rational_solve(denominators)?;
+    store_roots(set, data_base);
+    Ok(solution_output(equation.to_string()?, steps, solution_string))
+}
+
+
+/// Keep the roots of the last equation or system in `roots` and their
+/// multiplicities in `multiplicities`, or forget both when the roots cannot
+/// be listed. User variables of these names are left alone.
+fn store_roots(set: Option<SolutionSet>, data_base: &mut DataBase) {
+    let (roots, multiplicities) = match set.and_then(|set| Some((set.to_vector()?, set.multiplicities()?))) {
+        Some((roots, multiplicities)) => (Some(roots), Some(multiplicities)),
+        None => (None, None),
+    };
+    data_base.set_solve_result("roots", roots);
+    data_base.set_solve_result("multiplicities", multiplicities);
+}
+
+
+/// Solutions of `lhs = rhs` in the way a `?` equation is solved.
+fn equation_solution_set(left_tree: &BinaryTree<Element>, right_tree: &BinaryTree<Element>, data_base: &DataBase) -> Result<SolutionSet, String> {
+    let mut equation = Equation::new();
+    if let Err(e) = equation.make_equation(left_tree, right_tree) {
+        if let Ok(denominators) = equation.make_rational_equation(left_tree, right_tree) {
+            return equation.rational_solution_set(&denominators)
+        }
+        let (tree, variable) = non_polynomial_tree(left_tree, right_tree, e)?;
+        return Ok(Equation::non_polynomial_solution_set(&tree, &variable, data_base))
+    }
+    equation.solution_set()
+}
+
+
+/// The reduced form, the numbered steps if any, then the solution.
+fn solution_output(reduced_form: String, steps: Vec<String>, solution_string: String) -> (String, String) {
+    let mut string = format!("  {} = 0\n", reduced_form);
+    for (i, step) in steps.iter().enumerate() {
+        string += &format!("{}. {}\n", i + 1, step);
+    }
+    let result = string.clone() + solution_string.as_str();
+    (result, string + solution_string.as_str() + "\n")
+}
+
+
+fn solution_system(vec: &[Token], data_base: &mut DataBase) -> Result<(String, String), String> {
+    let mut equations = Vec::new();
+    for (left_vec, right_vec) in Parser::get_system_equations(vec)? {
+        let mut sides = Vec::new();
+        for side_vec in [left_vec, right_vec] {
+            let mut parser = Parser::new(side_vec);
+            let mut tree = parser.make_tree(data_base)?;
+            parser.calculation(&mut tree, data_base, None)?;
+            sides.push(tree);
+        }
+        let right_tree = sides.pop().unwrap();
+        equations.push((sides.pop().unwrap(), right_tree));
+    }
+    let system = LinearSystem::new(&equations)?;
+    let string = format!("{}\n", system);
+    let (solution_string, set) = system.solution()?;
+    store_roots(Some(set), data_base);
     let result = string.clone() + solution_string.as_str();
     Ok((result, string + solution_string.as_str() + "\n"))
 }
 
 
+/// Both sides of the equation of a `solve` form, and its unknown.
+fn solve_trees(vec: &Vec<Token>, data_base: &DataBase) -> Result<(BinaryTree<Element>, BinaryTree<Element>, String), String> {
+    let ((left_vec, right_vec), variable) = Parser::get_solve_args(vec)?;
+    let mut sides = Vec::new();
+    for side_vec in [left_vec, right_vec] {
+        let mut parser = Parser::new(side_vec);
+        let mut tree = parser.make_tree(data_base)?;
+        parser.calculation(&mut tree, data_base, None)?;
+        sides.push(tree);
+    }
+    let mut variables = Vec::new();
+    Parser::get_variables_in_tree(&sides[0], &mut variables);
+    Parser::get_variables_in_tree(&sides[1], &mut variables);
+    let variable = match (variable, variables.as_slice()) {
+        (Some(v), _) => v,
+        (None, [v]) => v.clone(),
+        (None, []) => "x".to_string(),
+        (None, _) => return Err("error: solve needs the unknown when there are several variables".to_string()),
+    };
+    let right_tree = sides.pop().unwrap();
+    Ok((sides.pop().unwrap(), right_tree, variable))
+}
+
+
+fn solve_symbolic(vec: &Vec<Token>, data_base: &DataBase) -> Result<(String, String), String> {
+    let (left_tree, right_tree, variable) = solve_trees(vec, data_base)?;
+    let expr = Expr::sub(Expr::from_tree(&left_tree)?, Expr::from_tree(&right_tree)?).simplify();
+    let string = format!("  {} = 0\n", Parser::print_tree(&expr.to_tree())?);
+    let solution_string = symbolic_solve::solve(&expr, &variable, data_base.exact_roots())?;
+    let result = string.clone() + solution_string.as_str();
+    Ok((result, string + solution_string.as_str() + "\n"))
+}
+
+
+/// `name = solve(...)` stores the roots as a vector.
+fn register_solution(vec: &Vec<Token>, data_base: &mut DataBase) -> Result<(String, String), String> {
+    let (left_tree, right_tree, variable) = solve_trees(vec, data_base)?;
+    let mut variables = Vec::new();
+    Parser::get_variables_in_tree(&left_tree, &mut variables);
+    Parser::get_variables_in_tree(&right_tree, &mut variables);
+    if variables.iter().any(|v| *v != variable) {
+        return Err("error: the roots depend on parameters, use solve(...) ? to see them".to_string())
+    }
+    let set = equation_solution_set(&left_tree, &right_tree, data_base)?;
+    let vector = match (set.kind, set.to_vector()) {
+        (_, Some(vector)) => vector,
+        (SolutionKind::Every, None) => return Err("error: every number is a solution".to_string()),
+        (_, None) => return Err("error: the equation has no solution".to_string()),
+    };
+    let key = Parser::get_string_token_string(&vec[0])?;
+    data_base.register_num(key, vector)?;
+    let num = data_base.get_num(key).unwrap();
+    let form = data_base.complex_form();
+    Ok((num.to_string_form(form), format!("{}\n", num.to_show_value_string(form))))
+}
+
+
+/// `lhs - rhs` and its only variable, when the equation has a single unknown.
+fn non_polynomial_tree(left_tree: &BinaryTree<Element>, right_tree: &BinaryTree<Element>, error: String) -> Result<(BinaryTree<Element>, String), String> {
+    let mut variables = Vec::new();
+    Parser::get_variables_in_tree(left_tree, &mut variables);
+    Parser::get_variables_in_tree(right_tree, &mut variables);
+    variables.dedup();
+    match (Expr::from_tree(left_tree), Expr::from_tree(right_tree)) {
+        (Ok(lhs), Ok(rhs)) if variables.len() == 1 => Ok((Expr::sub(lhs, rhs).simplify().to_tree(), variables.pop().unwrap())),
+        _ => Err(error),
+    }
+}
+
+
+/// Solve numerically when the equation is not a polynomial but has a single unknown.
+fn non_polynomial_equation(left_tree: &BinaryTree<Element>, right_tree: &BinaryTree<Element>, data_base: &mut DataBase, error: String) -> Result<(String, String), String> {
+    let (tree, variable) = non_polynomial_tree(left_tree, right_tree, error)?;
+    let steps = if data_base.solve_steps() {Equation::non_polynomial_steps(data_base)} else {Vec::new()};
+    let set = Equation::non_polynomial_solution_set(&tree, &variable, data_base);
+    let solution_string = Equation::non_polynomial_solution(&set, data_base);
+    store_roots(Some(set), data_base);
+    Ok(solution_output(Parser::print_tree(&tree)?, steps, solution_string))
+}
+
+
+/// The mode set by `<name> on` or `<name> off`, or `current` when none is given.
+fn get_on_off(vec: &[Token], current: bool) -> Result<bool, String> {
+    if vec.len() < 2 {
+        return Ok(current)
+    }
+    match Parser::get_string_token_string(&vec[1])?.as_str() {
+        "on" => Ok(true),
+        "off" => Ok(false),
+        s => Err(format!("error: unknown {} mode {}", Parser::get_string_token_string(&vec[0])?, s)),
+    }
+}
+
+
+fn set_solve_steps(vec: &[Token], data_base: &mut DataBase) -> Result<(String, String), String> {
+    data_base.set_solve_steps(get_on_off(vec, data_base.solve_steps())?);
+    let string = format!("steps: {}", if data_base.solve_steps() {"on"} else {"off"});
+    Ok((string.clone(), format!("  {}\n", string)))
+}
+
+
+fn set_exact_roots(vec: &[Token], data_base: &mut DataBase) -> Result<(String, String), String> {
+    data_base.set_exact_roots(get_on_off(vec, data_base.exact_roots())?);
+    let string = format!("exact: {}", if data_base.exact_roots() {"on"} else {"off"});
+    Ok((string.clone(), format!("  {}\n", string)))
+}
+
+
+fn set_solve_interval(vec: &[Token], data_base: &mut DataBase) -> Result<(String, String), String> {
+    if let Some(interval) = Parser::get_interval_args(vec)? {
+        data_base.set_solve_interval(interval);
+    }
+    let (a, b) = data_base.solve_interval();
+    let string = format!("interval: [{}, {}]", a, b);
+    Ok((string.clone(), format!("  {}\n", string)))
+}
+
+
 fn register(left_vec: Vec<Token>, right_vec: Vec<Token>, data_base: &mut DataBase) -> Result<(String, String), String> {
     let mut parser = Parser::new(right_vec);
     let mut tree = parser.make_tree(data_base)?;
@@ -84,56 +390,104 @@ fn register(left_vec: Vec<Token>, right_vec: Vec<Token>, data_base: &mut DataBas
     let right_value = parser.calculation(&mut tree, data_base, None)?;
     let right_value = match right_value {
         Some(v) => v,
-        None => return Err(format!("Undefined Variables")),
+        None => return Err("Undefined Variables".to_string()),
     };
 
     let key = Parser::get_string_token_string(&left_vec[0])?;
     data_base.register_num(key, right_value)?;
-    let num = data_base.get_num(&key).unwrap();
-    Ok((format!("{}", num), format!("{}\n", num.to_show_value_string())))
+    let num = data_base.get_num(key).unwrap();
+    let form = data_base.complex_form();
+    Ok((num.to_string_form(form), format!("{}\n", num.to_show_value_string(form))))
 }
 
 
 fn func_register(left_vec: Vec<Token>, right_vec: Vec<Token>, data_base: &mut DataBase) -> Result<(String, String), String> {
     let key = Parser::get_string_token_string(&left_vec[0])?;
-    let variable = Parser::get_string_token_string(&left_vec[2])?;
-
-    let mut parser = Parser::new(right_vec);
-    let mut tree = parser.make_tree(data_base)?;
-
-    parser.calculation(&mut tree, data_base, Some((variable, None)))?;
-
-    match Parser::check_variable_in_tree(&tree)? {
-        Some(var) => {
-            if var != *variable {
-                return Err(format!("{}, {}: error two variable", var, variable))
+    let variables = Parser::get_func_register_variables(&left_vec)?;
+
+    let tree = if Parser::is_diff(&right_vec) || Parser::is_integrate(&right_vec) {
+        let (name, (expr, func_variables)) = if Parser::is_diff(&right_vec) {
+            let (name, variable) = Parser::get_diff_args(&right_vec)?;
+            let result = derivative::derivative_func(data_base, &name, variable.as_ref())?;
+            (name, result)
+        } else {
+            let (name, bounds) = Parser::get_integrate_args(&right_vec)?;
+            if bounds.is_some() {
+                return Err("error: a definite integral is not a function".to_string())
             }
-        },
-        None => {},
+            let result = integral::antiderivative_func(data_base, &name)?;
+            (name, result)
+        };
+        if func_variables.len() != variables.len() {
+            return Err(format!("error: {} takes {} argument(s)", name, func_variables.len()))
+        }
+        let parameters: Vec<Expr> = variables.iter().map(|v| Expr::Variable(v.clone())).collect();
+        expr.substitute(&func_variables, &parameters).to_tree()
+    } else {
+        let mut parser = Parser::new(right_vec);
+        let mut tree = parser.make_tree(data_base)?;
+        let locals = variables.iter().map(|v| (v, None)).collect();
+        parser.calculation(&mut tree, data_base, Some(&locals))?;
+        tree
+    };
+
+    let mut tree_variables = Vec::new();
+    Parser::get_variables_in_tree(&tree, &mut tree_variables);
+    for var in tree_variables {
+        if !variables.contains(&var) {
+            return Err(format!("{}: error variable is not a parameter of {}({})", var, key, variables.join(", ")))
+        }
     }
 
-    data_base.register_func(key, tree, variable.clone())?;
-    let string = Parser::print_tree(&data_base.get_func(key).unwrap().0)?;
+    let canonical = Expr::from_tree(&tree).map(|e| e.canonical().to_tree()).unwrap_or(tree.clone());
+    data_base.register_func(key, tree, variables)?;
+    let string = Parser::print_tree(&canonical)?;
     Ok((string.clone(), format!("  {}\n", string)))
 }
 
 
-fn compute(code: &String, data_base: &mut DataBase, commands: &Commands) -> Result<(String, String), String> {
-    let mut lexer = Lexer::new(&code);
+fn compute(code: &str, data_base: &mut DataBase, commands: &Commands) -> Result<(String, String), String> {
+    let mut lexer = Lexer::new(code);
     let vec = lexer.make_token_vec()?;
 
     if Parser::is_show_variable(&vec) {
-        return show_variable(&data_base)
+        return show_variable(data_base)
     } else if Parser::is_show_functions(&vec) {
-        return show_function(&data_base)
+        return show_function(data_base)
+    } else if Parser::is_show_builtin_functions(&vec) {
+        return Ok(show_builtin_function(data_base))
     } else if Parser::is_show_commands(&vec) {
-        return Ok(show_commands(&commands))
+        return Ok(show_commands(commands))
+    } else if Parser::is_display_command(&vec) {
+        return set_complex_form(&vec, data_base)
+    } else if Parser::is_steps_command(&vec) {
+        return set_solve_steps(&vec, data_base)
+    } else if Parser::is_exact_command(&vec) {
+        return set_exact_roots(&vec, data_base)
+    } else if Parser::is_interval_command(&vec) {
+        return set_solve_interval(&vec, data_base)
+    } else if Parser::is_system(&vec) {
+        return solution_system(&vec, data_base)
+    } else if Parser::is_solve(&vec) {
+        return solve_symbolic(&vec, data_base)
+    } else if Parser::is_solve_register(&vec) {
+        return register_solution(&vec, data_base)
     }
 
     let (left_vec, right_vec) = Parser::separate_equal(vec)?;
 
-    if Parser::is_question_tokens(&right_vec) {
-        calculate(left_vec, &data_base)
+    if Parser::is_question_tokens(&right_vec) && Parser::is_diff(&left_vec) {
+        show_derivative(left_vec, data_base)
+    } else if Parser::is_question_tokens(&right_vec) && Parser::is_integrate(&left_vec) {
+        show_integral(left_vec, data_base)
+    } else if Parser::is_question_tokens(&right_vec) && Parser::is_nintegrate(&left_vec) {
+        show_numeric_integral(left_vec, data_base)
+    } else if Parser::is_question_tokens(&right_vec) && Parser::is_expand(&left_vec) {
+        show_expand(left_vec, data_base)
+    } else if Parser::is_question_tokens(&right_vec) && Parser::is_factor(&left_vec) {
+        show_factor(left_vec, data_base)
+    } else if Parser::is_question_tokens(&right_vec) {
+        calculate(left_vec, data_base)
     } else if Parser::is_solution_equation(&right_vec) {
         solution_equation(left_vec, right_vec, data_base)
     } else if Parser::is_variable_register(&left_vec) {
@@ -141,7 +495,7 @@ fn compute(code: &String, data_base: &mut DataBase, commands: &Commands) -> Resu
     } else if Parser::is_func_register(&left_vec) {
         func_register(left_vec, right_vec, data_base)
     } else {
-        Ok(("Unsupported format".to_string(), format!("  Unsupported format\n")))
+        Ok(("Unsupported format".to_string(), "  Unsupported format\n".to_string()))
     }
 }
 
@@ -158,7 +512,7 @@ fn interpreter() {
             .read_line(&mut code)
             .expect("Failed to read line");
 
-        if code == "exit\n" || code == "" {
+        if code == "exit\n" || code.is_empty() {
             println!("exit");
             break;
         }
@@ -195,7 +549,7 @@ fn interpreter_rich_loop(terminal_controller: &mut TerminalController, data_base
                     println!("exit");
                     break;
                 }
-                let (result, output) = match compute(&code, data_base, &commands) {
+                let (result, output) = match compute(&code, data_base, commands) {
                     Err(e) => {
                         (e.clone(), format!("  {}\n", e))
                     },
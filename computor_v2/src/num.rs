@@ -1,26 +1,53 @@
 use std::fmt;
 
+use crate::rational::Rational;
+
 
 const MAX_POW: usize = 1000;
+/// Exact values larger than this many bits fall back to floats.
+pub const MAX_BITS: u64 = 1 << 17;
 
 
+/// The parts are real numbers, either both exact (`Num::Rational`)
+/// or both approximate (`Num::Float`).
 #[derive(Debug, PartialEq, Clone)]
 pub struct Complex {
-    pub r: f64,
-    pub z: f64,
+    pub r: Num,
+    pub z: Num,
 }
 
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Matrix {
-    elem: Vec<Vec<f64>>,
+    elem: Vec<Vec<Num>>,
     size: (usize, usize),
 }
 
 
-#[derive(Debug, PartialEq, Clone)]
+/// How complex results are shown to the user.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ComplexForm {
+    Rectangular,
+    Polar,
+    Exponential,
+}
+
+
+impl fmt::Display for ComplexForm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ComplexForm::Rectangular => write!(f, "rectangular"),
+            ComplexForm::Polar => write!(f, "polar"),
+            ComplexForm::Exponential => write!(f, "exponential"),
+        }
+    }
+}
+
+
+#[derive(Debug, Clone)]
 pub enum Num {
     Float(f64),
+    Rational(Box<Rational>),
     Complex(Box<Complex>),
     Matrix(Box<Matrix>)
 }
@@ -28,64 +55,68 @@ pub enum Num {
 
 impl Complex {
     fn from_two_float(r: f64, z: f64) -> Complex {
-        Complex { r, z }
+        Complex { r: Num::Float(r), z: Num::Float(z) }
     }
 
-    // fn new() -> Complex {
-    //     Self::from_two_float(0.0, 1.0)
-    // }
+    pub fn to_string_form(&self, form: ComplexForm) -> String {
+        if self.z.is_zero() {
+            return format!("{}", self)
+        }
+        let modulus = self.r.float_value().hypot(self.z.float_value());
+        let argument = self.z.float_value().atan2(self.r.float_value());
+        let (modulus, argument) = (Self::short_string(modulus), Self::short_string(argument));
+        match form {
+            ComplexForm::Rectangular => format!("{}", self),
+            ComplexForm::Polar => format!("{}∠{}", modulus, argument),
+            ComplexForm::Exponential => format!("{}e^({}i)", modulus, argument),
+        }
+    }
 
-    // fn is_float(&self) -> bool {
-    //     self.z == 0.0
-    // }
+    /// `v` rounded to 3 decimal places, without trailing zeros.
+    fn short_string(v: f64) -> String {
+        let string = format!("{:.3}", v);
+        let string = string.trim_end_matches('0').trim_end_matches('.');
+        if string == "-0" {"0".to_string()} else {string.to_string()}
+    }
 
-    // fn to_float(&self) -> Option<f64> {
-    //     if self.is_float() {
-    //         Some(self.r)
-    //     } else {
-    //         None
-    //     }
-    // }
+    fn imaginary_string(z: &Num) -> String {
+        match z {
+            Num::Rational(q) if !q.is_integer() => {
+                if q.numer().is_one() {
+                    format!("i/{}", q.denom())
+                } else {
+                    format!("{}i/{}", q.numer(), q.denom())
+                }
+            },
+            _ => if z.is_one() {"i".to_string()} else {format!("{}i", z)},
+        }
+    }
 }
 
 
 impl fmt::Display for Complex {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        if self.z.is_sign_positive() {
-            if (self.r, self.z) == (0.0, 0.0) {
-                write!(f, "0")
-            } else if (self.r, self.z) == (0.0, 1.0) {
-                write!(f, "i")
-            } else if self.r == 0.0 {
-                write!(f, "{}i", self.z)
-            } else if self.z == 0.0 {
-                write!(f, "{}", self.r)
-            } else if self.z == 1.0 {
-                write!(f, "{} + i", self.r)
+        if self.r.is_zero() && self.z.is_zero() {
+            write!(f, "0")
+        } else if self.z.is_zero() {
+            write!(f, "{}", self.r)
+        } else if self.r.is_zero() {
+            if self.z.is_sign_negative() {
+                write!(f, "-{}", Self::imaginary_string(&self.z.reverse_sign()))
             } else {
-                write!(f, "{} + {}i", self.r, self.z)
+                write!(f, "{}", Self::imaginary_string(&self.z))
             }
+        } else if self.z.is_sign_negative() {
+            write!(f, "{} - {}", self.r, Self::imaginary_string(&self.z.reverse_sign()))
         } else {
-            if (self.r, self.z) == (0.0, 0.0) {
-                write!(f, "0")
-            } else if (self.r, self.z) == (0.0, -1.0) {
-                write!(f, "-i")
-            } else if self.r == 0.0 {
-                write!(f, "{}i", self.z)
-            } else if self.z == 0.0 {
-                write!(f, "{}", self.r)
-            } else if self.z == -1.0 {
-                write!(f, "{} - i", self.r)
-            } else {
-                write!(f, "{} - {}i", self.r, -self.z)
-            }
+            write!(f, "{} + {}", self.r, Self::imaginary_string(&self.z))
         }
     }
 }
 
 
 impl Matrix {
-    pub fn from_vec(elem: Vec<Vec<f64>>) -> Option<Matrix> {
+    pub fn from_vec(elem: Vec<Vec<Num>>) -> Option<Matrix> {
         let horizontal_len = elem.len();
         if horizontal_len == 0 {
             return None
@@ -95,22 +126,26 @@ impl Matrix {
             if row.len() != vertical_len {
                 return None
             }
+            for n in row {
+                if let Num::Matrix(_) = n {
+                    return None
+                }
+            }
         }
         Some(Matrix { elem, size: (horizontal_len, vertical_len) })
     }
 
-    #[allow(dead_code)]
-    pub fn at(&self, row: usize, col: usize) -> Option<f64> {
+    pub fn at(&self, row: usize, col: usize) -> Option<&Num> {
         if row >= self.size.0 || col >= self.size.1 {
             None
         } else {
-            Some(self.elem[row][col])
+            Some(&self.elem[row][col])
         }
     }
 
 
     #[allow(dead_code)]
-    pub fn at_mut(&mut self, row: usize, col: usize) -> Option<&mut f64> {
+    pub fn at_mut(&mut self, row: usize, col: usize) -> Option<&mut Num> {
         if row >= self.size.0 || col >= self.size.1 {
             None
         } else {
@@ -119,59 +154,84 @@ impl Matrix {
     }
 
 
-    #[allow(dead_code)]
+    /// Element `index` of a matrix with a single row or column.
+    pub fn vector_at(&self, index: usize) -> Option<&Num> {
+        match self.size {
+            (1, _) => self.at(0, index),
+            (_, 1) => self.at(index, 0),
+            _ => None,
+        }
+    }
+
     pub fn size(&self) -> &(usize, usize) {
         &self.size
     }
 
 
-    pub fn apply_all_terms_float<F>(&self, apply_fn: F) -> Matrix
-        where F: Fn(&f64) -> f64
+    pub fn apply_all_terms<F>(&self, apply_fn: F) -> Matrix
+        where F: Fn(&Num) -> Num
     {
-        let mut vec = vec![vec![0.0; self.size.1]; self.size.0];
+        let mut vec = Vec::new();
         for m in 0..self.size.0 {
+            let mut row = Vec::new();
             for n in 0..self.size.1 {
-                vec[m][n] = apply_fn(&self.elem[m][n]);
+                row.push(apply_fn(&self.elem[m][n]));
             }
+            vec.push(row);
         }
-        return Matrix { elem: vec, size: self.size }
+        Matrix { elem: vec, size: self.size }
     }
 
 
-    pub fn apply_all_terms_matrix<F>(&self, rhs: &Matrix, apply_fn: F) -> Option<Matrix>
-        where F: Fn(&f64, &f64) -> f64
+    pub fn try_apply_all_terms<F>(&self, apply_fn: F) -> Result<Matrix, String>
+        where F: Fn(&Num) -> Result<Num, String>
+    {
+        let mut vec = Vec::new();
+        for m in 0..self.size.0 {
+            let mut row = Vec::new();
+            for n in 0..self.size.1 {
+                row.push(apply_fn(&self.elem[m][n])?);
+            }
+            vec.push(row);
+        }
+        Ok(Matrix { elem: vec, size: self.size })
+    }
+
+
+    pub fn apply_all_terms_matrix<F>(&self, rhs: &Matrix, apply_fn: F) -> Result<Option<Matrix>, String>
+        where F: Fn(&Num, &Num) -> Result<Num, String>
     {
         if self.size != rhs.size {
-            return None
+            return Ok(None)
         }
-        let mut vec = vec![vec![0.0; self.size.1]; self.size.0];
+        let mut vec = Vec::new();
         for m in 0..self.size.0 {
+            let mut row = Vec::new();
             for n in 0..self.size.1 {
-                vec[m][n] = apply_fn(&self.elem[m][n], &rhs.elem[m][n]);
+                row.push(apply_fn(&self.elem[m][n], &rhs.elem[m][n])?);
             }
+            vec.push(row);
         }
-        return Some(Matrix { elem: vec, size: self.size })
+        Ok(Some(Matrix { elem: vec, size: self.size }))
     }
 
 
     pub fn checked_value(&self) -> Result<&Matrix, String> {
         for m in 0..self.size.0 {
             for n in 0..self.size.1 {
-                if !self.elem[m][n].is_finite() {
-                    return Err(format!("The calculation resulted in '{}'.", self.elem[m][n]))
-                }
+                self.elem[m][n].checked_value()?;
             }
         }
         Ok(self)
     }
 
 
-    pub fn to_string_rich(&self) -> String {
+    pub fn to_string_rich(&self, form: ComplexForm) -> String {
         let mut string = String::new();
         for m in 0..self.size.0 {
             string += "  [";
             for n in 0..self.size.1 {
-                string.push_str(format!(" {} ,", self.elem[m][n]).as_str());
+                string.push_str(format!(" {} ,", self.elem[m][n].to_string_form(form)).as_str());
             }
             string.pop();
             string += "]\n";
@@ -181,19 +241,138 @@ impl Matrix {
     }
 
 
-    pub fn matrix_mul(&self, rhs: &Matrix) -> Option<Matrix> {
+    pub fn matrix_mul(&self, rhs: &Matrix) -> Result<Option<Matrix>, String> {
         if self.size.1 != rhs.size.0 {
-            return None
+            return Ok(None)
         }
-        let mut vec = vec![vec![0.0; rhs.size.1]; self.size.0];
+        let mut vec = Vec::new();
         for m in 0..self.size.0 {
+            let mut row = Vec::new();
             for n in 0..rhs.size.1 {
+                let mut value = Num::zero();
                 for k in 0..self.size.1 {
-                    vec[m][n] += self.elem[m][k] * rhs.elem[k][n];
+                    value = value.supported_add(&self.elem[m][k].supported_mul(&rhs.elem[k][n])?)?;
+                }
+                row.push(value);
+            }
+            vec.push(row);
+        }
+        Ok(Some(Matrix { elem: vec, size: (self.size.0, rhs.size.1) }))
+    }
+
+
+    pub fn identity(n: usize) -> Matrix {
+        let elem = (0..n).map(|m| (0..n).map(|k| {
+            if m == k {Num::from_i64(1)} else {Num::zero()}
+        }).collect()).collect();
+        Matrix { elem, size: (n, n) }
+    }
+
+
+    pub fn is_square(&self) -> bool {
+        self.size.0 == self.size.1
+    }
+
+
+    /// Gauss-Jordan elimination with partial pivoting over the first `columns`
+    /// columns of `rows`, skipping columns without a pivot. Inexact pivots of
+    /// magnitude at most `tolerance` count as zero. Returns the pivot columns
+    /// and the product of the pivots, negated for each row swap.
+    fn row_reduce(rows: &mut [Vec<Num>], columns: usize, tolerance: f64) -> Result<(Vec<usize>, Num), String> {
+        let mut pivots = Vec::new();
+        let mut det = Num::from_i64(1);
+        for col in 0..columns {
+            let row = pivots.len();
+            if row == rows.len() {
+                break
+            }
+            let pivot_row = (row..rows.len()).fold(row, |max, m| {
+                if rows[m][col].magnitude() > rows[max][col].magnitude() {m} else {max}
+            });
+            let pivot = rows[pivot_row][col].clone();
+            if pivot.is_zero() || (!pivot.is_exact() && pivot.magnitude() <= tolerance) {
+                continue
+            }
+            if pivot_row != row {
+                rows.swap(pivot_row, row);
+                det = det.reverse_sign();
+            }
+            det = det.supported_mul(&pivot)?;
+            for value in rows[row].iter_mut() {
+                *value = value.supported_div(&pivot)?;
+            }
+            let pivot_elem = rows[row].clone();
+            for (m, other) in rows.iter_mut().enumerate() {
+                let factor = other[col].clone();
+                if m == row || factor.is_zero() {
+                    continue
+                }
+                for (value, p) in other.iter_mut().zip(&pivot_elem) {
+                    *value = value.supported_sub(&factor.supported_mul(p)?)?;
                 }
             }
+            pivots.push(col);
+        }
+        Ok((pivots, det))
+    }
+
+
+    fn pivot_tolerance(&self) -> f64 {
+        let scale = self.elem.iter().flatten().map(|x| x.magnitude()).fold(0.0, f64::max);
+        scale * self.size.0.max(self.size.1) as f64 * f64::EPSILON
+    }
+
+
+    /// Gauss-Jordan elimination, applying the same row operations to `rhs`.
+    /// Returns the determinant, or None if singular.
+    fn gauss_jordan(&self, rhs: &mut [Vec<Num>]) -> Result<Option<Num>, String> {
+        let n = self.size.0;
+        let mut rows: Vec<Vec<Num>> = self.elem.iter().zip(rhs.iter())
+            .map(|(row, r)| row.iter().chain(r).cloned().collect())
+            .collect();
+        let (pivots, det) = Self::row_reduce(&mut rows, n, self.pivot_tolerance())?;
+        if pivots.len() < n {
+            return Ok(None)
+        }
+        for (r, row) in rhs.iter_mut().zip(rows) {
+            *r = row[n..].to_vec();
+        }
+        Ok(Some(det))
+    }
+
+
+    /// The reduced row echelon form in the first `columns` columns, with the
+    /// pivot columns. Inexact entries negligible next to the largest one are zero.
+    pub fn reduced_row_echelon(&self, columns: usize) -> Result<(Matrix, Vec<usize>), String> {
+        let tolerance = self.pivot_tolerance();
+        let mut rows = self.elem.clone();
+        let (pivots, _) = Self::row_reduce(&mut rows, columns.min(self.size.1), tolerance)?;
+        for value in rows.iter_mut().flatten() {
+            if !value.is_exact() && value.magnitude() <= tolerance {
+                *value = Num::zero();
+            }
+        }
+        Ok((Matrix { elem: rows, size: self.size }, pivots))
+    }
+
+
+    pub fn determinant(&self) -> Result<Num, String> {
+        if !self.is_square() {
+            return Err("error: det requires a square matrix".to_string())
+        }
+        Ok(self.gauss_jordan(&mut vec![Vec::new(); self.size.0])?.unwrap_or(Num::zero()))
+    }
+
+
+    pub fn inverse(&self) -> Result<Matrix, String> {
+        if !self.is_square() {
+            return Err("error: inv requires a square matrix".to_string())
+        }
+        let mut inv = Self::identity(self.size.0);
+        match self.gauss_jordan(&mut inv.elem)? {
+            None => Err("error: the matrix is singular".to_string()),
+            Some(_) => Ok(inv),
         }
-        Some(Matrix { elem: vec, size: (self.size.0, rhs.size.1) })
     }
 }
 
@@ -218,7 +397,7 @@ impl fmt::Display for Matrix {
 
 
 impl Num {
-    pub fn from_string_to_float(string: &String) -> Result<Num, String> {
+    pub fn from_string_to_float(string: &str) -> Result<Num, String> {
         match string.parse() {
             Ok(n) => Ok(Num::Float(n)),
             Err(_) => {
@@ -228,6 +407,34 @@ impl Num {
     }
 
 
+    /// Number literals are exact whenever they can be.
+    pub fn from_string(string: &str) -> Result<Num, String> {
+        match Rational::from_decimal_str(string) {
+            Some(q) => Ok(Self::from_rational(q)),
+            None => Self::from_string_to_float(string),
+        }
+    }
+
+
+    pub fn from_rational(q: Rational) -> Num {
+        if q.bits() > MAX_BITS {
+            Num::Float(q.to_f64())
+        } else {
+            Num::Rational(Box::new(q))
+        }
+    }
+
+
+    pub fn from_i64(n: i64) -> Num {
+        Self::from_rational(Rational::from_i64(n))
+    }
+
+
+    pub fn zero() -> Num {
+        Self::from_i64(0)
+    }
+
+
     pub fn from_two_float(r: f64, z: f64) -> Num {
         if z == 0.0 {
             Num::Float(r)
@@ -237,17 +444,52 @@ impl Num {
     }
 
 
+    /// Like `from_two_float`, but a part that is only rounding noise compared
+    /// with the other one is dropped, so that e.g. exp(i * pi) gives -1.
+    pub fn from_two_float_trimmed(r: f64, z: f64) -> Num {
+        let modulus = r.hypot(z);
+        if !modulus.is_finite() {
+            return Self::from_two_float(r, z)
+        }
+        let r = if r.abs() <= modulus * f64::EPSILON {0.0} else {r};
+        let z = if z.abs() <= modulus * f64::EPSILON {0.0} else {z};
+        Self::from_two_float(r, z)
+    }
+
+
     pub fn from_two_float_to_complex(r: f64, z: f64) -> Num {
         Num::Complex(Box::new(Complex::from_two_float(r, z)))
     }
 
 
+    /// Build a scalar from two real parts. The result stays exact
+    /// only when both parts are exact.
+    pub fn from_two_num(r: Num, z: Num) -> Num {
+        if z.is_zero() {
+            return r
+        }
+        match (&r, &z) {
+            (Num::Rational(_), Num::Rational(_)) => Num::Complex(Box::new(Complex { r, z })),
+            _ => Self::from_two_float_to_complex(r.float_value(), z.float_value()),
+        }
+    }
+
+
     pub fn new_complex() -> Num {
-        Self::from_two_float_to_complex(0.0, 1.0)
+        Num::Complex(Box::new(Complex { r: Self::zero(), z: Self::from_i64(1) }))
     }
 
 
+    #[allow(dead_code)]
     pub fn from_vec(elem: Vec<Vec<f64>>) -> Result<Num, String> {
+        let elem = elem.into_iter()
+            .map(|row| row.into_iter().map(Num::Float).collect())
+            .collect();
+        Self::from_num_vec(elem)
+    }
+
+
+    pub fn from_num_vec(elem: Vec<Vec<Num>>) -> Result<Num, String> {
         match Matrix::from_vec(elem) {
             Some(m) => Ok(Num::Matrix(Box::new(m))),
             None => Err("Conversion Failure".to_string())
@@ -255,28 +497,169 @@ impl Num {
     }
 
 
+    pub fn is_matrix(&self) -> bool {
+        matches!(self, Num::Matrix(_))
+    }
+
+
+    pub fn is_real(&self) -> bool {
+        matches!(self, Num::Float(_) | Num::Rational(_))
+    }
+
+
+    pub fn is_zero(&self) -> bool {
+        match self {
+            Num::Float(n) => *n == 0.0,
+            Num::Rational(q) => q.is_zero(),
+            Num::Complex(c) => c.r.is_zero() && c.z.is_zero(),
+            Num::Matrix(_) => false,
+        }
+    }
+
+
+    pub fn is_one(&self) -> bool {
+        match self {
+            Num::Float(n) => *n == 1.0,
+            Num::Rational(q) => q.is_integer() && q.numer().is_one(),
+            Num::Complex(_) | Num::Matrix(_) => false,
+        }
+    }
+
+
+    /// Sign of a real value. Always false for other values.
+    pub fn is_sign_negative(&self) -> bool {
+        match self {
+            Num::Float(n) => n.is_sign_negative(),
+            Num::Rational(q) => q.is_negative(),
+            Num::Complex(_) | Num::Matrix(_) => false,
+        }
+    }
+
+
+    /// Approximate value of a real number, NaN for other values.
+    pub fn float_value(&self) -> f64 {
+        match self {
+            Num::Float(n) => *n,
+            Num::Rational(q) => q.to_f64(),
+            Num::Complex(_) | Num::Matrix(_) => f64::NAN,
+        }
+    }
+
+
+    /// Absolute value (modulus) of a scalar as a float. NaN for matrices.
+    pub fn magnitude(&self) -> f64 {
+        match self {
+            Num::Complex(c) => c.r.float_value().hypot(c.z.float_value()),
+            _ => self.float_value().abs(),
+        }
+    }
+
+
+    pub fn is_exact(&self) -> bool {
+        match self {
+            Num::Rational(_) => true,
+            Num::Complex(c) => c.r.is_exact(),
+            Num::Float(_) | Num::Matrix(_) => false,
+        }
+    }
+
+
+    /// The value as an integer, if it is a real number without a fractional part.
+    pub fn to_int_value(&self) -> Option<i64> {
+        match self {
+            Num::Float(n) => {
+                if n.is_finite() && Self::is_int_value(*n) {
+                    Some(*n as i64)
+                } else {
+                    None
+                }
+            },
+            Num::Rational(q) => if q.is_integer() {q.numer().to_i64()} else {None},
+            Num::Complex(_) | Num::Matrix(_) => None,
+        }
+    }
+
+
+    /// Real and imaginary parts of a scalar.
+    pub fn to_parts(&self) -> (Num, Num) {
+        match self {
+            Num::Complex(c) => (c.r.clone(), c.z.clone()),
+            Num::Float(_) => (self.clone(), Num::Float(0.0)),
+            _ => (self.clone(), Self::zero()),
+        }
+    }
+
+
+    fn real_add(&self, rhs: &Num) -> Num {
+        match (self, rhs) {
+            (Num::Rational(l), Num::Rational(r)) => Self::from_rational(l.add(r)),
+            _ => Num::Float(self.float_value() + rhs.float_value()),
+        }
+    }
+
+
+    fn real_sub(&self, rhs: &Num) -> Num {
+        match (self, rhs) {
+            (Num::Rational(l), Num::Rational(r)) => Self::from_rational(l.sub(r)),
+            _ => Num::Float(self.float_value() - rhs.float_value()),
+        }
+    }
+
+
+    fn real_mul(&self, rhs: &Num) -> Num {
+        match (self, rhs) {
+            (Num::Rational(l), Num::Rational(r)) => Self::from_rational(l.mul(r)),
+            _ => Num::Float(self.float_value() * rhs.float_value()),
+        }
+    }
+
+
+    /// Exact division by zero falls back to floats so that it is reported
+    /// the same way as a float division.
+    fn real_div(&self, rhs: &Num) -> Num {
+        match (self, rhs) {
+            (Num::Rational(l), Num::Rational(r)) => {
+                match l.div(r) {
+                    Some(q) => Self::from_rational(q),
+                    None => Num::Float(self.float_value() / rhs.float_value()),
+                }
+            },
+            _ => Num::Float(self.float_value() / rhs.float_value()),
+        }
+    }
+
+
+    fn real_rem(&self, rhs: &Num) -> Num {
+        match (self, rhs) {
+            (Num::Rational(l), Num::Rational(r)) => {
+                match l.rem_euclid(r) {
+                    Some(q) => Self::from_rational(q),
+                    None => Num::Float(f64::NAN),
+                }
+            },
+            _ => Num::Float(self.float_value().rem_euclid(rhs.float_value())),
+        }
+    }
+
+
     pub fn checked_value(&self) -> Result<&Num, String> {
         match self {
             Num::Float(n) => {
                 if n.is_finite() {
-                    Ok(&self)
+                    Ok(self)
                 } else {
                     Err(format!("The calculation resulted in '{}'.", n))
                 }
             },
+            Num::Rational(_) => Ok(self),
             Num::Complex(b) => {
-                if !b.r.is_finite() {
-                    Err(format!("The calculation resulted in '{}'.", b.r))
-                }
-                else if !b.z.is_finite() {
-                    Err(format!("The calculation resulted in '{}'.", b.z))
-                } else {
-                    Ok(&self)
-                }
+                b.r.checked_value()?;
+                b.z.checked_value()?;
+                Ok(self)
             },
             Num::Matrix(b) => {
                 b.checked_value()?;
-                Ok(&self)
+                Ok(self)
             },
         }
     }
@@ -284,146 +667,221 @@ impl Num {
 
     pub fn supported_add(&self, rhs: &Num) -> Result<Num, String> {
         match (self, rhs) {
-            (Num::Float(l), Num::Float(r))
-                => Ok(Num::Float(l + r)),
-            (Num::Float(l), Num::Complex(r))
-                => Ok(Num::from_two_float(l + r.r, r.z)),
-            (Num::Complex(l), Num::Float(r))
-                => Ok(Num::from_two_float(l.r + r, l.z)),
-            (Num::Complex(l), Num::Complex(r))
-                => Ok(Num::from_two_float(l.r + r.r, l.z + r.z)),
             (Num::Matrix(l), Num::Matrix(r)) => {
-                match l.apply_all_terms_matrix(r, |x, y| x + y) {
+                match l.apply_all_terms_matrix(r, |x, y| x.supported_add(y))? {
                     None => Err(format!("Unsupported different sizes operator {} + {}", self, rhs)),
                     Some(m) => Ok(Num::Matrix(Box::new(m))),
                 }
             },
-            _ => Err(format!("Unsupported operator {} + {}", self, rhs)),
+            (Num::Matrix(_), _) | (_, Num::Matrix(_))
+                => Err(format!("Unsupported operator {} + {}", self, rhs)),
+            (l, r) if l.is_real() && r.is_real() => Ok(l.real_add(r)),
+            _ => {
+                let ((a, b), (c, d)) = (self.to_parts(), rhs.to_parts());
+                Ok(Self::from_two_num(a.real_add(&c), b.real_add(&d)))
+            },
         }
     }
 
 
     pub fn supported_sub(&self, rhs: &Num) -> Result<Num, String> {
         match (self, rhs) {
-            (Num::Float(l), Num::Float(r))
-                => Ok(Num::Float(l - r)),
-            (Num::Float(l), Num::Complex(r))
-                => Ok(Num::from_two_float(l - r.r, -r.z)),
-            (Num::Complex(l), Num::Float(r))
-                => Ok(Num::from_two_float(l.r - r, l.z)),
-            (Num::Complex(l), Num::Complex(r))
-                => Ok(Num::from_two_float(l.r - r.r, l.z - r.z)),
             (Num::Matrix(l), Num::Matrix(r)) => {
-                match l.apply_all_terms_matrix(r, |x, y| x - y) {
+                match l.apply_all_terms_matrix(r, |x, y| x.supported_sub(y))? {
                     None => Err(format!("Unsupported different sizes operator {} - {}", self, rhs)),
                     Some(m) => Ok(Num::Matrix(Box::new(m))),
                 }
             },
-            _ => Err(format!("Unsupported operator ({}) - ({})", self, rhs)),
+            (Num::Matrix(_), _) | (_, Num::Matrix(_))
+                => Err(format!("Unsupported operator ({}) - ({})", self, rhs)),
+            (l, r) if l.is_real() && r.is_real() => Ok(l.real_sub(r)),
+            _ => {
+                let ((a, b), (c, d)) = (self.to_parts(), rhs.to_parts());
+                Ok(Self::from_two_num(a.real_sub(&c), b.real_sub(&d)))
+            },
         }
     }
 
 
     pub fn supported_mul(&self, rhs: &Num) -> Result<Num, String> {
         match (self, rhs) {
-            (Num::Float(l), Num::Float(r))
-                =>Ok(Num::Float(l * r)),
-            (Num::Float(l), Num::Complex(r))
-                => Ok(Num::from_two_float(l * r.r, l * r.z)),
-            (Num::Complex(l), Num::Float(r))
-                => Ok(Num::from_two_float(l.r * r, l.z * r)),
-            (Num::Complex(l), Num::Complex(r))
-                => Ok(Num::from_two_float(l.r * r.r - l.z * r.z, l.r * r.z + l.z * r.r)),
-            (Num::Float(l), Num::Matrix(r))
-                => Ok(Num::Matrix(Box::new(r.apply_all_terms_float(|x| l * x)))),
-            (Num::Matrix(l), Num::Float(r))
-                => Ok(Num::Matrix(Box::new(l.apply_all_terms_float(|x| x * r)))),
             (Num::Matrix(l), Num::Matrix(r)) => {
-                match l.apply_all_terms_matrix(r, |x, y| x * y) {
+                match l.apply_all_terms_matrix(r, |x, y| x.supported_mul(y))? {
                     None => Err(format!("Unsupported different sizes operator {} * {}", self, rhs)),
                     Some(m) => Ok(Num::Matrix(Box::new(m))),
                 }
             },
-            _ => Err(format!("Unsupported operator ({}) * ({})", self, rhs)),
+            (l, Num::Matrix(r))
+                => Ok(Num::Matrix(Box::new(r.try_apply_all_terms(|x| l.supported_mul(x))?))),
+            (Num::Matrix(l), r)
+                => Ok(Num::Matrix(Box::new(l.try_apply_all_terms(|x| x.supported_mul(r))?))),
+            (l, r) if l.is_real() && r.is_real() => Ok(l.real_mul(r)),
+            _ => {
+                let ((a, b), (c, d)) = (self.to_parts(), rhs.to_parts());
+                Ok(Self::from_two_num(
+                    a.real_mul(&c).real_sub(&b.real_mul(&d)),
+                    a.real_mul(&d).real_add(&b.real_mul(&c)),
+                ))
+            },
         }
     }
 
 
     pub fn supported_div(&self, rhs: &Num) -> Result<Num, String> {
         match (self, rhs) {
-            (Num::Float(l), Num::Float(r))
-                => Ok(Num::Float(l / r)),
-            (Num::Float(l), Num::Complex(r)) => {
-                let v = r.r * r.r + r.z * r.z;
-                Ok(Num::from_two_float(l * r.r / v, - l * r.z / v))
-            },
-            (Num::Complex(l), Num::Float(r))
-                => Ok(Num::from_two_float(l.r / r, l.z / r)),
-            (Num::Complex(_), Num::Complex(r)) => {
-                let v = r.r * r.r + r.z * r.z;
-                let r2 = Num::from_two_float(r.r, -r.z);
-                Ok(self.supported_mul(&r2).unwrap()
-                        .supported_div(&Num::Float(v)).unwrap())
-            },
-            (Num::Float(l), Num::Matrix(r))
-                => Ok(Num::Matrix(Box::new(r.apply_all_terms_float(|x| l / x)))),
-            (Num::Matrix(l), Num::Float(r))
-                => Ok(Num::Matrix(Box::new(l.apply_all_terms_float(|x| x / r)))),
             (Num::Matrix(l), Num::Matrix(r)) => {
-                match l.apply_all_terms_matrix(r, |x, y| x / y) {
+                match l.apply_all_terms_matrix(r, |x, y| x.supported_div(y))? {
                     None => Err(format!("Unsupported different sizes operator {} / {}", self, rhs)),
                     Some(m) => Ok(Num::Matrix(Box::new(m))),
                 }
             },
-            _ => Err(format!("Unsupported operator ({}) / ({})", self, rhs)),
+            (l, Num::Matrix(r))
+                => Ok(Num::Matrix(Box::new(r.try_apply_all_terms(|x| l.supported_div(x))?))),
+            (Num::Matrix(l), r)
+                => Ok(Num::Matrix(Box::new(l.try_apply_all_terms(|x| x.supported_div(r))?))),
+            (l, r) if l.is_real() && r.is_real() => Ok(l.real_div(r)),
+            (_, Num::Complex(r)) => {
+                let (a, b) = self.to_parts();
+                let v = r.r.real_mul(&r.r).real_add(&r.z.real_mul(&r.z));
+                Ok(Self::from_two_num(
+                    a.real_mul(&r.r).real_add(&b.real_mul(&r.z)).real_div(&v),
+                    b.real_mul(&r.r).real_sub(&a.real_mul(&r.z)).real_div(&v),
+                ))
+            },
+            _ => {
+                let (a, b) = self.to_parts();
+                Ok(Self::from_two_num(a.real_div(rhs), b.real_div(rhs)))
+            },
         }
     }
 
 
     pub fn supported_rem(&self, rhs: &Num) -> Result<Num, String> {
         match (self, rhs) {
-            (Num::Float(l), Num::Float(r))
-                => Ok(Num::Float(l.rem_euclid(*r))),
-            (Num::Complex(l), Num::Float(r))
-                => Ok(Num::from_two_float(l.r.rem_euclid(*r), l.z.rem_euclid(*r))),
+            (l, r) if l.is_real() && r.is_real() => Ok(l.real_rem(r)),
+            (Num::Complex(l), r) if r.is_real()
+                => Ok(Self::from_two_num(l.r.real_rem(r), l.z.real_rem(r))),
             _ => Err(format!("Unsupported operator ({}) % ({})", self, rhs))
         }
     }
 
 
+    fn rational_pow(lhs: &Rational, rhs: &Rational) -> Num {
+        if rhs.is_integer() {
+            if let Some(exp) = rhs.numer().to_i64() {
+                if lhs.bits() <= 1 || lhs.bits().saturating_mul(exp.unsigned_abs()) <= MAX_BITS {
+                    if let Some(q) = lhs.pow(exp) {
+                        return Self::from_rational(q)
+                    }
+                }
+            }
+        } else if !lhs.is_negative() {
+            if let Some(root) = rhs.denom().to_i64()
+                    .filter(|n| *n as usize <= MAX_POW)
+                    .and_then(|n| lhs.exact_root(n as u64)) {
+                return Self::rational_pow(&root, &Rational::from_bigint(rhs.numer().clone()))
+            }
+        }
+        Num::Float(lhs.to_f64().powf(rhs.to_f64()))
+    }
+
+
+    fn one_like(&self) -> Num {
+        match self {
+            Num::Float(_) => Num::Float(1.0),
+            Num::Complex(c) if matches!(c.r, Num::Float(_)) => Num::Float(1.0),
+            _ => Self::from_i64(1),
+        }
+    }
+
+
+    /// `self` multiplied by itself `pow` times (`pow` >= 1), by repeated squaring.
+    fn repeated_mul(&self, pow: usize, mul: fn(&Num, &Num) -> Result<Num, String>) -> Result<Num, String> {
+        let mut num = self.clone();
+        let mut base = self.clone();
+        let mut pow = pow - 1;
+        while pow > 0 {
+            if pow & 1 == 1 {
+                num = mul(&num, &base)?;
+                num.checked_value()?;
+            }
+            pow >>= 1;
+            if pow > 0 {
+                base = mul(&base, &base)?;
+                base.checked_value()?;
+            }
+        }
+        Ok(num)
+    }
+
+
     pub fn supported_pow(&self, rhs: &Num) -> Result<Num, String> {
         match (self, rhs) {
-            (Num::Float(l), Num::Float(r)) => {
-                Ok(Num::Float(l.powf(*r)))
+            (Num::Rational(l), Num::Rational(r)) if !l.is_negative() || r.is_integer()
+                => Ok(Self::rational_pow(l, r)),
+            (l, r) if l.is_real() && r.is_real() && (!l.is_sign_negative() || r.to_int_value().is_some())
+                => Ok(Num::Float(l.float_value().powf(r.float_value()))),
+            (Num::Complex(_), r) if r.to_int_value().is_some_and(|n| n.unsigned_abs() as usize <= MAX_POW) => {
+                let pow = r.to_int_value().unwrap();
+                if pow == 0 {
+                    Ok(self.one_like())
+                } else if pow < 0 {
+                    self.one_like().supported_div(&self.repeated_mul(pow.unsigned_abs() as usize, Self::supported_mul)?)
+                } else {
+                    self.repeated_mul(pow as usize, Self::supported_mul)
+                }
             },
-            (Num::Complex(l), Num::Float(r)) => {
-                if !Self::is_int_value(*r) || (*r != 0.0 && r.is_sign_negative()) {
-                    Err(format!("Unsupported operator ({}) ^ ({})", self, rhs))
+            (Num::Matrix(m), r) if r.is_real() => {
+                let pow = match r.to_int_value() {
+                    Some(n) if n.unsigned_abs() as usize <= MAX_POW => n,
+                    Some(_) => return Err(format!("Unsupported operator ({}) ^ ({}): the exponent exceeds {}", self, rhs, MAX_POW)),
+                    None => return Err(format!("Unsupported operator ({}) ^ ({})", self, rhs)),
+                };
+                if !m.is_square() {
+                    return Err(format!("Unsupported sizes operator {} ^ {}", self, rhs))
+                }
+                let base = if pow < 0 {
+                    Num::Matrix(Box::new(m.inverse()?))
                 } else {
-                    let pow = *r as usize;
-                    if pow == 0 {
-                        Ok(Num::Float(1.0))
-                    } else if pow > MAX_POW {
-                        Err(format!("Unsupported operator ({}) ^ ({})", self, rhs))
-                    } else {
-                        let mut num = Num::from_two_float(l.r, l.z);
-                        for _ in 1..pow {
-                            num = num.supported_mul(&num)?;
-                            num.checked_value()?;
-                        }
-                        Ok(num)
-                    }
+                    self.clone()
+                };
+                if pow == 0 {
+                    Ok(Num::Matrix(Box::new(Matrix::identity(m.size.0))))
+                } else {
+                    base.repeated_mul(pow.unsigned_abs() as usize, Self::supported_matrix_mul)
                 }
             },
+            (l, r) if !l.is_matrix() && !r.is_matrix() => self.complex_pow(rhs),
             _ => Err(format!("Unsupported operator ({}) ^ ({})", self, rhs))
         }
     }
 
 
+    /// Principal value of `self ^ rhs`, that is exp(rhs * log(self)).
+    fn complex_pow(&self, rhs: &Num) -> Result<Num, String> {
+        let (a, b) = self.to_parts();
+        let (a, b) = (a.float_value(), b.float_value());
+        let (c, d) = rhs.to_parts();
+        let (c, d) = (c.float_value(), d.float_value());
+        if self.is_zero() {
+            return if c > 0.0 {
+                Ok(Num::Float(0.0))
+            } else {
+                Err(format!("Unsupported operator ({}) ^ ({})", self, rhs))
+            }
+        }
+        let log_modulus = a.hypot(b).ln();
+        let argument = b.atan2(a);
+        let modulus = (c * log_modulus - d * argument).exp();
+        let argument = d * log_modulus + c * argument;
+        Ok(Self::from_two_float_trimmed(modulus * argument.cos(), modulus * argument.sin()))
+    }
+
+
     pub fn supported_matrix_mul(&self, rhs: &Num) -> Result<Num, String> {
         match (self, rhs) {
             (Num::Matrix(l), Num::Matrix(r)) => {
-                match l.matrix_mul(r) {
+                match l.matrix_mul(r)? {
                     Some(m) => Ok(Num::Matrix(Box::new(m))),
                     None => Err(format!("Unsupported sizes operator {} ** {}", self, rhs)),
                 }
@@ -433,11 +891,44 @@ impl Num {
     }
 
 
+    /// `self // rhs`, that is `self * inv(rhs)`.
+    pub fn supported_matrix_div(&self, rhs: &Num) -> Result<Num, String> {
+        match rhs {
+            Num::Matrix(r) => {
+                let inv = Num::Matrix(Box::new(r.inverse()?));
+                match self {
+                    Num::Matrix(_) => self.supported_matrix_mul(&inv)
+                        .map_err(|_| format!("Unsupported sizes operator {} // {}", self, rhs)),
+                    _ => self.supported_mul(&inv),
+                }
+            },
+            _ => Err(format!("Unsupported operator ({}) // ({})", self, rhs))
+        }
+    }
+
+
+    /// `self \ rhs`, that is `inv(self) * rhs`.
+    pub fn supported_matrix_left_div(&self, rhs: &Num) -> Result<Num, String> {
+        match self {
+            Num::Matrix(l) => {
+                let inv = Num::Matrix(Box::new(l.inverse()?));
+                match rhs {
+                    Num::Matrix(_) => inv.supported_matrix_mul(rhs)
+                        .map_err(|_| format!("Unsupported sizes operator {} \\ {}", self, rhs)),
+                    _ => inv.supported_mul(rhs),
+                }
+            },
+            _ => Err(format!("Unsupported operator ({}) \\ ({})", self, rhs))
+        }
+    }
+
+
     pub fn is_need_sign_reverse(&self) -> bool {
         match self {
             Num::Float(n) => n.is_sign_negative(),
+            Num::Rational(q) => q.is_negative(),
             Num::Complex(n) => {
-                (n.r == 0.0 && n.z.is_sign_negative())
+                (n.r.is_zero() && n.z.is_sign_negative())
                     || n.r.is_sign_negative()
             }
             Num::Matrix(_) => false,
@@ -450,7 +941,9 @@ impl Num {
         } else {
             match &self {
                 Num::Float(_) => false,
-                Num::Complex(n) => n.r != 0.0 && n.z != 0.0,
+                Num::Rational(q) => !q.is_integer(),
+                Num::Complex(n) => (!n.r.is_zero() && !n.z.is_zero())
+                    || matches!(&n.z, Num::Rational(q) if !q.is_integer()),
                 Num::Matrix(_) => false
             }
         }
@@ -459,15 +952,30 @@ impl Num {
     pub fn reverse_sign(&self) -> Num {
         match &self {
             Num::Float(n) => Num::Float(-n),
-            Num::Complex(n) => Num::from_two_float_to_complex(-n.r, -n.z),
-            Num::Matrix(n) => Num::Matrix(Box::new(n.apply_all_terms_float(|x| -x)))
+            Num::Rational(q) => Num::Rational(Box::new(q.neg())),
+            Num::Complex(n) => Num::Complex(Box::new(Complex { r: n.r.reverse_sign(), z: n.z.reverse_sign() })),
+            Num::Matrix(n) => Num::Matrix(Box::new(n.apply_all_terms(|x| x.reverse_sign())))
+        }
+    }
+
+    pub fn to_show_value_string(&self, form: ComplexForm) -> String {
+        match self {
+            Num::Matrix(m) => m.to_string_rich(form),
+            _ => format!("  {}", self.to_string_form(form)),
         }
     }
 
-    pub fn to_show_value_string(&self) -> String {
+    pub fn to_string_form(&self, form: ComplexForm) -> String {
         match self {
-            Num::Matrix(m) => m.to_string_rich(),
-            _ => format!("  {}", self),
+            Num::Complex(c) => c.to_string_form(form),
+            Num::Matrix(m) => {
+                let rows: Vec<String> = m.elem.iter().map(|row| {
+                    let row: Vec<String> = row.iter().map(|n| n.to_string_form(form)).collect();
+                    format!("[{}]", row.join(","))
+                }).collect();
+                format!("[{}]", rows.join(";"))
+            },
+            _ => format!("{}", self),
         }
     }
 
@@ -478,10 +986,27 @@ impl Num {
 }
 
 
+/// Float and Rational values compare by their exact value.
+impl PartialEq for Num {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Num::Float(l), Num::Float(r)) => l == r,
+            (Num::Rational(l), Num::Rational(r)) => l == r,
+            (Num::Float(f), Num::Rational(q)) | (Num::Rational(q), Num::Float(f))
+                => Rational::from_f64(*f).as_ref() == Some(q.as_ref()),
+            (Num::Complex(l), Num::Complex(r)) => l == r,
+            (Num::Matrix(l), Num::Matrix(r)) => l == r,
+            _ => false,
+        }
+    }
+}
+
+
 impl fmt::Display for Num {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Num::Float(n) => write!(f, "{}", n),
+            Num::Rational(n) => write!(f, "{}", n),
             Num::Complex(n) => write!(f, "{}", n),
             Num::Matrix(n) => write!(f, "{}", n),
         }
@@ -526,7 +1051,7 @@ mod tests {
         let r = 0.0;
         let z = 1.0;
         assert_eq!(Num::from_two_float(r, z),
-                   Num::Complex(Box::new(Complex { r, z })));
+                   Num::Complex(Box::new(Complex { r: Num::Float(r), z: Num::Float(z) })));
     }
 
     #[test]
@@ -555,7 +1080,7 @@ mod tests {
         match num {
             Num::Matrix(m) => {
                 assert_eq!(m.size(), &(1, 1));
-                assert_eq!(m.at(0, 0), Some(1.0));
+                assert_eq!(m.at(0, 0), Some(&Num::Float(1.0)));
             },
             _ => panic!()
         }
@@ -688,6 +1213,16 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn supported_add_complex_matrix_matrix() -> Result<(), String> {
+        let lhs = Num::from_num_vec(vec![vec![Num::Float(1.0), Num::new_complex()]])?;
+        let rhs = Num::from_num_vec(vec![vec![Num::new_complex(), Num::from_two_float(0.0, -1.0)]])?;
+        let ans = Num::from_num_vec(vec![vec![Num::from_two_float(1.0, 1.0), Num::Float(0.0)]])?;
+        assert_eq!(lhs.supported_add(&rhs),
+                   Ok(ans));
+        Ok(())
+    }
+
     #[test]
     fn supported_add_error_matrix_matrix_diff_size() -> Result<(), String> {
         let vec = vec![vec![1.0; 3]; 2];
@@ -695,7 +1230,7 @@ mod tests {
         let vec = vec![vec![2.0; 2]; 2];
         let rhs = Num::from_vec(vec)?;
         assert_eq!(lhs.supported_add(&rhs),
-            Err(format!("Unsupported different sizes operator [[1,1,1];[1,1,1]] + [[2,2];[2,2]]")));
+            Err("Unsupported different sizes operator [[1,1,1];[1,1,1]] + [[2,2];[2,2]]".to_string()));
         Ok(())
     }
 
@@ -705,7 +1240,7 @@ mod tests {
         let lhs = Num::from_vec(vec)?;
         let rhs = Num::from_two_float(3.0, 1.0);
         assert_eq!(lhs.supported_add(&rhs),
-            Err(format!("Unsupported operator [[1,1,1];[1,1,1]] + 3 + i")));
+            Err("Unsupported operator [[1,1,1];[1,1,1]] + 3 + i".to_string()));
         Ok(())
     }
 
@@ -771,7 +1306,7 @@ mod tests {
         let vec = vec![vec![2.0; 2]; 2];
         let rhs = Num::from_vec(vec)?;
         assert_eq!(lhs.supported_sub(&rhs),
-            Err(format!("Unsupported different sizes operator [[1,1,1];[1,1,1]] - [[2,2];[2,2]]")));
+            Err("Unsupported different sizes operator [[1,1,1];[1,1,1]] - [[2,2];[2,2]]".to_string()));
         Ok(())
     }
 
@@ -781,7 +1316,7 @@ mod tests {
         let lhs = Num::from_vec(vec)?;
         let rhs = Num::from_two_float(3.0, 1.0);
         assert_eq!(lhs.supported_sub(&rhs),
-            Err(format!("Unsupported operator ([[1,1,1];[1,1,1]]) - (3 + i)")));
+            Err("Unsupported operator ([[1,1,1];[1,1,1]]) - (3 + i)".to_string()));
         Ok(())
     }
 
@@ -873,17 +1408,28 @@ mod tests {
         let vec = vec![vec![2.0; 2]; 2];
         let rhs = Num::from_vec(vec)?;
         assert_eq!(lhs.supported_mul(&rhs),
-            Err(format!("Unsupported different sizes operator [[1,1,1];[1,1,1]] * [[2,2];[2,2]]")));
+            Err("Unsupported different sizes operator [[1,1,1];[1,1,1]] * [[2,2];[2,2]]".to_string()));
         Ok(())
     }
 
     #[test]
-    fn supported_mul_error_unsupported() -> Result<(), String> {
+    fn supported_mul_matrix_complex() -> Result<(), String> {
         let vec = vec![vec![1.0; 3]; 2];
         let lhs = Num::from_vec(vec)?;
         let rhs = Num::from_two_float(3.0, 1.0);
+        let ans = Num::from_num_vec(vec![vec![Num::from_two_float(3.0, 1.0); 3]; 2])?;
+        assert_eq!(lhs.supported_mul(&rhs),
+                   Ok(ans));
+        Ok(())
+    }
+
+    #[test]
+    fn supported_mul_complex_matrix() -> Result<(), String> {
+        let lhs = Num::new_complex();
+        let rhs = Num::from_num_vec(vec![vec![Num::Float(2.0), Num::from_two_float(0.0, 1.0)]])?;
+        let ans = Num::from_num_vec(vec![vec![Num::from_two_float(0.0, 2.0), Num::Float(-1.0)]])?;
         assert_eq!(lhs.supported_mul(&rhs),
-            Err(format!("Unsupported operator ([[1,1,1];[1,1,1]]) * (3 + i)")));
+                   Ok(ans));
         Ok(())
     }
 
@@ -992,17 +1538,18 @@ mod tests {
         let vec = vec![vec![2.0; 2]; 2];
         let rhs = Num::from_vec(vec)?;
         assert_eq!(lhs.supported_div(&rhs),
-            Err(format!("Unsupported different sizes operator [[1,1,1];[1,1,1]] / [[2,2];[2,2]]")));
+            Err("Unsupported different sizes operator [[1,1,1];[1,1,1]] / [[2,2];[2,2]]".to_string()));
         Ok(())
     }
 
     #[test]
-    fn supported_div_error_unsupported() -> Result<(), String> {
-        let vec = vec![vec![1.0; 3]; 2];
+    fn supported_div_matrix_complex() -> Result<(), String> {
+        let vec = vec![vec![2.0; 3]; 2];
         let lhs = Num::from_vec(vec)?;
-        let rhs = Num::from_two_float(3.0, 1.0);
+        let rhs = Num::from_two_float(1.0, 1.0);
+        let ans = Num::from_num_vec(vec![vec![Num::from_two_float(1.0, -1.0); 3]; 2])?;
         assert_eq!(lhs.supported_div(&rhs),
-            Err(format!("Unsupported operator ([[1,1,1];[1,1,1]]) / (3 + i)")));
+                   Ok(ans));
         Ok(())
     }
 
@@ -1116,7 +1663,7 @@ mod tests {
         let lhs = Num::from_vec(vec)?;
         let rhs = Num::from_two_float(3.0, 1.0);
         assert_eq!(lhs.supported_rem(&rhs),
-            Err(format!("Unsupported operator ([[1,1,1];[1,1,1]]) % (3 + i)")));
+            Err("Unsupported operator ([[1,1,1];[1,1,1]]) % (3 + i)".to_string()));
         Ok(())
     }
 
@@ -1188,32 +1735,63 @@ mod tests {
     fn supported_pow_error_complex_float_max() {
         let lhs = Num::from_two_float(2.0, 3.0);
         let rhs = Num::Float(1001.0);
-        assert_eq!(lhs.supported_pow(&rhs),
-            Err(format!("Unsupported operator ({}) ^ ({})", lhs, rhs)));
+        assert!(lhs.supported_pow(&rhs).unwrap().checked_value().is_err());
     }
 
     #[test]
-    fn supported_pow_error_complex_float_non_integer() {
-        let lhs = Num::from_two_float(2.0, 3.0);
-        let rhs = Num::Float(2.5);
+    fn supported_pow_complex_float_negative() {
+        let lhs = Num::from_two_float(0.0, 2.0);
+        let rhs = Num::Float(-2.0);
         assert_eq!(lhs.supported_pow(&rhs),
-            Err(format!("Unsupported operator ({}) ^ ({})", lhs, rhs)));
+                    Ok(Num::Float(-0.25)));
+    }
+
+    #[test]
+    fn supported_pow_complex_float_non_integer() {
+        let lhs = Num::from_two_float(0.0, 2.0);
+        let rhs = Num::Float(0.5);
+        let diff = lhs.supported_pow(&rhs).unwrap().supported_sub(&Num::from_two_float(1.0, 1.0)).unwrap();
+        assert!(diff.magnitude() < 1e-12);
+    }
+
+    #[test]
+    fn supported_pow_float_complex() {
+        let lhs = Num::Float(2.0);
+        let rhs = Num::from_two_float(1.0, 1.0);
+        let ans = Num::from_two_float(2.0 * 2f64.ln().cos(), 2.0 * 2f64.ln().sin());
+        assert_eq!(lhs.supported_pow(&rhs), Ok(ans));
     }
 
     #[test]
-    fn supported_pow_error_float_complex() {
+    fn supported_pow_zero_complex() {
         let lhs = Num::Float(0.0);
         let rhs = Num::from_two_float(2.0, 3.0);
+        assert_eq!(lhs.supported_pow(&rhs), Ok(Num::Float(0.0)));
+    }
+
+    #[test]
+    fn supported_pow_error_zero_complex() {
+        let lhs = Num::Float(0.0);
+        let rhs = Num::from_two_float(-2.0, 3.0);
         assert_eq!(lhs.supported_pow(&rhs),
                    Err(format!("Unsupported operator ({}) ^ ({})", lhs, rhs)));
     }
 
     #[test]
-    fn supported_pow_error_complex_complex() {
-        let lhs = Num::from_two_float(1.0, 3.0);
-        let rhs = Num::from_two_float(2.0, 3.0);
+    fn supported_pow_complex_complex() {
+        let lhs = Num::new_complex();
+        let rhs = Num::new_complex();
         assert_eq!(lhs.supported_pow(&rhs),
-                   Err(format!("Unsupported operator ({}) ^ ({})", lhs, rhs)));
+                   Ok(Num::Float((-std::f64::consts::FRAC_PI_2).exp())));
+    }
+
+    #[test]
+    fn supported_pow_negative_fraction() {
+        let lhs = Num::from_i64(-8);
+        let rhs = rational(1, 3);
+        let value = lhs.supported_pow(&rhs).unwrap();
+        let diff = value.supported_sub(&Num::from_two_float(1.0, 3f64.sqrt())).unwrap();
+        assert!(diff.magnitude() < 1e-12);
     }
 
     #[test]
@@ -1222,7 +1800,7 @@ mod tests {
         let lhs = Num::from_vec(vec)?;
         let rhs = Num::from_two_float(3.0, 1.0);
         assert_eq!(lhs.supported_pow(&rhs),
-            Err(format!("Unsupported operator ([[1,1,1];[1,1,1]]) ^ (3 + i)")));
+            Err("Unsupported operator ([[1,1,1];[1,1,1]]) ^ (3 + i)".to_string()));
         Ok(())
     }
 
@@ -1242,6 +1820,19 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn supported_matrix_mul_complex_matrix() -> Result<(), String> {
+        let lhs = Num::from_num_vec(vec![
+            vec![Num::Float(1.0), Num::new_complex()],
+            vec![Num::Float(0.0), Num::Float(1.0)],
+        ])?;
+        let rhs = Num::from_num_vec(vec![vec![Num::new_complex()], vec![Num::Float(1.0)]])?;
+        let ans = Num::from_num_vec(vec![vec![Num::from_two_float(0.0, 2.0)], vec![Num::Float(1.0)]])?;
+        assert_eq!(lhs.supported_matrix_mul(&rhs),
+                   Ok(ans));
+        Ok(())
+    }
+
     #[test]
     fn supported_matrix_mul_error_matrix_matrix_diff_size() -> Result<(), String> {
         let vec = vec![vec![1.0; 3]; 2];
@@ -1249,7 +1840,7 @@ mod tests {
         let vec = vec![vec![2.0; 2]; 2];
         let rhs = Num::from_vec(vec)?;
         assert_eq!(lhs.supported_matrix_mul(&rhs),
-            Err(format!("Unsupported sizes operator [[1,1,1];[1,1,1]] ** [[2,2];[2,2]]")));
+            Err("Unsupported sizes operator [[1,1,1];[1,1,1]] ** [[2,2];[2,2]]".to_string()));
         Ok(())
     }
 
@@ -1259,7 +1850,84 @@ mod tests {
         let lhs = Num::from_vec(vec)?;
         let rhs = Num::from_two_float(3.0, 1.0);
         assert_eq!(lhs.supported_matrix_mul(&rhs),
-            Err(format!("Unsupported operator ([[1,1,1];[1,1,1]]) ** (3 + i)")));
+            Err("Unsupported operator ([[1,1,1];[1,1,1]]) ** (3 + i)".to_string()));
+        Ok(())
+    }
+
+    fn int_matrix(vec: Vec<Vec<i64>>) -> Num {
+        Num::from_num_vec(vec.into_iter()
+            .map(|row| row.into_iter().map(Num::from_i64).collect())
+            .collect()).unwrap()
+    }
+
+    #[test]
+    fn determinant_pivot() -> Result<(), String> {
+        let m = Matrix::from_vec(vec![
+            vec![Num::from_i64(0), Num::from_i64(1), Num::from_i64(2)],
+            vec![Num::from_i64(1), Num::from_i64(0), Num::from_i64(3)],
+            vec![Num::from_i64(4), Num::from_i64(-3), Num::from_i64(8)],
+        ]).unwrap();
+        assert_eq!(m.determinant(), Ok(Num::from_i64(-2)));
+        Ok(())
+    }
+
+    #[test]
+    fn determinant_singular() -> Result<(), String> {
+        let m = Matrix::from_vec(vec![vec![Num::Float(2f64.sqrt()), Num::Float(2.0)],
+                                      vec![Num::Float(1.0), Num::Float(2f64.sqrt())]]).unwrap();
+        assert_eq!(m.determinant(), Ok(Num::zero()));
+        Ok(())
+    }
+
+    #[test]
+    fn inverse_exact() -> Result<(), String> {
+        let m = Matrix::from_vec(vec![vec![Num::from_i64(1), Num::from_i64(2)],
+                                      vec![Num::from_i64(3), Num::from_i64(4)]]).unwrap();
+        let ans = Matrix::from_vec(vec![vec![Num::from_i64(-2), Num::from_i64(1)],
+                                        vec![rational(3, 2), rational(-1, 2)]]).unwrap();
+        assert_eq!(m.inverse(), Ok(ans));
+        Ok(())
+    }
+
+    #[test]
+    fn inverse_error_singular() -> Result<(), String> {
+        let m = Matrix::from_vec(vec![vec![Num::from_i64(1), Num::from_i64(2)],
+                                      vec![Num::from_i64(2), Num::from_i64(4)]]).unwrap();
+        assert_eq!(m.inverse(), Err("error: the matrix is singular".to_string()));
+        Ok(())
+    }
+
+    #[test]
+    fn inverse_error_not_square() -> Result<(), String> {
+        let m = Matrix::from_vec(vec![vec![Num::from_i64(1), Num::from_i64(2)]]).unwrap();
+        assert_eq!(m.inverse(), Err("error: inv requires a square matrix".to_string()));
+        Ok(())
+    }
+
+    #[test]
+    fn supported_matrix_div_matrix_matrix() -> Result<(), String> {
+        let lhs = int_matrix(vec![vec![5, 6], vec![7, 8]]);
+        let rhs = int_matrix(vec![vec![1, 2], vec![3, 4]]);
+        let ans = int_matrix(vec![vec![-1, 2], vec![-2, 3]]);
+        assert_eq!(lhs.supported_matrix_div(&rhs), Ok(ans));
+        Ok(())
+    }
+
+    #[test]
+    fn supported_matrix_left_div_matrix_matrix() -> Result<(), String> {
+        let lhs = int_matrix(vec![vec![1, 2], vec![3, 4]]);
+        let rhs = int_matrix(vec![vec![5, 6], vec![7, 8]]);
+        let ans = int_matrix(vec![vec![-3, -4], vec![4, 5]]);
+        assert_eq!(lhs.supported_matrix_left_div(&rhs), Ok(ans));
+        Ok(())
+    }
+
+    #[test]
+    fn supported_matrix_div_error_unsupported() -> Result<(), String> {
+        let lhs = Num::from_vec(vec![vec![1.0, 2.0], vec![3.0, 4.0]])?;
+        let rhs = Num::Float(2.0);
+        assert_eq!(lhs.supported_matrix_div(&rhs),
+            Err("Unsupported operator ([[1,2];[3,4]]) // (2)".to_string()));
         Ok(())
     }
 
@@ -1351,7 +2019,7 @@ mod tests {
     fn to_show_value_string_small() -> Result<(), String> {
         let vec = vec![vec![1.0]];
         let num = Num::from_vec(vec)?;
-        assert_eq!(num.to_show_value_string(), "  [ 1 ]".to_string());
+        assert_eq!(num.to_show_value_string(ComplexForm::Rectangular), "  [ 1 ]".to_string());
         Ok(())
     }
 
@@ -1359,7 +2027,214 @@ mod tests {
     fn to_show_value_string_normal() -> Result<(), String> {
         let vec = vec![vec![1.0; 3]; 2];
         let num = Num::from_vec(vec)?;
-        assert_eq!(num.to_show_value_string(), "  [ 1 , 1 , 1 ]\n  [ 1 , 1 , 1 ]".to_string());
+        assert_eq!(num.to_show_value_string(ComplexForm::Rectangular), "  [ 1 , 1 , 1 ]\n  [ 1 , 1 , 1 ]".to_string());
+        Ok(())
+    }
+
+    #[test]
+    fn to_show_value_string_complex() -> Result<(), String> {
+        let num = Num::from_num_vec(vec![
+            vec![Num::Float(1.0), Num::new_complex()],
+            vec![Num::Float(0.0), Num::from_two_float(1.0, -2.0)],
+        ])?;
+        assert_eq!(num.to_show_value_string(ComplexForm::Rectangular), "  [ 1 , i ]\n  [ 0 , 1 - 2i ]".to_string());
+        assert_eq!(format!("{}", num), "[[1,i];[0,1 - 2i]]".to_string());
         Ok(())
     }
+
+    fn rational(num: i64, den: i64) -> Num {
+        Num::from_rational(Rational::from_i64(num).div(&Rational::from_i64(den)).unwrap())
+    }
+
+    #[test]
+    fn from_string_rational() {
+        let string = "0.25".to_string();
+        assert!(matches!(Num::from_string(&string), Ok(Num::Rational(_))));
+        assert_eq!(Num::from_string(&string), Ok(rational(1, 4)));
+    }
+
+    #[test]
+    fn eq_float_rational_exact() {
+        assert_eq!(Num::Float(0.5), rational(1, 2));
+        assert_ne!(Num::Float(0.1), rational(1, 10));
+    }
+
+    #[test]
+    fn supported_add_rational_third() {
+        let third = rational(1, 3);
+        let sum = third.supported_add(&third).and_then(|n| n.supported_add(&third));
+        assert!(matches!(sum, Ok(Num::Rational(_))));
+        assert_eq!(sum, Ok(Num::from_i64(1)));
+    }
+
+    #[test]
+    fn supported_add_rational_float() {
+        let lhs = rational(1, 2);
+        let rhs = Num::Float(0.25);
+        assert!(matches!(lhs.supported_add(&rhs), Ok(Num::Float(_))));
+    }
+
+    #[test]
+    fn supported_div_rational_rational() {
+        let lhs = Num::from_i64(3);
+        let rhs = Num::from_i64(-6);
+        assert_eq!(lhs.supported_div(&rhs), Ok(rational(-1, 2)));
+    }
+
+    #[test]
+    fn supported_div_rational_zero() {
+        let lhs = Num::from_i64(1);
+        let rhs = Num::zero();
+        assert_eq!(lhs.supported_div(&rhs).unwrap().checked_value(),
+                   Err(format!("The calculation resulted in '{}'.", f64::INFINITY)));
+    }
+
+    #[test]
+    fn supported_mul_rational_complex() {
+        let lhs = rational(1, 2);
+        let rhs = Num::from_two_num(Num::from_i64(1), Num::from_i64(3));
+        assert_eq!(lhs.supported_mul(&rhs),
+                   Ok(Num::from_two_num(rational(1, 2), rational(3, 2))));
+    }
+
+    #[test]
+    fn supported_pow_rational_negative_integer() {
+        let lhs = rational(-2, 3);
+        let rhs = Num::from_i64(-3);
+        assert_eq!(lhs.supported_pow(&rhs), Ok(rational(-27, 8)));
+    }
+
+    #[test]
+    fn supported_pow_rational_exact_root() {
+        let lhs = rational(4, 9);
+        let rhs = rational(3, 2);
+        assert_eq!(lhs.supported_pow(&rhs), Ok(rational(8, 27)));
+    }
+
+    #[test]
+    fn supported_pow_rational_irrational() {
+        let lhs = Num::from_i64(2);
+        let rhs = rational(1, 2);
+        assert_eq!(lhs.supported_pow(&rhs), Ok(Num::Float(2f64.sqrt())));
+    }
+
+    #[test]
+    fn supported_pow_complex_odd() {
+        let lhs = Num::from_two_num(Num::from_i64(1), Num::from_i64(1));
+        let rhs = Num::from_i64(3);
+        assert_eq!(lhs.supported_pow(&rhs),
+                   Ok(Num::from_two_num(Num::from_i64(-2), Num::from_i64(2))));
+    }
+
+    #[test]
+    fn supported_rem_rational_big() {
+        let lhs = Num::from_i64(2).supported_pow(&Num::from_i64(200)).unwrap();
+        let rhs = Num::from_i64(7);
+        assert_eq!(lhs.supported_rem(&rhs), Ok(Num::from_i64(4)));
+    }
+
+    #[test]
+    fn supported_rem_rational_zero() {
+        let lhs = Num::from_i64(1);
+        let rhs = Num::zero();
+        assert_eq!(lhs.supported_rem(&rhs).unwrap().checked_value(),
+                   Err(format!("The calculation resulted in '{}'.", f64::NAN)));
+    }
+
+    #[test]
+    fn supported_rem_complex_rational() {
+        let lhs = Num::from_two_num(Num::from_i64(-5), rational(13, 2));
+        let rhs = Num::from_i64(4);
+        assert_eq!(lhs.supported_rem(&rhs),
+                   Ok(Num::from_two_num(Num::from_i64(3), rational(5, 2))));
+    }
+
+    #[test]
+    fn supported_pow_big_integer() {
+        let lhs = Num::from_i64(2);
+        let rhs = Num::from_i64(200);
+        assert_eq!(format!("{}", lhs.supported_pow(&rhs).unwrap()),
+                   "1606938044258990275541962092341162602522202993782792835301376".to_string());
+    }
+
+    #[test]
+    fn supported_pow_matrix_int() {
+        let lhs = int_matrix(vec![vec![1, 1], vec![1, 0]]);
+        let rhs = Num::from_i64(10);
+        assert_eq!(lhs.supported_pow(&rhs), Ok(int_matrix(vec![vec![89, 55], vec![55, 34]])));
+    }
+
+    #[test]
+    fn supported_pow_matrix_zero() {
+        let lhs = int_matrix(vec![vec![2, 3], vec![4, 5]]);
+        let rhs = Num::zero();
+        assert_eq!(lhs.supported_pow(&rhs), Ok(int_matrix(vec![vec![1, 0], vec![0, 1]])));
+    }
+
+    #[test]
+    fn supported_pow_matrix_negative() {
+        let lhs = int_matrix(vec![vec![1, 1], vec![0, 1]]);
+        let rhs = Num::from_i64(-3);
+        assert_eq!(lhs.supported_pow(&rhs), Ok(int_matrix(vec![vec![1, -3], vec![0, 1]])));
+    }
+
+    #[test]
+    fn supported_pow_matrix_error_singular() {
+        let lhs = int_matrix(vec![vec![1, 2], vec![2, 4]]);
+        let rhs = Num::from_i64(-1);
+        assert_eq!(lhs.supported_pow(&rhs), Err("error: the matrix is singular".to_string()));
+    }
+
+    #[test]
+    fn supported_pow_matrix_error_not_square() {
+        let lhs = int_matrix(vec![vec![1, 2]]);
+        let rhs = Num::from_i64(2);
+        assert_eq!(lhs.supported_pow(&rhs), Err("Unsupported sizes operator [[1,2]] ^ 2".to_string()));
+    }
+
+    #[test]
+    fn supported_pow_matrix_error_not_integer() {
+        let lhs = int_matrix(vec![vec![1, 2], vec![3, 4]]);
+        let rhs = Num::Float(0.5);
+        assert_eq!(lhs.supported_pow(&rhs), Err("Unsupported operator ([[1,2];[3,4]]) ^ (0.5)".to_string()));
+        let rhs = Num::from_i64(1001);
+        assert_eq!(lhs.supported_pow(&rhs), Err("Unsupported operator ([[1,2];[3,4]]) ^ (1001): the exponent exceeds 1000".to_string()));
+        let rhs = Num::from_i64(-1001);
+        assert_eq!(lhs.supported_pow(&rhs), Err("Unsupported operator ([[1,2];[3,4]]) ^ (-1001): the exponent exceeds 1000".to_string()));
+    }
+
+    #[test]
+    fn to_string_form_polar() {
+        let num = Num::from_two_float(0.0, 2.0);
+        assert_eq!(num.to_string_form(ComplexForm::Polar), "2∠1.571".to_string());
+        assert_eq!(num.to_string_form(ComplexForm::Exponential), "2e^(1.571i)".to_string());
+        let num = Num::from_two_float(1.0, 1.0);
+        assert_eq!(num.to_string_form(ComplexForm::Polar), "1.414∠0.785".to_string());
+        let num = Num::from_two_float(-1.0, -0.0001);
+        assert_eq!(num.to_string_form(ComplexForm::Polar), "1∠-3.141".to_string());
+        assert_eq!(Num::Float(-1.0).to_string_form(ComplexForm::Polar), "-1".to_string());
+    }
+
+    #[test]
+    fn to_show_value_string_polar_matrix() -> Result<(), String> {
+        let num = Num::from_num_vec(vec![vec![Num::Float(1.0), Num::from_two_float(-3.0, 0.0)],
+                                         vec![Num::from_two_float(0.0, -1.0), Num::zero()]])?;
+        assert_eq!(num.to_show_value_string(ComplexForm::Exponential),
+                   "  [ 1 , -3 ]\n  [ 1e^(-1.571i) , 0 ]".to_string());
+        Ok(())
+    }
+
+    #[test]
+    fn fmt_rational() {
+        assert_eq!(format!("{}", rational(-4, 6)), "-2/3".to_string());
+        assert_eq!(format!("{}", rational(4, 2)), "2".to_string());
+    }
+
+    #[test]
+    fn fmt_complex_rational() {
+        let num = Num::from_two_num(rational(1, 3), rational(-3, 4));
+        assert_eq!(format!("{}", num), "1/3 - 3i/4".to_string());
+        let num = Num::from_two_num(Num::zero(), rational(1, 2));
+        assert_eq!(format!("{}", num), "i/2".to_string());
+    }
 }
@@ -0,0 +1,249 @@
+use std::ops::{Add, Sub, Mul, Div};
+
+
+const MAX_ITERATIONS: usize = 500;
+/// Largest relative spread of the approximations of one repeated root.
+const CLUSTER_LIMIT: f64 = 1e-2;
+const POLISH_STEPS: usize = 3;
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Complex64 {
+    pub re: f64,
+    pub im: f64,
+}
+
+
+impl Complex64 {
+    pub fn new(re: f64, im: f64) -> Complex64 {
+        Complex64 { re, im }
+    }
+
+    pub fn abs(&self) -> f64 {
+        self.re.hypot(self.im)
+    }
+
+    fn is_finite(&self) -> bool {
+        self.re.is_finite() && self.im.is_finite()
+    }
+}
+
+
+impl Add for Complex64 {
+    type Output = Complex64;
+
+    fn add(self, rhs: Complex64) -> Complex64 {
+        Complex64::new(self.re + rhs.re, self.im + rhs.im)
+    }
+}
+
+
+impl Sub for Complex64 {
+    type Output = Complex64;
+
+    fn sub(self, rhs: Complex64) -> Complex64 {
+        Complex64::new(self.re - rhs.re, self.im - rhs.im)
+    }
+}
+
+
+impl Mul for Complex64 {
+    type Output = Complex64;
+
+    fn mul(self, rhs: Complex64) -> Complex64 {
+        Complex64::new(self.re * rhs.re - self.im * rhs.im, self.re * rhs.im + self.im * rhs.re)
+    }
+}
+
+
+impl Div for Complex64 {
+    type Output = Complex64;
+
+    fn div(self, rhs: Complex64) -> Complex64 {
+        let norm = rhs.re * rhs.re + rhs.im * rhs.im;
+        Complex64::new((self.re * rhs.re + self.im * rhs.im) / norm, (self.im * rhs.re - self.re * rhs.im) / norm)
+    }
+}
+
+
+/// Value and derivative of the polynomial at `z` by Horner's scheme.
+fn evaluate(coefficients: &[f64], z: Complex64) -> (Complex64, Complex64) {
+    let zero = Complex64::new(0.0, 0.0);
+    coefficients.iter().rev().fold((zero, zero), |(value, derivative), c| {
+        (value * z + Complex64::new(*c, 0.0), derivative * z + value)
+    })
+}
+
+
+/// Bound on the rounding error of `evaluate` at `z`.
+fn rounding_bound(coefficients: &[f64], z: Complex64) -> f64 {
+    let modulus = z.abs();
+    let sum = coefficients.iter().rev().fold(0.0, |sum, c| sum * modulus + c.abs());
+    4.0 * f64::EPSILON * coefficients.len() as f64 * sum
+}
+
+
+/// Distinct roots of the polynomial, given lowest degree first with a nonzero
+/// leading coefficient, by the Aberth–Ehrlich iteration.
+/// Each root comes with its multiplicity and an estimate of its absolute error.
+pub fn aberth_roots(coefficients: &[f64]) -> Result<Vec<(Complex64, usize, f64)>, String> {
+    let degree = coefficients.len() - 1;
+    let lead = coefficients[degree];
+    let monic: Vec<f64> = coefficients.iter().map(|c| c / lead).collect();
+    let mut roots = initial_points(&monic);
+    let mut converged = vec![false; degree];
+    for _ in 0..MAX_ITERATIONS {
+        for k in 0..degree {
+            if converged[k] {
+                continue
+            }
+            let (value, derivative) = evaluate(&monic, roots[k]);
+            if value.abs() <= rounding_bound(&monic, roots[k]) {
+                converged[k] = true;
+                continue
+            }
+            let ratio = value / derivative;
+            let sum = (0..degree).filter(|j| *j != k)
+                .fold(Complex64::new(0.0, 0.0), |sum, j| sum + Complex64::new(1.0, 0.0) / (roots[k] - roots[j]));
+            let correction = ratio / (Complex64::new(1.0, 0.0) - ratio * sum);
+            if !correction.is_finite() {
+                converged[k] = true;
+                continue
+            }
+            roots[k] = roots[k] - correction;
+            if correction.abs() <= f64::EPSILON * roots[k].abs() {
+                converged[k] = true;
+            }
+        }
+        if converged.iter().all(|c| *c) {
+            break
+        }
+    }
+    if let Some(root) = roots.iter().find(|root| !root.is_finite()) {
+        return Err(format!("The calculation resulted in '{}'.", if root.re.is_finite() {root.im} else {root.re}))
+    }
+    Ok(cluster_roots(&monic, &roots))
+}
+
+
+/// Points on a circle enclosing every root (Fujiwara's bound), slightly rotated
+/// so that none of them starts on the real axis.
+fn initial_points(monic: &[f64]) -> Vec<Complex64> {
+    let degree = monic.len() - 1;
+    let radius = (0..degree)
+        .map(|i| {
+            let c = if i == 0 {monic[0].abs() / 2.0} else {monic[i].abs()};
+            c.powf(1.0 / (degree - i) as f64)
+        })
+        .fold(0.0, f64::max) * 2.0;
+    let radius = if radius > 0.0 {radius} else {1.0};
+    (0..degree).map(|k| {
+        let angle = 2.0 * std::f64::consts::PI * k as f64 / degree as f64 + 0.4;
+        Complex64::new(radius * angle.cos(), radius * angle.sin())
+    }).collect()
+}
+
+
+/// Group the approximations whose Newton inclusion disks overlap, which is how a
+/// repeated root shows up, and replace each group by its mean.
+fn cluster_roots(monic: &[f64], roots: &[Complex64]) -> Vec<(Complex64, usize, f64)> {
+    let degree = roots.len() as f64;
+    let radius = |z: Complex64| {
+        let (value, derivative) = evaluate(monic, z);
+        let limit = CLUSTER_LIMIT * z.abs().max(1.0);
+        if value.abs() == 0.0 {0.0} else {(degree * value.abs() / derivative.abs()).min(limit)}
+    };
+    let mut clusters: Vec<Vec<(Complex64, f64)>> = Vec::new();
+    for root in roots {
+        let r = radius(*root);
+        let found = clusters.iter_mut()
+            .find(|cluster| cluster.iter().any(|(z, s)| (*z - *root).abs() <= r + s));
+        match found {
+            Some(cluster) => cluster.push((*root, r)),
+            None => clusters.push(vec![(*root, r)]),
+        }
+    }
+    clusters.into_iter().map(|cluster| {
+        let count = cluster.len();
+        if count == 1 {
+            let (z, error) = polish(monic, cluster[0].0);
+            return (z, 1, error)
+        }
+        // a root of multiplicity m is a simple root of the (m-1)-th derivative
+        let sum = cluster.iter().fold(Complex64::new(0.0, 0.0), |sum, (z, _)| sum + *z);
+        let mean = sum / Complex64::new(count as f64, 0.0);
+        let spread = cluster.iter().map(|(z, _)| (*z - mean).abs()).fold(0.0, f64::max);
+        let (z, error) = polish(&derivative(monic, count - 1), mean);
+        if (z - mean).abs() <= spread {(z, count, error)} else {(mean, count, spread)}
+    }).collect()
+}
+
+
+fn derivative(coefficients: &[f64], order: usize) -> Vec<f64> {
+    let mut coefficients = coefficients.to_vec();
+    for _ in 0..order {
+        coefficients = coefficients.iter().enumerate().skip(1).map(|(i, c)| c * i as f64).collect();
+    }
+    coefficients
+}
+
+
+/// Newton steps kept while they reduce the residual; the last step size estimates the error.
+fn polish(monic: &[f64], z: Complex64) -> (Complex64, f64) {
+    let mut z = z;
+    let mut error = 0.0;
+    for _ in 0..POLISH_STEPS {
+        let (value, derivative) = evaluate(monic, z);
+        if value.abs() == 0.0 {
+            return (z, 0.0)
+        }
+        let step = value / derivative;
+        error = step.abs();
+        let next = z - step;
+        if !next.is_finite() || evaluate(monic, next).0.abs() >= value.abs() {
+            break
+        }
+        z = next;
+    }
+    (z, error)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted_roots(coefficients: &[f64]) -> Vec<(Complex64, usize, f64)> {
+        let mut roots = aberth_roots(coefficients).unwrap();
+        roots.sort_by(|a, b| a.0.re.total_cmp(&b.0.re).then(a.0.im.total_cmp(&b.0.im)));
+        roots
+    }
+
+    #[test]
+    fn aberth_roots_simple() {
+        // (x - 1)(x - 2)(x - 3)(x - 4)(x - 5)
+        let roots = sorted_roots(&[-120.0, 274.0, -225.0, 85.0, -15.0, 1.0]);
+        assert_eq!(roots.len(), 5);
+        for (i, (z, multiplicity, error)) in roots.iter().enumerate() {
+            assert!((z.re - (i + 1) as f64).abs() < 1e-9 && z.im.abs() < 1e-9);
+            assert!(*multiplicity == 1 && *error < 1e-9);
+        }
+    }
+
+    #[test]
+    fn aberth_roots_complex() {
+        // x^6 - 1
+        let roots = sorted_roots(&[-1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0]);
+        assert_eq!(roots.len(), 6);
+        assert!(roots.iter().all(|(z, _, _)| (z.abs() - 1.0).abs() < 1e-12));
+    }
+
+    #[test]
+    fn aberth_roots_repeated() {
+        // (x - 1)^3 (x + 2)^2
+        let roots = sorted_roots(&[-4.0, 8.0, -1.0, -5.0, 1.0, 1.0]);
+        assert_eq!(roots.len(), 2);
+        assert!((roots[0].0.re + 2.0).abs() < 1e-6 && roots[0].1 == 2);
+        assert!((roots[1].0.re - 1.0).abs() < 1e-4 && roots[1].1 == 3);
+    }
+}
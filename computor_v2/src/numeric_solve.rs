@@ -0,0 +1,240 @@
+use crate::binary_tree::BinaryTree;
+use crate::data_base::DataBase;
+use crate::derivative;
+use crate::expression::Expr;
+use crate::num::Num;
+use crate::parser::{Parser, Element};
+
+
+pub const SAMPLES: usize = 1000;
+const MAX_ITERATIONS: usize = 100;
+/// Largest residual, relative to the neighbouring samples, accepted for a root
+/// where the function touches zero without changing sign.
+const TOUCH_TOLERANCE: f64 = 1e-12;
+/// Largest residual, relative to the samples around a sign change, accepted for
+/// a root; a larger one is left by a jump or a pole.
+const CROSS_TOLERANCE: f64 = 1e-9;
+/// Relative distance under which two roots are treated as the same root.
+const MERGE_TOLERANCE: f64 = 1e-9;
+
+
+struct Function<'a> {
+    tree: BinaryTree<Element>,
+    derivative: Option<BinaryTree<Element>>,
+    variable: &'a String,
+    data_base: &'a DataBase,
+}
+
+
+impl Function<'_> {
+    /// None where the function is undefined or not real.
+    fn value(&self, x: f64) -> Option<f64> {
+        Self::real_value(&self.tree, self.variable, x, self.data_base)
+    }
+
+    fn derivative(&self, x: f64) -> Option<f64> {
+        match &self.derivative {
+            Some(tree) => Self::real_value(tree, self.variable, x, self.data_base),
+            None => {
+                let h = 1e-6 * x.abs().max(1.0);
+                Some((self.value(x + h)? - self.value(x - h)?) / (2.0 * h))
+            },
+        }
+    }
+
+    fn real_value(tree: &BinaryTree<Element>, variable: &String, x: f64, data_base: &DataBase) -> Option<f64> {
+        match Parser::calculation_at(tree, variable, &Num::Float(x), data_base) {
+            Ok(n) if n.is_real() && n.float_value().is_finite() => Some(n.float_value()),
+            _ => None,
+        }
+    }
+}
+
+
+/// Real roots of `tree = 0` in `[a, b]`: every sign change between samples is
+/// refined by Brent's method, and every sample where the function comes close
+/// to zero without crossing it by Newton's method.
+pub fn find_roots(tree: &BinaryTree<Element>, variable: &String, (a, b): (f64, f64), data_base: &DataBase) -> Vec<f64> {
+    let derivative = Expr::from_tree(tree)
+        .and_then(|expr| derivative::derivative(&expr, variable))
+        .map(|expr| expr.simplify().to_tree())
+        .ok();
+    let f = Function { tree: tree.clone(), derivative, variable, data_base };
+    let samples: Vec<(f64, Option<f64>)> = (0..=SAMPLES)
+        .map(|i| {
+            let x = if i == SAMPLES {b} else {a + (b - a) * i as f64 / SAMPLES as f64};
+            (x, f.value(x))
+        })
+        .collect();
+    let mut roots = Vec::new();
+    let mut zeros_end = 0;
+    for (i, (x, y)) in samples.iter().enumerate() {
+        let y = match y {
+            Some(y) => *y,
+            None => continue,
+        };
+        if y == 0.0 {
+            if i >= zeros_end {
+                zeros_end = samples[i..].iter().position(|(_, y)| *y != Some(0.0)).map_or(samples.len(), |n| i + n);
+                if let Some(root) = zero_samples_root(&samples, (i, zeros_end)) {
+                    roots.push(root);
+                }
+            }
+            continue
+        }
+        if let Some((next_x, Some(next_y))) = samples.get(i + 1) {
+            if y * next_y < 0.0 {
+                if let Some(root) = brent(&f, (*x, y), (*next_x, *next_y)) {
+                    roots.push(root);
+                }
+                continue
+            }
+        }
+        if let (Some((prev_x, Some(prev_y))), Some((next_x, Some(next_y)))) = (i.checked_sub(1).map(|j| &samples[j]), samples.get(i + 1)) {
+            if y * prev_y > 0.0 && y * next_y > 0.0 && y.abs() < prev_y.abs() && y.abs() < next_y.abs() {
+                let scale = prev_y.abs().max(next_y.abs()).max(1.0);
+                if let Some(root) = newton(&f, *x, (*prev_x, *next_x), scale) {
+                    roots.push(root);
+                }
+            }
+        }
+    }
+    roots.sort_by(f64::total_cmp);
+    roots.dedup_by(|a, b| (*a - *b).abs() <= MERGE_TOLERANCE * a.abs().max(b.abs()).max(1.0));
+    roots.into_iter().map(|x| x + 0.0).collect()
+}
+
+
+/// The root at the samples `start..end` that are exactly zero: a crossing when
+/// the samples around them have opposite signs, or a single zero next to
+/// samples well above the underflow range. A run of zeros left by a function
+/// decaying under the smallest float is not a root.
+fn zero_samples_root(samples: &[(f64, Option<f64>)], (start, end): (usize, usize)) -> Option<f64> {
+    let prev = start.checked_sub(1).and_then(|i| samples[i].1);
+    let next = samples.get(end).and_then(|(_, y)| *y);
+    match (prev, next) {
+        (Some(prev), Some(next)) if prev * next < 0.0 => Some((samples[start].0 + samples[end - 1].0) / 2.0),
+        (None, None) => None,
+        _ if end - start == 1 => {
+            [prev, next].into_iter().flatten().all(f64::is_normal).then_some(samples[start].0)
+        },
+        _ => None,
+    }
+}
+
+
+/// Brent's method on a bracket with a sign change. None when the sign change is
+/// a pole or a jump.
+fn brent(f: &Function, (a, fa): (f64, f64), (b, fb): (f64, f64)) -> Option<f64> {
+    let scale = fa.abs().max(fb.abs()).max(1.0);
+    let (mut a, mut fa, mut b, mut fb) = if fa.abs() < fb.abs() {(b, fb, a, fa)} else {(a, fa, b, fb)};
+    let (mut c, mut fc) = (a, fa);
+    let mut d = c;
+    let mut bisected = true;
+    for _ in 0..MAX_ITERATIONS {
+        let tolerance = 2.0 * f64::EPSILON * b.abs().max(1e-300);
+        if fb == 0.0 || (b - a).abs() <= tolerance {
+            break
+        }
+        let mut s = if fa != fc && fb != fc {
+            a * fb * fc / ((fa - fb) * (fa - fc)) + b * fa * fc / ((fb - fa) * (fb - fc)) + c * fa * fb / ((fc - fa) * (fc - fb))
+        } else {
+            b - fb * (b - a) / (fb - fa)
+        };
+        let quarter = (3.0 * a + b) / 4.0;
+        if !(s > quarter.min(b) && s < quarter.max(b))
+                || (bisected && (s - b).abs() >= (b - c).abs() / 2.0)
+                || (!bisected && (s - b).abs() >= (c - d).abs() / 2.0)
+                || (bisected && (b - c).abs() < tolerance)
+                || (!bisected && (c - d).abs() < tolerance) {
+            s = (a + b) / 2.0;
+            bisected = true;
+        } else {
+            bisected = false;
+        }
+        let fs = f.value(s)?;
+        d = c;
+        c = b;
+        fc = fb;
+        if fa * fs < 0.0 {
+            b = s;
+            fb = fs;
+        } else {
+            a = s;
+            fa = fs;
+        }
+        if fa.abs() < fb.abs() {
+            std::mem::swap(&mut a, &mut b);
+            std::mem::swap(&mut fa, &mut fb);
+        }
+    }
+    if fb.abs() <= CROSS_TOLERANCE * scale {Some(b)} else {None}
+}
+
+
+/// Newton's method from `x`, for a root where the function does not change sign.
+fn newton(f: &Function, x: f64, (low, high): (f64, f64), scale: f64) -> Option<f64> {
+    let mut x = x;
+    for _ in 0..MAX_ITERATIONS {
+        let step = f.value(x)? / f.derivative(x)?;
+        if !step.is_finite() {
+            break
+        }
+        x -= step;
+        if x < low || x > high {
+            return None
+        }
+        if step.abs() <= f64::EPSILON * x.abs().max(1.0) {
+            break
+        }
+    }
+    if f.value(x)?.abs() <= TOUCH_TOLERANCE * scale {Some(x)} else {None}
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+
+    fn find_roots_test(code: &str, interval: (f64, f64)) -> Result<Vec<f64>, String> {
+        let data_base = DataBase::new();
+        let mut parser = Parser::new(Lexer::new(code).make_token_vec()?);
+        let mut tree = parser.make_tree(&data_base)?;
+        parser.calculation(&mut tree, &data_base, None)?;
+        Ok(find_roots(&tree, &"x".to_string(), interval, &data_base))
+    }
+
+    #[test]
+    fn find_roots_sign_change() -> Result<(), String> {
+        let roots = find_roots_test("cos(x) - x", (-10.0, 10.0))?;
+        assert_eq!(roots.len(), 1);
+        assert!((roots[0] - 0.7390851332151607).abs() < 1e-14);
+        let roots = find_roots_test("sin(x)", (-1.0, 7.0))?;
+        assert_eq!(roots.len(), 3);
+        assert!(roots[0] == 0.0 && (roots[2] - 2.0 * std::f64::consts::PI).abs() < 1e-14);
+        Ok(())
+    }
+
+    #[test]
+    fn find_roots_touch_and_pole() -> Result<(), String> {
+        let roots = find_roots_test("cos(x) - 1", (-1.0, 1.3))?;
+        assert_eq!(roots.len(), 1);
+        assert!(roots[0].abs() < 1e-6);
+        assert_eq!(find_roots_test("tan(x)", (1.0, 2.0))?, vec![]);
+        assert_eq!(find_roots_test("exp(x)", (-10.0, 10.0))?, vec![]);
+        assert_eq!(find_roots_test("floor(x) - 0.5", (-0.3, 3.3))?, vec![]);
+        assert_eq!(find_roots_test("sign(x) + 0.5", (-1.3, 1.0))?, vec![]);
+        assert_eq!(find_roots_test("exp(x) - 10000000000", (0.0, 30.0)).map(|r| r.len()), Ok(1));
+        Ok(())
+    }
+
+    #[test]
+    fn find_roots_underflow() -> Result<(), String> {
+        assert_eq!(find_roots_test("exp(-x^4)", (-10.0, 10.0))?, vec![]);
+        assert_eq!(find_roots_test("exp(-x^4) * x", (-10.0, 10.0))?, vec![0.0]);
+        assert_eq!(find_roots_test("x^2", (-1.0, 1.0))?, vec![0.0]);
+        assert_eq!(find_roots_test("x", (0.0, 1.0))?, vec![0.0]);
+        Ok(())
+    }
+}
@@ -9,9 +9,12 @@ pub enum Operator {
     Div,
     Rem,
     MatrixMul,
+    MatrixDiv,
+    MatrixLeftDiv,
     Pow,
     Paren,
     RParen,
+    Comma,
 }
 
 impl Operator {
@@ -19,22 +22,18 @@ impl Operator {
     pub fn priority(&self, rhs: &Self) -> bool {
         match *self {
             Self::Plus | Self::Minus => {
-                match rhs {
-                    Self::Mul | Self::Div | Self::Rem | Self::MatrixMul | Self::Pow => true,
-                    _ => false
-                }
+                matches!(rhs, Self::Mul | Self::Div | Self::Rem | Self::MatrixMul
+                        | Self::MatrixDiv | Self::MatrixLeftDiv | Self::Pow)
             },
-            Self::Mul | Self::Div | Self::Rem | Self::MatrixMul => {
-                match rhs {
-                    Self::Pow => true,
-                    _ => false
-                }
+            Self::Mul | Self::Div | Self::Rem | Self::MatrixMul
+                | Self::MatrixDiv | Self::MatrixLeftDiv => {
+                matches!(rhs, Self::Pow)
             },
             Self::Pow => {
-                match rhs {
-                    Self::Paren => true,
-                    _ => false
-                }
+                matches!(rhs, Self::Paren)
+            },
+            Self::Comma => {
+                !matches!(rhs, Self::Paren | Self::RParen | Self::Comma)
             },
             _ => false,
         }
@@ -52,8 +51,11 @@ impl fmt::Display for Operator {
             Operator::Rem => write!(f, "%"),
             Operator::Pow => write!(f, "^"),
             Operator::MatrixMul => write!(f, "**"),
+            Operator::MatrixDiv => write!(f, "//"),
+            Operator::MatrixLeftDiv => write!(f, "\\"),
             Operator::Paren => write!(f, "("),
             Operator::RParen => write!(f, ")"),
+            Operator::Comma => write!(f, ","),
         }
     }
 }
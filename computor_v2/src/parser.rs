@@ -3,10 +3,11 @@ use crate::binary_tree::BinaryTree;
 use crate::lexer::Token;
 use crate::operator::Operator;
 use crate::data_base::{DataBase, Data};
-use crate::functions::builtin_func;
+use crate::functions::{self, Builtin};
 
 
 #[derive(Debug, PartialEq, Clone)]
+#[allow(clippy::box_collection)]
 pub enum Element {
     Dummy,
     Operator(Operator),
@@ -15,6 +16,11 @@ pub enum Element {
     Func(Box<String>),
 }
 
+/// Tokens of the lower and upper bounds of a definite integral.
+pub type Bounds = (Vec<Token>, Vec<Token>);
+/// The two sides of an equation.
+pub type EquationTokens = (Vec<Token>, Vec<Token>);
+
 pub struct Parser {
     tokens: Vec<Token>,
     index: usize,
@@ -26,25 +32,116 @@ impl Parser {
         Parser { tokens, index: 0 }
     }
 
-    fn is_onw_command(tokens: &Vec<Token>, string: String) -> bool {
+    fn is_onw_command(tokens: &[Token], string: String) -> bool {
         tokens.len() == 1 && match Self::get_string_token_string(&tokens[0]) {
             Ok(s) => *s == string,
             Err(_) => false
         }
     }
 
-    pub fn is_show_variable(tokens: &Vec<Token>) -> bool {
+    pub fn is_show_variable(tokens: &[Token]) -> bool {
         Self::is_onw_command(tokens, "variables".to_string())
     }
 
-    pub fn is_show_functions(tokens: &Vec<Token>) -> bool {
+    pub fn is_show_functions(tokens: &[Token]) -> bool {
         Self::is_onw_command(tokens, "functions".to_string())
     }
 
-    pub fn is_show_commands(tokens: &Vec<Token>) -> bool {
+    pub fn is_show_builtin_functions(tokens: &[Token]) -> bool {
+        Self::is_onw_command(tokens, "builtins".to_string())
+    }
+
+    pub fn is_show_commands(tokens: &[Token]) -> bool {
         Self::is_onw_command(tokens, "history".to_string())
     }
 
+    /// `display` or `display <form>`
+    pub fn is_display_command(tokens: &[Token]) -> bool {
+        (tokens.len() == 1 || tokens.len() == 2) && match Self::get_string_token_string(&tokens[0]) {
+            Ok(s) => *s == "display",
+            Err(_) => false
+        }
+    }
+
+    /// `steps` or `steps on` or `steps off`
+    pub fn is_steps_command(tokens: &[Token]) -> bool {
+        (tokens.len() == 1 || tokens.len() == 2) && match Self::get_string_token_string(&tokens[0]) {
+            Ok(s) => *s == "steps",
+            Err(_) => false
+        }
+    }
+
+    /// `exact` or `exact on` or `exact off`
+    pub fn is_exact_command(tokens: &[Token]) -> bool {
+        (tokens.len() == 1 || tokens.len() == 2) && match Self::get_string_token_string(&tokens[0]) {
+            Ok(s) => *s == "exact",
+            Err(_) => false
+        }
+    }
+
+    /// `interval` or `interval <a> <b>`
+    pub fn is_interval_command(tokens: &[Token]) -> bool {
+        !tokens.is_empty() && !tokens.contains(&Token::Equal) && match Self::get_string_token_string(&tokens[0]) {
+            Ok(s) => *s == "interval",
+            Err(_) => false
+        }
+    }
+
+    pub fn get_interval_args(tokens: &[Token]) -> Result<Option<(f64, f64)>, String> {
+        if tokens.len() == 1 {
+            return Ok(None)
+        }
+        let mut bounds = Vec::new();
+        let mut sign = 1.0;
+        for token in &tokens[1..] {
+            match token {
+                Token::Minus if sign > 0.0 => sign = -1.0,
+                Token::NumString(s) => {
+                    let n: f64 = s.parse().map_err(|_| format!("{}: error invalid number", s))?;
+                    bounds.push(sign * n);
+                    sign = 1.0;
+                },
+                _ => return Err("error: interval takes two numbers".to_string()),
+            }
+        }
+        match bounds[..] {
+            [a, b] if sign > 0.0 && a < b && a.is_finite() && b.is_finite() => Ok(Some((a, b))),
+            [_, _] if sign > 0.0 => Err("error: the interval must be finite with a < b".to_string()),
+            _ => Err("error: interval takes two numbers".to_string()),
+        }
+    }
+
+    /// `{ lhs = rhs ; lhs = rhs ; ... } ?`
+    pub fn is_system(tokens: &[Token]) -> bool {
+        tokens.len() >= 3 && tokens[0] == Token::LBrace
+            && tokens.ends_with(&[Token::RBrace, Token::Question])
+    }
+
+    /// The two sides of each equation of a system.
+    pub fn get_system_equations(tokens: &[Token]) -> Result<Vec<EquationTokens>, String> {
+        let mut equations = Vec::new();
+        let mut equation = Vec::new();
+        let mut depth = 0;
+        for token in &tokens[1..tokens.len() - 1] {
+            match token {
+                Token::LParen | Token::LBracket => depth += 1,
+                Token::RParen | Token::RBracket => depth -= 1,
+                _ => {},
+            }
+            if depth == 0 && (*token == Token::SemiColon || *token == Token::RBrace) {
+                equations.push(Self::separate_equal(std::mem::take(&mut equation))?);
+            } else {
+                equation.push(token.clone());
+            }
+        }
+        for (lhs, rhs) in &equations {
+            if lhs.is_empty() || rhs.is_empty() {
+                return Err("syntax error".to_string())
+            }
+        }
+        Ok(equations)
+    }
+
     pub fn separate_equal(tokens: Vec<Token>) -> Result<(Vec<Token>, Vec<Token>), String> {
         let mut left_vec = Vec::new();
         let mut right_vec = Vec::new();
@@ -69,24 +166,196 @@ impl Parser {
         Ok((left_vec, right_vec))
     }
 
-    pub fn is_question_tokens(tokens: &Vec<Token>) -> bool {
+    pub fn is_question_tokens(tokens: &[Token]) -> bool {
         tokens.len() == 1 && tokens[0] == Token::Question
     }
 
-    pub fn is_solution_equation(tokens: &Vec<Token>) -> bool {
-        tokens.len() > 0 && tokens.last() == Some(&Token::Question)
+    pub fn is_solution_equation(tokens: &[Token]) -> bool {
+        !tokens.is_empty() && tokens.last() == Some(&Token::Question)
+    }
+
+    pub fn is_variable_register(tokens: &[Token]) -> bool {
+        tokens.len() == 1 && matches!(tokens[0], Token::String(_))
+    }
+
+    /// `name ( var , var , ... )`
+    pub fn is_func_register(tokens: &[Token]) -> bool {
+        tokens.len() >= 4 && tokens.len().is_multiple_of(2)
+            && matches!(tokens[0], Token::String(_))
+            && matches!(tokens[1], Token::LParen)
+            && matches!(tokens[tokens.len() - 1], Token::RParen)
+            && tokens[2..tokens.len() - 1].iter().enumerate().all(|(i, token)| {
+                if i % 2 == 0 {
+                    matches!(token, Token::String(_))
+                } else {
+                    *token == Token::Comma
+                }
+            })
+    }
+
+    pub fn get_func_register_variables(tokens: &[Token]) -> Result<Vec<String>, String> {
+        let mut variables: Vec<String> = Vec::new();
+        for token in tokens[2..tokens.len() - 1].iter().step_by(2) {
+            let variable = Self::get_string_token_string(token)?;
+            if variables.contains(variable) {
+                return Err(format!("{}: error duplicate variable", variable))
+            }
+            variables.push(variable.clone());
+        }
+        Ok(variables)
+    }
+
+    /// `diff ( f )` or `diff ( f , var )`
+    pub fn is_diff(tokens: &Vec<Token>) -> bool {
+        let is_string = |token: &Token| matches!(token, Token::String(_));
+        match tokens.as_slice() {
+            [name, Token::LParen, f, Token::RParen]
+                => is_string(f) && Self::is_name_token(name, "diff"),
+            [name, Token::LParen, f, Token::Comma, v, Token::RParen]
+                => is_string(f) && is_string(v) && Self::is_name_token(name, "diff"),
+            _ => false,
+        }
+    }
+
+    /// The function name and the optional variable of a `diff` form.
+    pub fn get_diff_args(tokens: &[Token]) -> Result<(String, Option<String>), String> {
+        let name = Self::get_string_token_string(&tokens[2])?.clone();
+        if tokens.len() == 6 {
+            Ok((name, Some(Self::get_string_token_string(&tokens[4])?.clone())))
+        } else {
+            Ok((name, None))
+        }
+    }
+
+    /// `solve ( expr , x ) ?` or `solve ( lhs = rhs , x ) ?`, also with `= ?` at the end.
+    /// The unknown can be left out when there is only one variable.
+    pub fn is_solve(tokens: &Vec<Token>) -> bool {
+        Self::is_solve_call(Self::solve_call(tokens))
+    }
+
+    /// `name = solve ( ... )`
+    pub fn is_solve_register(tokens: &[Token]) -> bool {
+        tokens.len() > 2 && matches!(tokens[0], Token::String(_)) && tokens[1] == Token::Equal
+            && Self::is_solve_call(&tokens[2..])
+    }
+
+    fn is_solve_call(call: &[Token]) -> bool {
+        call.len() >= 4
+            && Self::is_name_token(&call[0], "solve")
+            && call[1] == Token::LParen
+            && call[call.len() - 1] == Token::RParen
+    }
+
+    fn solve_call(tokens: &Vec<Token>) -> &[Token] {
+        match tokens.as_slice() {
+            [call @ .., Token::Equal, Token::Question] => call,
+            [call @ .., Token::Question] => call,
+            _ => &[],
+        }
+    }
+
+    /// The two sides of the equation of a `solve` form, the right one being `0`
+    /// when omitted, and the unknown when given.
+    pub fn get_solve_args(tokens: &Vec<Token>) -> Result<(EquationTokens, Option<String>), String> {
+        let call = if Self::is_solve_register(tokens) {&tokens[2..]} else {Self::solve_call(tokens)};
+        let inner = &call[2..call.len() - 1];
+        let (equation, variable) = match inner {
+            [equation @ .., Token::Comma, Token::String(v)] => (equation.to_vec(), Some(*v.clone())),
+            _ => (inner.to_vec(), None),
+        };
+        if equation.is_empty() {
+            return Err("error: solve takes an equation and an unknown".to_string())
+        }
+        if equation.contains(&Token::Equal) {
+            Ok((Self::separate_equal(equation)?, variable))
+        } else {
+            Ok(((equation, vec![Token::NumString(Box::new("0".to_string()))]), variable))
+        }
+    }
+
+    /// `integrate ( f )` or `integrate ( f , a , b )`
+    pub fn is_integrate(tokens: &[Token]) -> bool {
+        Self::is_integral_form(tokens, "integrate")
     }
 
-    pub fn is_variable_register(tokens: &Vec<Token>) -> bool {
-        tokens.len() == 1 && if let Token::String(_) = tokens[0] {true} else {false}
+    /// `nintegrate ( f , a , b )`
+    pub fn is_nintegrate(tokens: &[Token]) -> bool {
+        Self::is_integral_form(tokens, "nintegrate")
     }
 
-    pub fn is_func_register(tokens: &Vec<Token>) -> bool {
-        tokens.len() == 4
-            && if let Token::String(_) = tokens[0] {true} else {false}
-            && if let Token::LParen = tokens[1] {true} else {false}
-            && if let Token::String(_) = tokens[2] {true} else {false}
-            && if let Token::RParen = tokens[3] {true} else {false}
+    fn is_integral_form(tokens: &[Token], name: &str) -> bool {
+        tokens.len() >= 4
+            && Self::is_name_token(&tokens[0], name)
+            && tokens[1] == Token::LParen
+            && matches!(tokens[2], Token::String(_))
+            && tokens[tokens.len() - 1] == Token::RParen
+            && (tokens.len() == 4 || tokens[3] == Token::Comma)
+    }
+
+    /// The function name and, for a definite integral, the tokens of both bounds.
+    pub fn get_integrate_args(tokens: &[Token]) -> Result<(String, Option<Bounds>), String> {
+        let name = Self::get_string_token_string(&tokens[2])?.clone();
+        if tokens.len() == 4 {
+            return Ok((name, None))
+        }
+        let mut bounds = vec![Vec::new()];
+        let mut depth = 0;
+        for token in &tokens[4..tokens.len() - 1] {
+            match token {
+                Token::LParen | Token::LBracket => depth += 1,
+                Token::RParen | Token::RBracket => depth -= 1,
+                Token::Comma if depth == 0 => {
+                    bounds.push(Vec::new());
+                    continue
+                },
+                _ => {},
+            }
+            bounds.last_mut().unwrap().push(token.clone());
+        }
+        if bounds.len() != 2 || bounds.iter().any(|b| b.is_empty()) {
+            return Err(format!("error: {} takes a function and two bounds", Self::get_string_token_string(&tokens[0])?))
+        }
+        let b = bounds.pop().unwrap();
+        let a = bounds.pop().unwrap();
+        Ok((name, Some((a, b))))
+    }
+
+    /// `expand ( f )` or `expand ( expr )`
+    pub fn is_expand(tokens: &[Token]) -> bool {
+        Self::is_call_form(tokens, "expand")
+    }
+
+    /// `factor ( f )` or `factor ( expr )`
+    pub fn is_factor(tokens: &[Token]) -> bool {
+        Self::is_call_form(tokens, "factor")
+    }
+
+    /// `name ( ... )` where the parentheses after the name enclose the rest.
+    fn is_call_form(tokens: &[Token], name: &str) -> bool {
+        if tokens.len() < 4 || !Self::is_name_token(&tokens[0], name) || tokens[1] != Token::LParen {
+            return false
+        }
+        let mut depth = 0;
+        for (i, token) in tokens[1..].iter().enumerate() {
+            match token {
+                Token::LParen => depth += 1,
+                Token::RParen => depth -= 1,
+                _ => {},
+            }
+            if depth == 0 {
+                return i == tokens.len() - 2
+            }
+        }
+        false
+    }
+
+    /// The tokens between the parentheses of a call form.
+    pub fn get_call_argument(tokens: &[Token]) -> Vec<Token> {
+        tokens[2..tokens.len() - 1].to_vec()
+    }
+
+    fn is_name_token(token: &Token, name: &str) -> bool {
+        matches!(token, Token::String(s) if s.eq_ignore_ascii_case(name))
     }
 
     pub fn get_string_token_string(token: &Token) -> Result<&String, String> {
@@ -96,34 +365,28 @@ impl Parser {
         }
     }
 
-    pub fn get_num_token_float(token: &Token) -> Result<f64, String> {
+    pub fn get_num_token(token: &Token) -> Result<Num, String> {
         match token {
-            Token::NumString(s) => {
-                let num = Self::string_to_num(s)?;
-                match num {
-                    Num::Float(n) => Ok(n),
-                    _ => Err("syntax error".to_string()),
-                }
-            }
+            Token::NumString(s) => Self::string_to_num(s),
             _ => Err("syntax error".to_string())
         }
     }
 
-    fn string_to_num(string: &String) -> Result<Num, String> {
-        let num = Num::from_string_to_float(string)?;
+    fn string_to_num(string: &str) -> Result<Num, String> {
+        let num = Num::from_string(string)?;
         num.checked_value()?;
         Ok(num)
     }
 
     fn get_next_token_to_num(&self) -> Result<Num, String> {
-        Ok(Num::Float(Self::get_num_token_float(self.get_next_token()?)?))
+        Self::get_num_token(self.get_next_token()?)
     }
 
     pub fn make_tree(&mut self, data_base: &DataBase) -> Result<BinaryTree<Element>, String> {
         let mut tree = BinaryTree::new();
         self.root_tree_loop(&mut tree, data_base)?;
         if self.index < self.tokens.len() {
-            return Err(format!("syntax error"))
+            return Err("syntax error".to_string())
         }
         Ok(tree)
     }
@@ -147,7 +410,8 @@ impl Parser {
                 let token = self.get_next_token()?;
                 match token {
                     Token::Plus | Token::Minus | Token::Asterisk | 
-                        Token::Slash | Token::Percent | Token::Caret | Token::TwoAsterisk => {
+                        Token::Slash | Token::Percent | Token::Caret | Token::TwoAsterisk |
+                        Token::TwoSlash | Token::BackSlash | Token::Comma => {
                         let operator = Self::token_to_operator(token)?;
                         let tree_op = Self::get_tree_element_operator(tree)?;
                         if tree_op.priority(&operator) {
@@ -155,13 +419,13 @@ impl Parser {
                         } else {
                             Self::replace_and_add_left(tree, operator);
                             if !tree.left().unwrap().is_non_empty() {
-                                return Err(format!("syntax error"))
+                                return Err("syntax error".to_string())
                             }
                             self.index_plus();
                             self.while_next_token(tree, data_base)?;
                         }
                     },
-                    _ => return Err(format!("syntax error")),
+                    _ => return Err("syntax error".to_string()),
                 }
             }
         }
@@ -177,17 +441,20 @@ impl Parser {
             Token::Percent => Ok(Operator::Rem),
             Token::Caret => Ok(Operator::Pow),
             Token::TwoAsterisk => Ok(Operator::MatrixMul),
-            _ => Err(format!("syntax error")),
+            Token::TwoSlash => Ok(Operator::MatrixDiv),
+            Token::BackSlash => Ok(Operator::MatrixLeftDiv),
+            Token::Comma => Ok(Operator::Comma),
+            _ => Err("syntax error".to_string()),
         }
     }
 
     fn get_tree_element_operator(tree: &BinaryTree<Element>) -> Result<&Operator, String> {
         match tree {
-            BinaryTree::Empty => Err(format!("syntax error")),
+            BinaryTree::Empty => Err("syntax error".to_string()),
             BinaryTree::NonEmpty(node_box) => {
                 match &node_box.element {
                     Element::Operator(op) => Ok(op),
-                    _ => Err(format!("syntax error")),
+                    _ => Err("syntax error".to_string()),
                 }
             }
         }
@@ -198,7 +465,7 @@ impl Parser {
             Some(v) => {
                 Ok(v)
             },
-            None => Err(format!("syntax error")),
+            None => Err("syntax error".to_string()),
         }
     }
 
@@ -227,26 +494,26 @@ impl Parser {
             Token::Percent => self.add_operator(tree, Operator::Rem, data_base),
             Token::Caret => self.add_operator(tree, Operator::Pow, data_base),
             Token::TwoAsterisk => self.add_operator(tree, Operator::MatrixMul, data_base),
+            Token::TwoSlash => self.add_operator(tree, Operator::MatrixDiv, data_base),
+            Token::BackSlash => self.add_operator(tree, Operator::MatrixLeftDiv, data_base),
+            Token::Comma => self.add_operator(tree, Operator::Comma, data_base),
             Token::LParen => self.add_paren(tree, data_base),
-            Token::LBracket => self.add_matrix(tree),
+            Token::LBracket => self.add_matrix(tree, data_base),
             Token::RParen => Ok(true),
             Token::String(s) => {
                 let string_box = s.clone();
                 if Self::is_function(&string_box, data_base) {
                     self.add_function(tree, string_box, data_base)
                 } else {
-                    self.add_variable(tree, string_box)
+                    self.add_variable(tree, string_box, data_base)
                 }
             },
-            _ => Err(format!("syntax error")),
+            _ => Err("syntax error".to_string()),
         }
     }
 
-    fn is_function(string_box: &Box<String>, data_base: &DataBase) -> bool {
-        match data_base.get_func(&string_box) {
-            Some(_) => true,
-            None => false,
-        }
+    fn is_function(string_box: &str, data_base: &DataBase) -> bool {
+        data_base.get_builtin_func(string_box).is_some() || data_base.get_func(string_box).is_some()
     }
 
     fn add_num(&mut self, tree: &mut BinaryTree<Element>) -> Result<bool, String> {
@@ -290,19 +557,19 @@ impl Parser {
         Ok(false)
     }
 
-    fn add_matrix(&mut self, tree: &mut BinaryTree<Element>) -> Result<bool, String> {
+    fn add_matrix(&mut self, tree: &mut BinaryTree<Element>, data_base: &DataBase) -> Result<bool, String> {
         let next_tree = match self.is_next_tree_or_right_or_insert_mul(tree, "[".to_string())? {
             Some(f) => if f {tree} else {tree.right_mut().unwrap()},
             None => return Ok(false)
         };
-        *next_tree = BinaryTree::from_element(Element::Num(self.token_to_matrix()?));
+        *next_tree = BinaryTree::from_element(Element::Num(self.token_to_matrix(data_base)?));
         if self.is_num() || self.is_string_token() {
             self.insert_mul();
         }
         Ok(false)
     }
 
-    fn token_to_matrix(&mut self) -> Result<Num, String> {
+    fn token_to_matrix(&mut self, data_base: &DataBase) -> Result<Num, String> {
         let mut vec = Vec::new();
         self.index_plus();
         let flag = 'outer: loop {
@@ -312,12 +579,11 @@ impl Parser {
             self.index_plus();
             let mut v = Vec::new();
             loop {
-                if !self.is_num() {
-                    break 'outer false
-                }
-                let value = Self::get_num_token_float(self.get_next_token()?)?;
+                let value = match self.token_to_matrix_element(data_base)? {
+                    Some(n) => n,
+                    None => break 'outer false,
+                };
                 v.push(value);
-                self.index_plus();
                 if !self.is_next_token(Token::Comma) {
                     break
                 }
@@ -334,10 +600,34 @@ impl Parser {
             self.index_plus();
         };
         if !flag || !self.is_next_token(Token::RBracket) {
-            Err(format!("syntax error"))
+            Err("syntax error".to_string())
         } else {
             self.index_plus();
-            Num::from_vec(vec)
+            Num::from_num_vec(vec)
+        }
+    }
+
+    fn token_to_matrix_element(&mut self, data_base: &DataBase) -> Result<Option<Num>, String> {
+        let mut tokens = Vec::new();
+        let mut depth = 0;
+        while let Ok(token) = self.get_next_token() {
+            match token {
+                Token::LParen | Token::LBracket => depth += 1,
+                Token::RParen | Token::RBracket if depth > 0 => depth -= 1,
+                Token::Comma | Token::SemiColon | Token::RBracket if depth == 0 => break,
+                _ => {},
+            }
+            tokens.push(token.clone());
+            self.index_plus();
+        }
+        if tokens.is_empty() {
+            return Ok(None)
+        }
+        let mut parser = Parser::new(tokens);
+        let mut tree = parser.make_tree(data_base)?;
+        match parser.calculation(&mut tree, data_base, None)? {
+            Some(n) if !n.is_matrix() => Ok(Some(n)),
+            _ => Err("error: matrix elements must be numbers".to_string()),
         }
     }
 
@@ -353,10 +643,7 @@ impl Parser {
     fn is_num(&mut self) -> bool {
         match self.get_next_token() {
             Ok(token) => {
-                match token {
-                    Token::NumString(_) => true,
-                    _ => false,
-                }
+                matches!(token, Token::NumString(_))
             },
             Err(_) => false,
         }
@@ -365,10 +652,7 @@ impl Parser {
     fn is_string_token(&mut self) -> bool {
         match self.get_next_token() {
             Ok(token) => {
-                match token {
-                    Token::String(_) => true,
-                    _ => false,
-                }
+                matches!(token, Token::String(_))
             },
             Err(_) => false,
         }
@@ -383,7 +667,7 @@ impl Parser {
                         self.index_plus();
                         *tree.left_mut().unwrap() = BinaryTree::from_element(Element::Dummy);
                     },
-                    _ => return Err(format!("Unsupported unary operators: syntax error"))
+                    _ => return Err("Unsupported unary operators: syntax error".to_string())
                 }
             }
             BinaryTree::NonEmpty(node_box) => {
@@ -394,7 +678,11 @@ impl Parser {
                     },
                     Element::Operator(tree_op) => {
                         if !tree.right().unwrap().is_non_empty() {
-                            return Err(format!("syntax error"))
+                            if let (Operator::Comma, Operator::Plus | Operator::Minus) = (tree_op, operator) {
+                                self.add_operator(tree.right_mut().unwrap(), operator, data_base)?;
+                                return self.while_next_token(tree.right_mut().unwrap(), data_base)
+                            }
+                            return Err("syntax error".to_string())
                         }
                         if tree_op.priority(&operator) {
                             return self.while_next_token(tree.right_mut().unwrap(), data_base)
@@ -402,7 +690,7 @@ impl Parser {
                             return Ok(true);
                         }
                     },
-                    Element::Dummy => return Err(format!("syntax error"))
+                    Element::Dummy => return Err("syntax error".to_string())
                 }
             }
         }
@@ -429,7 +717,7 @@ impl Parser {
                             return Ok(false)
                         }
                     },
-                    Element::Dummy => return Err(format!("syntax error"))
+                    Element::Dummy => return Err("syntax error".to_string())
                 }
                 let right_tree = tree.right_mut().unwrap();
                 *right_tree = BinaryTree::from_element(Element::Operator(Operator::Paren));
@@ -453,19 +741,58 @@ impl Parser {
         Ok(false)
     }
 
-    fn add_variable(&mut self, tree: &mut BinaryTree<Element>, string_box: Box<String>) -> Result<bool, String> {
+    #[allow(clippy::box_collection)]
+    fn add_variable(&mut self, tree: &mut BinaryTree<Element>, string_box: Box<String>, data_base: &DataBase) -> Result<bool, String> {
         let next_tree = match self.is_next_tree_or_right_or_insert_mul(tree, *string_box.clone())? {
             Some(f) => if f {tree} else {tree.right_mut().unwrap()},
             None => return Ok(false)
         };
-        *next_tree = BinaryTree::from_element(Element::Variable(string_box));
         self.index_plus();
+        if self.is_next_token(Token::LBracket) && self.tokens.get(self.index + 1) != Some(&Token::LBracket) {
+            let element = self.token_to_vector_element(&string_box, data_base)?;
+            *next_tree = BinaryTree::from_element(Element::Num(element));
+        } else {
+            *next_tree = BinaryTree::from_element(Element::Variable(string_box));
+        }
         if self.is_num() || self.is_string_token() {
             self.insert_mul();
         }
         Ok(false)
     }
 
+    /// The element of the vector `name` at the index in brackets, counted from 0.
+    fn token_to_vector_element(&mut self, name: &String, data_base: &DataBase) -> Result<Num, String> {
+        self.index_plus();
+        let mut tokens = Vec::new();
+        let mut depth = 0;
+        loop {
+            let token = self.get_next_token()?.clone();
+            self.index_plus();
+            match token {
+                Token::LParen | Token::LBracket => depth += 1,
+                Token::RParen | Token::RBracket if depth > 0 => depth -= 1,
+                Token::RBracket => break,
+                _ => {},
+            }
+            tokens.push(token);
+        }
+        let mut parser = Parser::new(tokens);
+        let mut tree = parser.make_tree(data_base)?;
+        let index = match parser.calculation(&mut tree, data_base, None)? {
+            Some(n) if n.is_real() && !n.is_sign_negative() => n.to_int_value(),
+            _ => None,
+        };
+        let index = index.ok_or("error: an index must be a nonnegative integer".to_string())?;
+        match data_base.get_num(name) {
+            Some(Num::Matrix(m)) if m.size().0 == 1 || m.size().1 == 1 => match m.vector_at(index as usize) {
+                Some(n) => Ok(n.clone()),
+                None => Err(format!("error: index {} is out of range for {}", index, name)),
+            },
+            _ => Err(format!("error: {} is not a vector", name)),
+        }
+    }
+
+    #[allow(clippy::box_collection)]
     fn add_function(&mut self, tree: &mut BinaryTree<Element>, string_box: Box<String>, data_base: &DataBase) -> Result<bool, String> {
         let next_tree = match self.is_next_tree_or_right_or_insert_mul(tree, *string_box.clone())? {
             Some(f) => if f {tree} else {tree.right_mut().unwrap()},
@@ -476,8 +803,8 @@ impl Parser {
         if !self.is_next_token(Token::LParen) {
             return Err(format!("error: {} is defined as a function, so it needs parentheses", string_box))
         }
-        let mut paren_tree = next_tree.left_mut().unwrap();
-        self.add_paren(&mut paren_tree, data_base)?;
+        let paren_tree = next_tree.left_mut().unwrap();
+        self.add_paren(paren_tree, data_base)?;
         *next_tree.right_mut().unwrap() = BinaryTree::from_element(Element::Operator(Operator::RParen));
         Ok(false)
     }
@@ -515,30 +842,44 @@ impl Parser {
         tree.add_left_node_from_tree(tmp_tree);
     }
 
-    pub fn calculation(&self, tree: &mut BinaryTree<Element>, data_base: &DataBase, local_variable: Option<(&String, Option<&Data>)>) -> Result<Option<Num>, String> {
+    /// Value of `tree` with `variable` bound to `value`.
+    pub fn calculation_at(tree: &BinaryTree<Element>, variable: &String, value: &Num, data_base: &DataBase) -> Result<Num, String> {
+        let mut tree = tree.clone();
+        let data = Data::Num(value.clone());
+        let locals = vec![(variable, Some(&data))];
+        match Parser::new(Vec::new()).calculation(&mut tree, data_base, Some(&locals))? {
+            Some(n) => Ok(n),
+            None => Err("Undefined Variables".to_string()),
+        }
+    }
+
+    pub fn calculation(&self, tree: &mut BinaryTree<Element>, data_base: &DataBase, local_variable: Option<&Vec<(&String, Option<&Data>)>>) -> Result<Option<Num>, String> {
         match &tree {
-            BinaryTree::Empty => return Err(format!("syntax error")),
+            BinaryTree::Empty => Err("syntax error".to_string()),
             BinaryTree::NonEmpty(node_box) => {
                 let op = match &node_box.element {
-                    Element::Operator(Operator::RParen) => return Ok(Some(Num::Float(0.0))),
+                    Element::Operator(Operator::RParen) => return Ok(Some(Num::zero())),
                     Element::Operator(op) => {
-                        op.clone()
+                        *op
                     },
                     Element::Num(n) => return Ok(Some(n.clone())),
-                    Element::Dummy => return Ok(Some(Num::Float(0.0))),
+                    Element::Dummy => return Ok(Some(Num::zero())),
                     Element::Variable(string_box) => {
-                        if let Some((key, data)) = local_variable {
-                            if *key == **string_box {
-                                match data {
-                                    Some(d) => match d {
-                                        Data::Num(n) => return Ok(Some(n.clone())),
-                                        Data::Func(f) => {
-                                            *tree = f.0.clone();
-                                            return Ok(None)
-                                        },
+                        let local = local_variable
+                            .and_then(|v| v.iter().find(|(key, _)| **key == **string_box));
+                        if let Some((_, data)) = local {
+                            match data {
+                                Some(d) => match d {
+                                    Data::Num(n) => {
+                                        *tree = BinaryTree::from_element(Element::Num(n.clone()));
+                                        return Ok(Some(n.clone()))
                                     },
-                                    None => return Ok(None)
-                                }
+                                    Data::Func(f) => {
+                                        *tree = f.0.clone();
+                                        return Ok(None)
+                                    },
+                                },
+                                None => return Ok(None)
                             }
                         }
                         match data_base.get_num(string_box) {
@@ -550,44 +891,22 @@ impl Parser {
                         }
                     },
                     Element::Func(string_box) => {
-                        match data_base.get_builtin_func(string_box) {
-                            None => {},
-                            Some(b) => {
-                                let mut func_tree = b.0.clone();
-                                let variable = b.1.clone();
-                                let function_name = *string_box.clone();
-                                let left_value = match self.calculation(tree.left_mut().unwrap(), data_base, local_variable)? {
-                                    None => Data::Func(Box::new((tree.left().unwrap().clone(), variable.clone()))),
-                                    Some(num) => Data::Num(num),
-                                };
-                                match left_value {
-                                    Data::Num(n) => {
-                                        let n = builtin_func(function_name, &n)?;
-                                        *tree = BinaryTree::from_element(Element::Num(n.clone()));
-                                        return Ok(Some(n))
-                                    },
-                                    Data::Func(_) => {
-                                        match self.calculation(&mut func_tree.left_mut().unwrap(), data_base, Some((&variable, Some(&left_value))))? {
-                                            None => {
-                                                *tree = func_tree;
-                                                return Ok(None)
-                                            },
-                                            Some(_) => return Err(format!("syntax error")),
-                                        }
-                                    }
-                                }
-                            }
+                        if let Some(builtin) = data_base.get_builtin_func(string_box) {
+                            return self.calculation_builtin_args(tree, builtin, data_base, local_variable)
                         }
                         match data_base.get_func(string_box) {
                             None => return Ok(None),
                             Some(b) => {
                                 let mut func_tree = b.0.clone();
-                                let variable = b.1.clone();
-                                let left_value = match self.calculation(tree.left_mut().unwrap(), data_base, local_variable)? {
-                                    None => Data::Func(Box::new((tree.left().unwrap().clone(), variable.clone()))),
-                                    Some(num) => Data::Num(num),
-                                };
-                                match self.calculation(&mut func_tree, data_base, Some((&variable, Some(&left_value))))? {
+                                let variables = &b.1;
+                                let function_name = string_box.clone();
+                                let mut args = Vec::new();
+                                self.calculation_func_args(tree.left_mut().unwrap().left_mut().unwrap(), data_base, local_variable, &mut args)?;
+                                if args.len() != variables.len() {
+                                    return Err(format!("error: {} takes {} argument(s)", function_name, variables.len()))
+                                }
+                                let locals = variables.iter().zip(args.iter()).map(|(v, d)| (v, Some(d))).collect();
+                                match self.calculation(&mut func_tree, data_base, Some(&locals))? {
                                     None => {
                                         *tree = BinaryTree::from_element_and_tree(
                                             Element::Operator(Operator::Paren),
@@ -610,7 +929,7 @@ impl Parser {
                 let right_tree = tree.right().unwrap();
                 match (left_tree, right_tree) {
                     (BinaryTree::NonEmpty(_), BinaryTree::NonEmpty(_)) => {}
-                    _ => return Err(format!("syntax error")),
+                    _ => return Err("syntax error".to_string()),
                 };
                 let left_value_option =
                     self.calculation(tree.left_mut().unwrap(), data_base, local_variable)?;
@@ -627,7 +946,9 @@ impl Parser {
                             Operator::Pow => left_value.supported_pow(&right_value)?,
                             Operator::Paren => left_value,
                             Operator::MatrixMul => left_value.supported_matrix_mul(&right_value)?,
-                            _ => return Err(format!("syntax error")),
+                            Operator::MatrixDiv => left_value.supported_matrix_div(&right_value)?,
+                            Operator::MatrixLeftDiv => left_value.supported_matrix_left_div(&right_value)?,
+                            _ => return Err("syntax error".to_string()),
                         }
                     },
                     (None, Some(right_value)) => {
@@ -642,11 +963,77 @@ impl Parser {
                 };
                 value.checked_value()?;
                 *tree = BinaryTree::from_element(Element::Num(value.clone()));
-                return Ok(Some(value))
+                Ok(Some(value))
             }
         }
     }
 
+    /// The tree inside any number of enclosing parentheses.
+    fn strip_paren(tree: &BinaryTree<Element>) -> &BinaryTree<Element> {
+        match tree {
+            BinaryTree::NonEmpty(node_box) if matches!(node_box.element, Element::Operator(Operator::Paren))
+                => Self::strip_paren(tree.left().unwrap()),
+            _ => tree,
+        }
+    }
+
+    fn is_comma(tree: &BinaryTree<Element>) -> bool {
+        match tree {
+            BinaryTree::NonEmpty(node_box)
+                => matches!(node_box.element, Element::Operator(Operator::Comma)),
+            BinaryTree::Empty => false,
+        }
+    }
+
+    /// Evaluate a builtin called with a comma separated argument list.
+    fn calculation_builtin_args(&self, tree: &mut BinaryTree<Element>, builtin: &dyn Builtin, data_base: &DataBase, local_variable: Option<&Vec<(&String, Option<&Data>)>>) -> Result<Option<Num>, String> {
+        let args_tree = tree.left_mut().unwrap().left_mut().unwrap();
+        let mut args = Vec::new();
+        self.calculation_func_args(args_tree, data_base, local_variable, &mut args)?;
+        let mut values = Vec::new();
+        for arg in args {
+            match arg {
+                Data::Num(n) => values.push(n),
+                Data::Func(_) => {
+                    Self::strip_args_paren(args_tree);
+                    return Ok(None)
+                },
+            }
+        }
+        let n = functions::call_builtin(builtin, &values)?;
+        *tree = BinaryTree::from_element(Element::Num(n.clone()));
+        Ok(Some(n))
+    }
+
+    /// Remove the parentheses enclosing each comma separated argument.
+    fn strip_args_paren(tree: &mut BinaryTree<Element>) {
+        if Self::is_comma(tree) {
+            Self::strip_args_paren(tree.left_mut().unwrap());
+            Self::strip_args_paren(tree.right_mut().unwrap());
+        } else {
+            *tree = Self::strip_paren(tree).clone();
+        }
+    }
+
+    /// Push each comma separated argument to `args`, as its value or,
+    /// if it is not a number, as the parenthesized argument tree.
+    fn calculation_func_args(&self, tree: &mut BinaryTree<Element>, data_base: &DataBase, local_variable: Option<&Vec<(&String, Option<&Data>)>>, args: &mut Vec<Data>) -> Result<(), String> {
+        if Self::is_comma(tree) {
+            self.calculation_func_args(tree.left_mut().unwrap(), data_base, local_variable, args)?;
+            return self.calculation_func_args(tree.right_mut().unwrap(), data_base, local_variable, args)
+        }
+        let data = match self.calculation(tree, data_base, local_variable)? {
+            Some(n) => Data::Num(n),
+            None => Data::Func(Box::new((BinaryTree::from_element_and_tree(
+                Element::Operator(Operator::Paren),
+                tree.clone(),
+                BinaryTree::from_element(Element::Operator(Operator::RParen)),
+            ), Vec::new()))),
+        };
+        args.push(data);
+        Ok(())
+    }
+
     fn check_and_add_paren_to_value(tree: &mut BinaryTree<Element>, value: &Num, op: &Operator, right: bool) -> Result<(), String> {
         if !value.is_need_paren_to_display() {
             return Ok(())
@@ -662,18 +1049,20 @@ impl Parser {
                         tree.set_element(Element::Operator(Operator::Minus));
                         match &num {
                             Num::Complex(n) => {
-                                if n.z != 0.0 {
+                                if !n.z.is_zero() {
                                     Self::add_paren_to_value(tree, &num, right);
                                 }
                             },
-                            Num::Float(_) | Num::Matrix(_) => {}
+                            Num::Float(_) | Num::Rational(_) | Num::Matrix(_) => {}
                         }
                     }
                 }
             },
             Operator::Mul | Operator::Div | Operator::Rem | Operator::Pow | Operator::MatrixMul
+                | Operator::MatrixDiv | Operator::MatrixLeftDiv
                 => Self::add_paren_to_value(tree, value, right),
-            Operator::Paren | Operator::RParen => return Err(format!("syntax error"))
+            Operator::Comma => {},
+            Operator::Paren | Operator::RParen => return Err("syntax error".to_string())
         }
         Ok(())
     }
@@ -696,7 +1085,7 @@ impl Parser {
 
     pub fn print_tree_loop(tree: &BinaryTree<Element>, expr: &mut String) -> Result<(), String> {
         match tree {
-            BinaryTree::Empty => return Err(format!("syntax error")),
+            BinaryTree::Empty => return Err("syntax error".to_string()),
             BinaryTree::NonEmpty(node_box) => {
                 match &node_box.element {
                     Element::Operator(op) => {
@@ -717,7 +1106,7 @@ impl Parser {
                                 }
                                 Self::print_tree_loop(right_tree, expr)?;
                             },
-                            _ => return Err(format!("syntax error")),
+                            _ => return Err("syntax error".to_string()),
                         }
                     },
                     Element::Num(n) => *expr += format!("{} ", n).as_str(),
@@ -728,7 +1117,7 @@ impl Parser {
                         let left_tree = tree.left().unwrap();
                         match left_tree {
                             BinaryTree::NonEmpty(_) => Self::print_tree_loop(left_tree, expr)?,
-                            _ => return Err(format!("syntax error")),
+                            _ => return Err("syntax error".to_string()),
                         }
                     },
                 }
@@ -737,40 +1126,16 @@ impl Parser {
         Ok(())
     }
 
-    pub fn check_variable_in_tree(tree: &BinaryTree<Element>) -> Result<Option<String>, String> {
-        match tree {
-            BinaryTree::Empty => return Ok(None),
-            BinaryTree::NonEmpty(node_box) => {
-                let left = Self::check_variable_in_tree(tree.left().unwrap())?;
-                let right = Self::check_variable_in_tree(tree.right().unwrap())?;
-                let variable = match (left, right) {
-                    (Some(left_variable), Some(right_variable)) => {
-                        if left_variable != right_variable {
-                            return Err(format!("{}, {}: error two variable", left_variable, right_variable))
-                        }
-                        Some(left_variable)
-                    },
-                    (Some(left_variable), _) => Some(left_variable),
-                    (_, Some(right_variable)) => Some(right_variable),
-                    _ => None
-                };
-                let var = match &node_box.element {
-                    Element::Dummy | Element::Num(_) | Element::Operator(_) | Element::Func(_) => None,
-                    Element::Variable(v) => Some(v),
-                };
-                let variable = match (variable, var) {
-                    (Some(string1), Some(string2)) => {
-                        if **string2 != string1 {
-                            return Err(format!("{}, {}: error two variable", string2, string1))
-                        }
-                        Some(string1)
-                    },
-                    (Some(string1), _) => Some(string1),
-                    (_, Some(string2)) => Some(*string2.clone()),
-                    _ => None,
-                };
-                Ok(variable)
+    /// Push the names of the variables in the tree to `variables`, without duplicates.
+    pub fn get_variables_in_tree(tree: &BinaryTree<Element>, variables: &mut Vec<String>) {
+        if let BinaryTree::NonEmpty(node_box) = tree {
+            Self::get_variables_in_tree(tree.left().unwrap(), variables);
+            if let Element::Variable(v) = &node_box.element {
+                if !variables.contains(v) {
+                    variables.push(*v.clone());
+                }
             }
+            Self::get_variables_in_tree(tree.right().unwrap(), variables);
         }
     }
 
@@ -780,7 +1145,7 @@ impl Parser {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::Lexer;
+    use crate::lexer::Lexer;
 
     fn calculation_test(code: String) -> Result<Num, String> {
         let mut lexer = Lexer::new(&code);
@@ -795,6 +1160,8 @@ mod tests {
         data_base.register_num(&name, Num::Float(-2.0))?;
         let name = "z".to_string();
         data_base.register_num(&name, Num::from_two_float_to_complex(-1.0, -3.0))?;
+        let name = "v".to_string();
+        data_base.register_num(&name, Num::from_vec(vec![vec![1.0, 2.0, 3.0]])?)?;
         let mut parser = Parser::new(vec);
         let mut tree = match parser.make_tree(&data_base) {
             Ok(v) => v,
@@ -804,7 +1171,7 @@ mod tests {
             Ok(v) => {
                 match v {
                     Some(v) => Ok(v),
-                    None => Err(format!("error calculation"))
+                    None => Err("error calculation".to_string())
                 }
             },
             Err(e) => Err(format!("error calculation: {}", e))
@@ -860,7 +1227,7 @@ mod tests {
         }
     }
 
-    fn check_variable_in_tree_test(code: String) -> String {
+    fn get_variables_in_tree_test(code: String) -> String {
         let mut lexer = Lexer::new(&code);
         let vec = match lexer.make_token_vec() {
             Ok(v) => v,
@@ -880,15 +1247,9 @@ mod tests {
         };
         match parser.calculation(&mut tree, &data_base, None) {
             Ok(_) => {
-                match Parser::check_variable_in_tree(&tree) {
-                    Ok(s) => {
-                        match s {
-                            Some(v) => v,
-                            None => format!("None"),
-                        }
-                    },
-                    Err(e) => format!("error check_variable: {}", e),
-                }
+                let mut variables = Vec::new();
+                Parser::get_variables_in_tree(&tree, &mut variables);
+                variables.join(", ")
             }
             Err(e) => format!("error calculation: {}", e)
         }
@@ -908,22 +1269,19 @@ mod tests {
         let vec = lexer.make_token_vec()?;
         let mut parser = Parser::new(vec);
         let mut tree = parser.make_tree(&data_base)?;
-        parser.calculation(&mut tree, &data_base, Some((&variable, None)))?;
+        parser.calculation(&mut tree, &data_base, Some(&vec![(&variable, None)]))?;
 
         let mut lexer = Lexer::new(&code);
         let vec = match lexer.make_token_vec() {
             Ok(v) => v,
             Err(e) => return Err(format!("error lexer: {}", e))
         };
-        match Parser::check_variable_in_tree(&tree)? {
-            Some(var) => {
-                if var != *variable {
-                    return Err(format!("{}, {}: error two variable", var, variable))
-                }
-            },
-            None => {},
+        let mut variables = Vec::new();
+        Parser::get_variables_in_tree(&tree, &mut variables);
+        if variables.iter().any(|var| *var != variable) {
+            return Err(format!("{:?}, {}: error two variable", variables, variable))
         }
-        data_base.register_func(&function_name, tree, variable.clone())?;
+        data_base.register_func(&function_name, tree, vec![variable.clone()])?;
 
         let mut parser = Parser::new(vec);
         let mut tree = match parser.make_tree(&data_base) {
@@ -934,7 +1292,7 @@ mod tests {
             Ok(v) => {
                 match v {
                     Some(v) => Ok(v),
-                    None => Err(format!("error calculation"))
+                    None => Err("error calculation".to_string())
                 }
             },
             Err(e) => Err(format!("error calculation: {}", e))
@@ -955,22 +1313,19 @@ mod tests {
         let vec = lexer.make_token_vec()?;
         let mut parser = Parser::new(vec);
         let mut tree = parser.make_tree(&data_base)?;
-        parser.calculation(&mut tree, &data_base, Some((&variable, None)))?;
+        parser.calculation(&mut tree, &data_base, Some(&vec![(&variable, None)]))?;
 
         let mut lexer = Lexer::new(&code);
         let vec = match lexer.make_token_vec() {
             Ok(v) => v,
             Err(e) => return Err(format!("error lexer: {}", e))
         };
-        match Parser::check_variable_in_tree(&tree)? {
-            Some(var) => {
-                if var != *variable {
-                    return Err(format!("{}, {}: error two variable", var, variable))
-                }
-            },
-            None => {},
+        let mut variables = Vec::new();
+        Parser::get_variables_in_tree(&tree, &mut variables);
+        if variables.iter().any(|var| *var != variable) {
+            return Err(format!("{:?}, {}: error two variable", variables, variable))
         }
-        data_base.register_func(&function_name, tree, variable.clone())?;
+        data_base.register_func(&function_name, tree, vec![variable.clone()])?;
 
         let mut parser = Parser::new(vec);
         let mut tree = match parser.make_tree(&data_base) {
@@ -1240,6 +1595,28 @@ mod tests {
         assert_eq!(calculation_test(code), Ok(Num::Float(512.0)))
     }
 
+    #[test]
+    fn calculation_rational_exact() {
+        let code = "1/3 + 1/3 + 1/3".to_string();
+        let value = calculation_test(code);
+        assert!(matches!(value, Ok(Num::Rational(_))));
+        assert_eq!(value, Ok(Num::from_i64(1)))
+    }
+
+    #[test]
+    fn calculation_rational_decimal() {
+        let code = "0.1 + 0.2".to_string();
+        assert_eq!(calculation_test(code).map(|n| format!("{}", n)), Ok("3/10".to_string()))
+    }
+
+    #[test]
+    fn calculation_big_integer() {
+        let code = "123456789012345678901234567890 * 987654321098765432109876543210 % 1000000007".to_string();
+        assert_eq!(calculation_test(code).map(|n| format!("{}", n)),
+                   Ok(format!("{}", 123456789012345678901234567890u128 % 1000000007
+                       * (987654321098765432109876543210u128 % 1000000007) % 1000000007)))
+    }
+
     #[test]
     fn calculation_multi_priority() {
         let code = "1 + 2^3 * 2".to_string();
@@ -1272,17 +1649,228 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn calculation_matrix_complex() -> Result<(), String> {
+        let code = "[[1, i];[0, 1]] ** [[2i];[1 + i]] * i".to_string();
+        let num = Num::from_num_vec(vec![
+            vec![Num::from_two_float(-3.0, -1.0)],
+            vec![Num::from_two_float(-1.0, 1.0)],
+        ])?;
+        assert_eq!(calculation_test(code), Ok(num));
+        Ok(())
+    }
+
+    #[test]
+    fn calculation_matrix_expression_element() -> Result<(), String> {
+        let code = "[[-1, 2x];[(1 + 1) ^ 2, y]]".to_string();
+        let num = Num::from_vec(vec![vec![-1.0, 4.0], vec![4.0, -2.0]])?;
+        assert_eq!(calculation_test(code), Ok(num));
+        Ok(())
+    }
+
+    #[test]
+    fn calculation_matrix_det_inv() -> Result<(), String> {
+        let code = "det([[1, 2];[3, 4]]) * inv([[2, 0];[0, 4]])".to_string();
+        let num = Num::from_vec(vec![vec![-1.0, 0.0], vec![0.0, -0.5]])?;
+        assert_eq!(calculation_test(code), Ok(num));
+        Ok(())
+    }
+
+    #[test]
+    fn calculation_matrix_division() -> Result<(), String> {
+        let code = "[[5, 6];[7, 8]] // [[1, 2];[3, 4]] + [[1, 2];[3, 4]] \\ [[5, 6];[7, 8]]".to_string();
+        let num = Num::from_vec(vec![vec![-4.0, -2.0], vec![2.0, 8.0]])?;
+        assert_eq!(calculation_test(code), Ok(num));
+        Ok(())
+    }
+
+    #[test]
+    fn calculation_matrix_pow() -> Result<(), String> {
+        let code = "[[1, 1];[1, 0]] ^ 5 ** [[1, 1];[1, 0]] ^ (-3)".to_string();
+        let num = Num::from_vec(vec![vec![2.0, 1.0], vec![1.0, 1.0]])?;
+        assert_eq!(calculation_test(code), Ok(num));
+        Ok(())
+    }
+
+    #[test]
+    fn calculation_func_complex_exp() {
+        let code = "exp(i * pi)".to_string();
+        assert_eq!(calculation_test(code), Ok(Num::Float(-1.0)))
+    }
+
+    #[test]
+    fn calculation_func_complex_abs() {
+        let code = "abs(3 + 4i) + abs(z)".to_string();
+        assert_eq!(calculation_test(code), Ok(Num::Float(5.0 + 10f64.sqrt())))
+    }
+
+    #[test]
+    fn calculation_func_complex_sqrt() {
+        let code = "sqrt(-3 - 4i)".to_string();
+        assert_eq!(calculation_test(code), Ok(Num::from_two_float(1.0, -2.0)))
+    }
+
+    #[test]
+    fn calculation_func_complex_sin_cos() {
+        let code = "sin(1 + i) ^ 2 + cos(1 + i) ^ 2".to_string();
+        let value = calculation_test(code).unwrap();
+        assert!(value.supported_sub(&Num::Float(1.0)).unwrap().magnitude() < 1e-12)
+    }
+
+    #[test]
+    fn calculation_func_complex_tan() {
+        let code = "tan(1 + i) - sin(1 + i) / cos(1 + i)".to_string();
+        assert!(calculation_test(code).is_ok_and(|n| n.magnitude() < 1e-12));
+        let code = "tan(1 + 400i)".to_string();
+        assert_eq!(calculation_test(code), Ok(Num::from_two_float(0.0, 1.0)))
+    }
+
+    #[test]
+    fn calculation_func_matrix() -> Result<(), String> {
+        let code = "sqrt([[4, -1];[0, 1/4]])".to_string();
+        let num = Num::from_num_vec(vec![
+            vec![Num::Float(2.0), Num::new_complex()],
+            vec![Num::Float(0.0), Num::Float(0.5)],
+        ])?;
+        assert_eq!(calculation_test(code), Ok(num));
+        Ok(())
+    }
+
+    #[test]
+    fn calculation_func_complex_parts() {
+        let code = "re(z) * 10 + im(z) + conj(z) + arg(-1) * 0".to_string();
+        assert_eq!(calculation_test(code), Ok(Num::from_two_float(-14.0, 3.0)))
+    }
+
+    #[test]
+    fn calculation_func_polar() {
+        let code = "polar(2, pi / 2) + polar(1 + 1, 0)".to_string();
+        assert_eq!(calculation_test(code), Ok(Num::from_two_float(2.0, 2.0)))
+    }
+
+    #[test]
+    fn calculation_error_func_args() {
+        let code = "polar(2)".to_string();
+        assert_eq!(calculation_test(code), Err("error calculation: error: polar takes 2 argument(s)".to_string()));
+        let code = "sqrt(2, 1)".to_string();
+        assert_eq!(calculation_test(code), Err("error calculation: error: sqrt takes 1 argument(s)".to_string()));
+        let code = "1, 2".to_string();
+        assert_eq!(calculation_test(code), Err("error calculation: syntax error".to_string()))
+    }
+
+    #[test]
+    fn calculation_func_math_library() {
+        let code = "log10(1000) + log(2, 8) + ln(1)".to_string();
+        assert_eq!(calculation_test(code), Ok(Num::Float(6.0)));
+        let code = "floor(-7/2) + ceil(-7/2) + round(5/2) + sign(-3)".to_string();
+        assert_eq!(calculation_test(code), Ok(Num::from_i64(-5)));
+        let code = "min(1/3, 0.3) + max(2, 3)".to_string();
+        assert_eq!(calculation_test(code), Num::from_string("3.3"));
+        let code = "factorial(20)".to_string();
+        assert_eq!(calculation_test(code), Ok(Num::from_i64(2432902008176640000)));
+        let code = "gamma(5) - gamma(0.5) ^ 2".to_string();
+        assert!(calculation_test(code).is_ok_and(|n| (n.float_value() - 24.0 + std::f64::consts::PI).abs() < 1e-12));
+        let code = "atan2(1, 1) * 4 - atan(1) * 4 + asin(1) * 2 + acos(-1)".to_string();
+        assert!(calculation_test(code).is_ok_and(|n| (n.float_value() - 2.0 * std::f64::consts::PI).abs() < 1e-12));
+        let code = "cosh(x) ^ 2 - sinh(x) ^ 2 + tanh(0)".to_string();
+        assert!(calculation_test(code).is_ok_and(|n| (n.float_value() - 1.0).abs() < 1e-12));
+        let code = "ln(-1)".to_string();
+        assert_eq!(calculation_test(code), Ok(Num::from_two_float(0.0, std::f64::consts::PI)))
+    }
+
+    #[test]
+    fn calculation_func_large_arguments() {
+        let inf = Err("error calculation: The calculation resulted in 'inf'.".to_string());
+        let code = "factorial(round(exp(40)))".to_string();
+        assert_eq!(calculation_test(code), inf);
+        let code = "gamma(round(exp(30)))".to_string();
+        assert_eq!(calculation_test(code), inf);
+        let code = "gamma(1000.5)".to_string();
+        assert_eq!(calculation_test(code), inf);
+        let code = "factorial(170.0)".to_string();
+        assert!(calculation_test(code).is_ok_and(|n| n.float_value().is_finite()));
+        let code = "tanh(1 + i) - sinh(1 + i) / cosh(1 + i)".to_string();
+        assert!(calculation_test(code).is_ok_and(|n| n.magnitude() < 1e-12));
+        let code = "tanh(-400 + i)".to_string();
+        assert_eq!(calculation_test(code), Ok(Num::Float(-1.0)))
+    }
+
+    #[test]
+    fn calculation_registered_builtin() -> Result<(), String> {
+        use crate::functions::NativeFunc;
+        let mut data_base = DataBase::new();
+        let hypot = NativeFunc::new("Hypot", 2, "length of the hypotenuse",
+            |a| Ok(Num::Float(a[0].float_value().hypot(a[1].float_value()))));
+        data_base.register_builtin(Box::new(hypot))?;
+        let pi = NativeFunc::new("pi", 1, "", |a| Ok(a[0].clone()));
+        assert_eq!(data_base.register_builtin(Box::new(pi)), Err("pi: error the name is already used".to_string()));
+
+        let code = "hypot(3, 4) + 1".to_string();
+        let mut parser = Parser::new(Lexer::new(&code).make_token_vec()?);
+        let mut tree = parser.make_tree(&data_base)?;
+        assert_eq!(parser.calculation(&mut tree, &data_base, None), Ok(Some(Num::Float(6.0))));
+
+        let code = "hypot(a, 4)".to_string();
+        let mut parser = Parser::new(Lexer::new(&code).make_token_vec()?);
+        let mut tree = parser.make_tree(&data_base)?;
+        assert_eq!(parser.calculation(&mut tree, &data_base, None), Ok(None));
+        assert_eq!(Parser::print_tree(&tree), Ok("hypot ( a , 4 )".to_string()));
+
+        let code = "hypot(1)".to_string();
+        let mut parser = Parser::new(Lexer::new(&code).make_token_vec()?);
+        let mut tree = parser.make_tree(&data_base)?;
+        assert_eq!(parser.calculation(&mut tree, &data_base, None),
+                   Err("error: Hypot takes 2 argument(s)".to_string()));
+        Ok(())
+    }
+
+    #[test]
+    fn calculation_error_func_domain() {
+        let code = "ln(0)".to_string();
+        assert_eq!(calculation_test(code), Err("error calculation: The calculation resulted in '-inf'.".to_string()));
+        let code = "asin(2)".to_string();
+        assert_eq!(calculation_test(code), Err("error calculation: The calculation resulted in 'NaN'.".to_string()));
+        let code = "log(1, 2)".to_string();
+        assert_eq!(calculation_test(code), Err("error calculation: The calculation resulted in 'inf'.".to_string()));
+        let code = "gamma(-2)".to_string();
+        assert_eq!(calculation_test(code), Err("error calculation: The calculation resulted in 'NaN'.".to_string()));
+        let code = "factorial(1.5)".to_string();
+        assert_eq!(calculation_test(code), Err("error calculation: error: factorial requires a non-negative integer".to_string()));
+        let code = "min(i, 1)".to_string();
+        assert_eq!(calculation_test(code), Err("error calculation: error: min requires real arguments".to_string()))
+    }
+
+    #[test]
+    fn calculation_complex_pow_principal() {
+        let code = "i ^ i".to_string();
+        assert_eq!(calculation_test(code), Ok(Num::Float((-std::f64::consts::FRAC_PI_2).exp())));
+        let code = "(-4) ^ 0.5".to_string();
+        assert_eq!(calculation_test(code), Ok(Num::from_two_float(0.0, 2.0)))
+    }
+
+    #[test]
+    fn calculation_error_matrix_singular() {
+        let code = "[[1, 2];[3, 4]] // [[1, 2];[2, 4]]".to_string();
+        assert_eq!(calculation_test(code), Err("error calculation: error: the matrix is singular".to_string()))
+    }
+
+    #[test]
+    fn calculation_error_matrix_nested() {
+        let code = "[[[[1]], 2]]".to_string();
+        assert_eq!(calculation_test(code), Err("error parser: error: matrix elements must be numbers".to_string()))
+    }
+
     #[test]
     fn calculation_error_matrix_plus() -> Result<(), String> {
         let code = "2 + [[3, 3, 3];[3, 3, 3]]".to_string();
-        assert_eq!(calculation_test(code), Err(format!("error calculation: Unsupported operator 2 + [[3,3,3];[3,3,3]]")));
+        assert_eq!(calculation_test(code), Err("error calculation: Unsupported operator 2 + [[3,3,3];[3,3,3]]".to_string()));
         Ok(())
     }
 
     #[test]
     fn calculation_error_matrix_size() -> Result<(), String> {
         let code = "[[3, 3];[3, 3]] + [[3, 3, 3];[3, 3, 3]]".to_string();
-        assert_eq!(calculation_test(code), Err(format!("error calculation: Unsupported different sizes operator [[3,3];[3,3]] + [[3,3,3];[3,3,3]]")));
+        assert_eq!(calculation_test(code), Err("error calculation: Unsupported different sizes operator [[3,3];[3,3]] + [[3,3,3];[3,3,3]]".to_string()));
         Ok(())
     }
 
@@ -1569,6 +2157,24 @@ mod tests {
         assert_eq!(calculation_and_print_test(code), format!("( -1 - 3i ) ^ a"))
     }
 
+    #[test]
+    fn calculation_and_print_rational() {
+        let code = "a ^ (1 / 3) - 2 / 4".to_string();
+        assert_eq!(calculation_and_print_test(code), format!("a ^ ( 1/3 ) - 1/2"))
+    }
+
+    #[test]
+    fn calculation_and_print_func_args() {
+        let code = "polar(a, 1 + 1)".to_string();
+        assert_eq!(calculation_and_print_test(code), format!("polar ( a , 2 )"))
+    }
+
+    #[test]
+    fn calculation_and_print_func_unevaluated() {
+        let code = "log10(a) + atan2(a, 1) * ln((a + 1))".to_string();
+        assert_eq!(calculation_and_print_test(code), format!("log10 ( a ) + atan2 ( a , 1 ) * ln ( a + 1 )"))
+    }
+
     #[test]
     fn calculation_and_print_axy() {
         let code = "- 1 + 2 (x + a) ^ 2 * 3 - 2y".to_string();
@@ -1576,21 +2182,196 @@ mod tests {
     }
 
     #[test]
-    fn check_variable_in_tree_normal() {
+    fn get_variables_in_tree_normal() {
         let code = "- 1 + 2 (x + a) ^ 2 * 3 - 2y".to_string();
-        assert_eq!(check_variable_in_tree_test(code), format!("a"))
+        assert_eq!(get_variables_in_tree_test(code), format!("a"))
     }
 
     #[test]
-    fn check_variable_in_tree_complex() {
+    fn get_variables_in_tree_complex() {
         let code = "- 1 + 2 (x + a) ^ 2 * 3i - 2y".to_string();
-        assert_eq!(check_variable_in_tree_test(code), format!("a"))
+        assert_eq!(get_variables_in_tree_test(code), format!("a"))
     }
 
     #[test]
-    fn check_variable_in_tree_small() {
+    fn get_variables_in_tree_small() {
         let code = "a".to_string();
-        assert_eq!(check_variable_in_tree_test(code), format!("a"))
+        assert_eq!(get_variables_in_tree_test(code), format!("a"))
+    }
+
+    #[test]
+    fn get_variables_in_tree_many() {
+        let code = "b * a + a ^ 2 - b".to_string();
+        assert_eq!(get_variables_in_tree_test(code), format!("b, a"))
+    }
+
+    #[test]
+    fn calculation_function_many_variables() -> Result<(), String> {
+        let mut data_base = DataBase::new();
+        let code = "x ^ 2 + x * y".to_string();
+        let mut parser = Parser::new(Lexer::new(&code).make_token_vec()?);
+        let mut tree = parser.make_tree(&data_base)?;
+        let (x, y) = ("x".to_string(), "y".to_string());
+        parser.calculation(&mut tree, &data_base, Some(&vec![(&x, None), (&y, None)]))?;
+        data_base.register_func("f", tree, vec![x, y])?;
+
+        let code = "f(2, -3) + f(1, 1)".to_string();
+        let mut parser = Parser::new(Lexer::new(&code).make_token_vec()?);
+        let mut tree = parser.make_tree(&data_base)?;
+        assert_eq!(parser.calculation(&mut tree, &data_base, None), Ok(Some(Num::Float(0.0))));
+
+        let code = "f(2, a)".to_string();
+        let mut parser = Parser::new(Lexer::new(&code).make_token_vec()?);
+        let mut tree = parser.make_tree(&data_base)?;
+        assert_eq!(parser.calculation(&mut tree, &data_base, None), Ok(None));
+        assert_eq!(Parser::print_tree(&tree), Ok("( 4 + 2 * ( a ) )".to_string()));
+
+        let code = "f(2)".to_string();
+        let mut parser = Parser::new(Lexer::new(&code).make_token_vec()?);
+        let mut tree = parser.make_tree(&data_base)?;
+        assert_eq!(parser.calculation(&mut tree, &data_base, None),
+                   Err("error: f takes 2 argument(s)".to_string()));
+        Ok(())
+    }
+
+    #[test]
+    fn calculation_solve_results() -> Result<(), String> {
+        let mut data_base = DataBase::new();
+        let calculate = |code: &str, data_base: &DataBase| -> Result<Option<Num>, String> {
+            let mut parser = Parser::new(Lexer::new(code).make_token_vec()?);
+            let mut tree = parser.make_tree(data_base)?;
+            parser.calculation(&mut tree, data_base, None)
+        };
+        data_base.set_solve_result("roots", Some(Num::from_vec(vec![vec![-2.0, 2.0]])?));
+        assert_eq!(calculate("roots[1]", &data_base), Ok(Some(Num::Float(2.0))));
+
+        data_base.register_num("roots", Num::from_i64(5))?;
+        data_base.set_solve_result("roots", Some(Num::from_vec(vec![vec![1.0]])?));
+        assert_eq!(calculate("roots", &data_base), Ok(Some(Num::from_i64(5))));
+        data_base.set_solve_result("roots", None);
+        assert_eq!(calculate("roots", &data_base), Ok(Some(Num::from_i64(5))));
+        Ok(())
+    }
+
+    #[test]
+    fn calculation_shadow_builtin() -> Result<(), String> {
+        let mut data_base = DataBase::new();
+        let calculate = |code: &str, data_base: &DataBase| -> Result<Option<Num>, String> {
+            let mut parser = Parser::new(Lexer::new(code).make_token_vec()?);
+            let mut tree = parser.make_tree(data_base)?;
+            parser.calculation(&mut tree, data_base, None)
+        };
+        assert_eq!(calculate("max(5, 1)", &data_base), Ok(Some(Num::from_i64(5))));
+        data_base.register_num("im", Num::from_i64(3))?;
+        assert_eq!(calculate("im * 2", &data_base), Ok(Some(Num::from_i64(6))));
+
+        let mut parser = Parser::new(Lexer::new("a - b").make_token_vec()?);
+        let mut tree = parser.make_tree(&data_base)?;
+        let (a, b) = ("a".to_string(), "b".to_string());
+        parser.calculation(&mut tree, &data_base, Some(&vec![(&a, None), (&b, None)]))?;
+        data_base.register_func("max", tree, vec![a, b])?;
+        assert_eq!(calculate("max(5, 1)", &data_base), Ok(Some(Num::from_i64(4))));
+        assert_eq!(data_base.register_num("pi", Num::from_i64(3)), Err("The variable cannot be registered".to_string()));
+        Ok(())
+    }
+
+    #[test]
+    fn is_diff_normal() -> Result<(), String> {
+        let vec = Lexer::new("diff(f)").make_token_vec()?;
+        assert!(Parser::is_diff(&vec));
+        assert_eq!(Parser::get_diff_args(&vec), Ok(("f".to_string(), None)));
+        let vec = Lexer::new("DIFF(f, y)").make_token_vec()?;
+        assert_eq!(Parser::get_diff_args(&vec), Ok(("f".to_string(), Some("y".to_string()))));
+        let vec = Lexer::new("diff(f(x))").make_token_vec()?;
+        assert!(!Parser::is_diff(&vec));
+        Ok(())
+    }
+
+    #[test]
+    fn is_call_form_normal() -> Result<(), String> {
+        let vec = Lexer::new("expand((x + 1) ^ 3)").make_token_vec()?;
+        assert!(Parser::is_expand(&vec));
+        assert_eq!(Parser::get_call_argument(&vec).len(), 7);
+        let vec = Lexer::new("FACTOR(f)").make_token_vec()?;
+        assert!(Parser::is_factor(&vec));
+        let vec = Lexer::new("factor(x) * (x + 1)").make_token_vec()?;
+        assert!(!Parser::is_factor(&vec));
+        Ok(())
+    }
+
+    #[test]
+    fn calculation_vector_index() {
+        assert_eq!(calculation_test("v[0] + 2v[1 + 1]".to_string()), Ok(Num::Float(7.0)));
+        assert_eq!(calculation_test("v[3]".to_string()), Err("error parser: error: index 3 is out of range for v".to_string()));
+        assert_eq!(calculation_test("x[0]".to_string()), Err("error parser: error: x is not a vector".to_string()));
+    }
+
+    #[test]
+    fn get_solve_args_normal() -> Result<(), String> {
+        let vec = Lexer::new("solve(a*x^2 + b*x + c, x) ?").make_token_vec()?;
+        assert!(Parser::is_solve(&vec));
+        let ((lhs, rhs), variable) = Parser::get_solve_args(&vec)?;
+        assert_eq!((lhs.len(), rhs.len(), variable), (11, 1, Some("x".to_string())));
+        let vec = Lexer::new("solve(x^2 = a, x) = ?").make_token_vec()?;
+        assert_eq!(Parser::get_solve_args(&vec).map(|((l, r), _)| (l.len(), r.len())), Ok((3, 1)));
+        let vec = Lexer::new("r = solve(x^2 - 4 = 0)").make_token_vec()?;
+        assert!(Parser::is_solve_register(&vec) && !Parser::is_solve(&vec));
+        assert_eq!(Parser::get_solve_args(&vec).map(|((l, r), v)| (l.len(), r.len(), v)), Ok((5, 1, None)));
+        let vec = Lexer::new("solve() = ?").make_token_vec()?;
+        assert_eq!(Parser::get_solve_args(&vec), Err("error: solve takes an equation and an unknown".to_string()));
+        Ok(())
+    }
+
+    #[test]
+    fn get_system_equations_normal() -> Result<(), String> {
+        let vec = Lexer::new("{2x + y = 3; [[1, 2]; [3, 4]] = x} ?").make_token_vec()?;
+        assert!(Parser::is_system(&vec));
+        let equations = Parser::get_system_equations(&vec)?;
+        assert_eq!(equations.iter().map(|(l, r)| (l.len(), r.len())).collect::<Vec<_>>(), vec![(4, 1), (13, 1)]);
+        let vec = Lexer::new("{x = 1; } ?").make_token_vec()?;
+        assert_eq!(Parser::get_system_equations(&vec), Err("= never appeared: syntax error".to_string()));
+        Ok(())
+    }
+
+    #[test]
+    fn get_interval_args_normal() -> Result<(), String> {
+        let vec = Lexer::new("interval -2.5 10").make_token_vec()?;
+        assert!(Parser::is_interval_command(&vec));
+        assert_eq!(Parser::get_interval_args(&vec), Ok(Some((-2.5, 10.0))));
+        let vec = Lexer::new("interval 3 1").make_token_vec()?;
+        assert_eq!(Parser::get_interval_args(&vec), Err("error: the interval must be finite with a < b".to_string()));
+        let vec = Lexer::new("interval 1 -").make_token_vec()?;
+        assert_eq!(Parser::get_interval_args(&vec), Err("error: interval takes two numbers".to_string()));
+        Ok(())
+    }
+
+    #[test]
+    fn get_integrate_args_normal() -> Result<(), String> {
+        let vec = Lexer::new("integrate(f)").make_token_vec()?;
+        assert!(Parser::is_integrate(&vec));
+        assert_eq!(Parser::get_integrate_args(&vec), Ok(("f".to_string(), None)));
+        let vec = Lexer::new("integrate(f, -1, max(1, 2))").make_token_vec()?;
+        assert!(Parser::is_integrate(&vec));
+        let (_, bounds) = Parser::get_integrate_args(&vec)?;
+        assert_eq!(bounds.map(|(a, b)| (a.len(), b.len())), Some((2, 6)));
+        let vec = Lexer::new("integrate(f, 1)").make_token_vec()?;
+        assert_eq!(Parser::get_integrate_args(&vec), Err("error: integrate takes a function and two bounds".to_string()));
+        let vec = Lexer::new("NIntegrate(f, 0, 1)").make_token_vec()?;
+        assert!(Parser::is_nintegrate(&vec) && !Parser::is_integrate(&vec));
+        Ok(())
+    }
+
+    #[test]
+    fn is_func_register_many_variables() -> Result<(), String> {
+        let vec = Lexer::new("f(x, y, z)").make_token_vec()?;
+        assert!(Parser::is_func_register(&vec));
+        assert_eq!(Parser::get_func_register_variables(&vec),
+                   Ok(vec!["x".to_string(), "y".to_string(), "z".to_string()]));
+        let vec = Lexer::new("f(x, x)").make_token_vec()?;
+        assert_eq!(Parser::get_func_register_variables(&vec), Err("x: error duplicate variable".to_string()));
+        let vec = Lexer::new("f(x, )").make_token_vec()?;
+        assert!(!Parser::is_func_register(&vec));
+        Ok(())
     }
 
     #[test]
@@ -0,0 +1,280 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+use crate::bigint::{BigInt, ldexp};
+
+
+/// Exact fraction kept in lowest terms with a positive denominator.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Rational {
+    num: BigInt,
+    den: BigInt,
+}
+
+
+impl Rational {
+    pub fn new(num: BigInt, den: BigInt) -> Option<Rational> {
+        if den.is_zero() {
+            return None
+        }
+        let gcd = num.gcd(&den);
+        let (mut num, _) = num.divrem(&gcd).unwrap();
+        let (mut den, _) = den.divrem(&gcd).unwrap();
+        if den.is_negative() {
+            num = -&num;
+            den = -&den;
+        }
+        Some(Rational { num, den })
+    }
+
+    pub fn from_bigint(num: BigInt) -> Rational {
+        Rational { num, den: BigInt::one() }
+    }
+
+    pub fn from_i64(num: i64) -> Rational {
+        Self::from_bigint(BigInt::from_i64(num))
+    }
+
+    /// Parse a literal such as "12" or "0.125".
+    pub fn from_decimal_str(string: &str) -> Option<Rational> {
+        let (int_part, frac_part) = match string.split_once('.') {
+            Some((i, f)) => (i, f),
+            None => (string, ""),
+        };
+        let int_part = if int_part.is_empty() {"0"} else {int_part};
+        let num = BigInt::from_decimal_str(&(int_part.to_string() + frac_part))?;
+        let den = BigInt::from_u64(10).pow(frac_part.len() as u64);
+        Self::new(num, den)
+    }
+
+    /// Exact value of a finite float.
+    pub fn from_f64(value: f64) -> Option<Rational> {
+        if !value.is_finite() {
+            return None
+        }
+        let mut exp = 0usize;
+        let mut value = value;
+        while value.fract() != 0.0 {
+            value *= 2.0;
+            exp += 1;
+        }
+        Self::new(BigInt::from_f64_int(value)?, BigInt::one().shl(exp))
+    }
+
+    pub fn numer(&self) -> &BigInt {
+        &self.num
+    }
+
+    pub fn denom(&self) -> &BigInt {
+        &self.den
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.num.is_zero()
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.den.is_one()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.num.is_negative()
+    }
+
+    pub fn bits(&self) -> u64 {
+        self.num.bits().max(self.den.bits())
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        if self.num.is_zero() {
+            return 0.0
+        }
+        let shift = 64 + self.den.bits() as i64 - self.num.bits() as i64;
+        let q = if shift >= 0 {
+            self.num.shl(shift as usize).divrem(&self.den).unwrap().0
+        } else {
+            self.num.divrem(&self.den.shl((-shift) as usize)).unwrap().0
+        };
+        ldexp(q.to_f64(), -shift)
+    }
+
+    pub fn neg(&self) -> Rational {
+        Rational { num: -&self.num, den: self.den.clone() }
+    }
+
+    pub fn abs(&self) -> Rational {
+        Rational { num: self.num.abs(), den: self.den.clone() }
+    }
+
+    pub fn add(&self, rhs: &Rational) -> Rational {
+        let num = &(&self.num * &rhs.den) + &(&rhs.num * &self.den);
+        Self::new(num, &self.den * &rhs.den).unwrap()
+    }
+
+    pub fn sub(&self, rhs: &Rational) -> Rational {
+        self.add(&rhs.neg())
+    }
+
+    pub fn mul(&self, rhs: &Rational) -> Rational {
+        Self::new(&self.num * &rhs.num, &self.den * &rhs.den).unwrap()
+    }
+
+    pub fn div(&self, rhs: &Rational) -> Option<Rational> {
+        Self::new(&self.num * &rhs.den, &self.den * &rhs.num)
+    }
+
+    pub fn recip(&self) -> Option<Rational> {
+        Self::new(self.den.clone(), self.num.clone())
+    }
+
+    pub fn floor(&self) -> BigInt {
+        let (q, r) = self.num.divrem(&self.den).unwrap();
+        if self.is_negative() && !r.is_zero() {
+            &q - &BigInt::one()
+        } else {
+            q
+        }
+    }
+
+    /// Remainder with the sign of `rhs` ignored, like `f64::rem_euclid`.
+    pub fn rem_euclid(&self, rhs: &Rational) -> Option<Rational> {
+        let rhs = rhs.abs();
+        let quotient = Self::from_bigint(self.div(&rhs)?.floor());
+        Some(self.sub(&quotient.mul(&rhs)))
+    }
+
+    /// None when a negative power of zero is requested.
+    pub fn pow(&self, exp: i64) -> Option<Rational> {
+        let value = Rational {
+            num: self.num.pow(exp.unsigned_abs()),
+            den: self.den.pow(exp.unsigned_abs()),
+        };
+        if exp < 0 {
+            value.recip()
+        } else {
+            Some(value)
+        }
+    }
+
+    /// Exact n-th root when both numerator and denominator are perfect powers.
+    pub fn exact_root(&self, n: u64) -> Option<Rational> {
+        if n == 0 {
+            return None
+        }
+        let odd = n % 2 == 1;
+        if self.is_negative() && !odd {
+            return None
+        }
+        let (num, num_exact) = self.num.abs().nth_root(n)?;
+        let (den, den_exact) = self.den.nth_root(n)?;
+        if !num_exact || !den_exact {
+            return None
+        }
+        let num = if self.is_negative() {-&num} else {num};
+        Some(Rational { num, den })
+    }
+}
+
+
+impl Ord for Rational {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (&self.num * &other.den).cmp(&(&other.num * &self.den))
+    }
+}
+
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_integer() {
+            write!(f, "{}", self.num)
+        } else {
+            write!(f, "{}/{}", self.num, self.den)
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn q(num: i64, den: i64) -> Rational {
+        Rational::new(BigInt::from_i64(num), BigInt::from_i64(den)).unwrap()
+    }
+
+    #[test]
+    fn new_reduce() {
+        assert_eq!(q(6, -4), q(-3, 2));
+        assert_eq!(format!("{}", q(6, -4)), "-3/2".to_string());
+        assert_eq!(format!("{}", q(8, 4)), "2".to_string());
+        assert_eq!(Rational::new(BigInt::one(), BigInt::zero()), None);
+    }
+
+    #[test]
+    fn from_decimal_str_normal() {
+        assert_eq!(Rational::from_decimal_str("0.125"), Some(q(1, 8)));
+        assert_eq!(Rational::from_decimal_str("12."), Some(q(12, 1)));
+        assert_eq!(Rational::from_decimal_str("1.2.3"), None);
+    }
+
+    #[test]
+    fn from_f64_exact() {
+        assert_eq!(Rational::from_f64(0.75), Some(q(3, 4)));
+        assert_eq!(Rational::from_f64(-2.0), Some(q(-2, 1)));
+        assert_eq!(Rational::from_f64(f64::NAN), None);
+        assert_ne!(Rational::from_f64(0.1), Some(q(1, 10)));
+    }
+
+    #[test]
+    fn add_third() {
+        let third = q(1, 3);
+        assert_eq!(third.add(&third).add(&third), q(1, 1));
+    }
+
+    #[test]
+    fn div_zero() {
+        assert_eq!(q(1, 3).div(&q(0, 1)), None);
+        assert_eq!(q(1, 3).div(&q(2, 3)), Some(q(1, 2)));
+    }
+
+    #[test]
+    fn floor_normal() {
+        assert_eq!(q(7, 2).floor(), BigInt::from_i64(3));
+        assert_eq!(q(-7, 2).floor(), BigInt::from_i64(-4));
+        assert_eq!(q(-4, 1).floor(), BigInt::from_i64(-4));
+    }
+
+    #[test]
+    fn rem_euclid_normal() {
+        assert_eq!(q(5, 1).rem_euclid(&q(-4, 1)), Some(q(1, 1)));
+        assert_eq!(q(-5, 1).rem_euclid(&q(4, 1)), Some(q(3, 1)));
+        assert_eq!(q(7, 2).rem_euclid(&q(4, 3)), Some(q(5, 6)));
+        assert_eq!(q(1, 1).rem_euclid(&q(0, 1)), None);
+    }
+
+    #[test]
+    fn pow_negative() {
+        assert_eq!(q(-2, 3).pow(-3), Some(q(-27, 8)));
+        assert_eq!(q(0, 1).pow(-1), None);
+    }
+
+    #[test]
+    fn exact_root_normal() {
+        assert_eq!(q(-8, 27).exact_root(3), Some(q(-2, 3)));
+        assert_eq!(q(2, 1).exact_root(2), None);
+        assert_eq!(q(-4, 1).exact_root(2), None);
+    }
+
+    #[test]
+    fn to_f64_normal() {
+        assert_eq!(q(1, 3).to_f64(), 1.0 / 3.0);
+        assert_eq!(q(-5, 2).to_f64(), -2.5);
+    }
+}
@@ -1,28 +1,382 @@
 use crate::equation::{Equation, Term};
+use crate::numeric_roots::{self, Complex64};
+use crate::fraction::Poly;
+use crate::numeric_solve;
+use crate::binary_tree::BinaryTree;
+use crate::data_base::DataBase;
+use crate::parser::Element;
+use crate::num::Num;
+
+
+/// Relative size under which a discriminant is treated as zero.
+const ROOT_TOLERANCE: f64 = 1e-12;
+/// Relative distance under which two roots are treated as one repeated root.
+const MERGE_TOLERANCE: f64 = 1e-7;
+const POLISH_STEPS: usize = 3;
+const MAX_NUMERIC_DEGREE: i64 = 100;
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Root {
+    Real(f64),
+    /// The conjugate pair `r ± zi` with `z > 0`.
+    Complex(f64, f64),
+}
+
+
+/// Distinct roots with their multiplicities.
+type Roots = Vec<(Root, usize)>;
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SolutionKind {
+    /// No value solves the equation.
+    Empty,
+    /// Every value solves the equation, except maybe the roots of a denominator;
+    /// for a system, infinitely many values do.
+    Every,
+    /// The solutions are the roots.
+    Finite,
+}
+
+
+/// Solutions of an equation as numbers, for use in later calculations.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SolutionSet {
+    pub kind: SolutionKind,
+    /// Distinct roots, real or complex, with their multiplicities, in printed order.
+    pub roots: Vec<(Num, usize)>,
+    /// Roots left out because they make a denominator zero.
+    pub excluded: Vec<Num>,
+    /// The precision achieved when the roots are found numerically.
+    pub precision: Option<f64>,
+}
+
+
+impl SolutionSet {
+    pub fn from_kind(kind: SolutionKind) -> SolutionSet {
+        SolutionSet { kind, roots: Vec::new(), excluded: Vec::new(), precision: None }
+    }
+
+    /// Simple roots, such as the values of the unknowns of a system.
+    pub fn from_values(values: Vec<Num>) -> SolutionSet {
+        SolutionSet { roots: values.into_iter().map(|x| (x, 1)).collect(), ..Self::from_kind(SolutionKind::Finite) }
+    }
+
+    fn from_roots(roots: &[(Root, usize)]) -> SolutionSet {
+        if roots.is_empty() {
+            return Self::from_kind(SolutionKind::Empty)
+        }
+        SolutionSet { roots: Self::to_nums(roots), ..Self::from_kind(SolutionKind::Finite) }
+    }
+
+    /// Both roots of each conjugate pair are listed.
+    fn to_nums(roots: &[(Root, usize)]) -> Vec<(Num, usize)> {
+        let mut nums = Vec::new();
+        for (root, multiplicity) in roots {
+            match root {
+                Root::Real(x) => nums.push((Num::Float(*x), *multiplicity)),
+                Root::Complex(r, z) => {
+                    nums.push((Num::from_two_float(*r, *z), *multiplicity));
+                    nums.push((Num::from_two_float(*r, -z), *multiplicity));
+                },
+            }
+        }
+        nums
+    }
+
+    /// The roots with each conjugate pair listed once, as they are printed.
+    fn to_roots(nums: &[(Num, usize)]) -> Roots {
+        nums.iter()
+            .filter_map(|(num, multiplicity)| {
+                let (r, z) = num.to_parts();
+                let (r, z) = (r.float_value(), z.float_value());
+                match z {
+                    _ if z == 0.0 => Some((Root::Real(r), *multiplicity)),
+                    _ if z > 0.0 => Some((Root::Complex(r, z), *multiplicity)),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
+    /// The roots as a row vector, or None when they cannot be listed.
+    pub fn to_vector(&self) -> Option<Num> {
+        if self.kind != SolutionKind::Finite {
+            return None
+        }
+        Num::from_num_vec(vec![self.roots.iter().map(|(root, _)| root.clone()).collect()]).ok()
+    }
+
+    /// The multiplicities of the roots as a row vector, in the same order.
+    pub fn multiplicities(&self) -> Option<Num> {
+        if self.kind != SolutionKind::Finite {
+            return None
+        }
+        Num::from_num_vec(vec![self.roots.iter().map(|(_, m)| Num::from_i64(*m as i64)).collect()]).ok()
+    }
+}
 
 
 impl Equation {
     pub fn solution(&self) -> Result<String, String> {
+        Ok(self.solve(false)?.0)
+    }
+
+    /// The solution printed from the solution set, with the roots of linear and
+    /// quadratic equations as fractions and radicals when `exact` and possible.
+    /// The set is None when the equation cannot be solved.
+    pub fn solve(&self, exact: bool) -> Result<(String, Option<SolutionSet>), String> {
+        if let Some(message) = self.unsupported() {
+            return Ok((message, None))
+        }
+        let set = self.solution_set()?;
+        let exact_string = if exact {self.exact_roots_string()} else {None};
+        let string = exact_string.unwrap_or_else(|| self.solution_string(&set));
+        Ok((string, Some(set)))
+    }
+
+    /// Why the equation cannot be solved, if it cannot.
+    fn unsupported(&self) -> Option<String> {
         if !self.expr.is_empty() && self.expr[0].degree < 0 {
-            return Ok(format!("Negative integer powers are not supported."))
+            Some("Negative integer powers are not supported.".to_string())
+        } else if self.degree > MAX_NUMERIC_DEGREE {
+            Some(format!("The polynomial degree is strictly greater than {}, I can't solve.", MAX_NUMERIC_DEGREE))
+        } else {
+            None
+        }
+    }
+
+    fn solution_string(&self, set: &SolutionSet) -> String {
+        let excluded = Self::roots_list(&SolutionSet::to_roots(&set.excluded.iter().map(|x| (x.clone(), 1)).collect::<Vec<_>>()));
+        let mut string = match set.kind {
+            SolutionKind::Every if excluded.is_empty() => "The solution is an arbitrary real number.".to_string(),
+            SolutionKind::Every => format!("The solution is an arbitrary real number except {}.", excluded),
+            SolutionKind::Empty => "There is no solution.".to_string(),
+            SolutionKind::Finite if self.degree == 1 => format!("Solution:\n{}", set.roots[0].0),
+            SolutionKind::Finite => Self::roots_string(&SolutionSet::to_roots(&set.roots)),
+        };
+        if set.kind != SolutionKind::Every && !excluded.is_empty() {
+            string += &format!("\nExcluded {}, which makes a denominator zero.", excluded);
+        }
+        if let Some(precision) = set.precision {
+            string += &format!("\nPrecision: {:.1e}", precision);
+        }
+        string
+    }
+
+    /// Real roots of `tree = 0` in the solve interval, printed from their set,
+    /// for equations that are not polynomials.
+    pub fn non_polynomial_solution(set: &SolutionSet, data_base: &DataBase) -> String {
+        let (a, b) = data_base.solve_interval();
+        let mut string = match set.roots.len() {
+            0 => format!("No solution found on [{}, {}].", a, b),
+            1 => format!("Only one solution found on [{}, {}]:", a, b),
+            n => format!("{} solutions found on [{}, {}]:", Self::count_word(n), a, b),
+        };
+        for (root, _) in &set.roots {
+            string += &format!("\n{}", root);
+        }
+        string
+    }
+
+    pub fn solution_set(&self) -> Result<SolutionSet, String> {
+        if let Some(message) = self.unsupported() {
+            return Err(message)
         }
         match self.degree {
-            0 => Ok(self.degree_0_solution()),
-            1 => self.degree_1_solution(),
-            2 => self.degree_2_solution(),
-            _ => Ok(format!("The polynomial degree is strictly greater than 2, I can't solve."))
+            0 if self.expr[0].coefficient == 0.0 => Ok(SolutionSet::from_kind(SolutionKind::Every)),
+            0 => Ok(SolutionSet::from_kind(SolutionKind::Empty)),
+            _ => {
+                let (roots, precision) = self.roots()?;
+                Ok(SolutionSet { precision, ..SolutionSet::from_roots(&roots) })
+            },
         }
     }
 
-    fn degree_0_solution(&self) -> String {
-        if self.expr.is_empty() || self.expr[0].coefficient == 0.0 {
-            format!("The solution is an arbitrary real number.")
-        } else {
-            format!("There is no solution.")
+    /// The solution of an equation multiplied through by the common denominator,
+    /// printed from `rational_solution_set`.
+    pub fn rational_solve(&self, denominators: &[Poly]) -> Result<(String, Option<SolutionSet>), String> {
+        if let Some(message) = self.unsupported() {
+            return Ok((message, None))
+        }
+        let set = self.rational_solution_set(denominators)?;
+        Ok((self.solution_string(&set), Some(set)))
+    }
+
+    pub fn rational_solution(&self, denominators: &[Poly]) -> Result<String, String> {
+        Ok(self.rational_solve(denominators)?.0)
+    }
+
+    /// Solutions of an equation multiplied through by the common denominator,
+    /// without the roots that make one of `denominators` zero.
+    pub fn rational_solution_set(&self, denominators: &[Poly]) -> Result<SolutionSet, String> {
+        let mut set = self.solution_set()?;
+        if set.kind == SolutionKind::Every {
+            let mut excluded = Vec::new();
+            for denominator in denominators {
+                let (roots, _) = Self::from_coefficients(denominator, self.variable.clone()).roots()?;
+                excluded.extend(roots.into_iter().filter_map(|(root, _)| match root {
+                    Root::Real(x) => Some(x),
+                    Root::Complex(_, _) => None,
+                }));
+            }
+            excluded.sort_by(f64::total_cmp);
+            excluded.dedup();
+            set.excluded = excluded.into_iter().map(Num::Float).collect();
+        } else if set.kind == SolutionKind::Finite {
+            let roots = SolutionSet::to_roots(&set.roots);
+            let (excluded, roots): (Roots, Roots) = roots.into_iter()
+                .partition(|(root, _)| denominators.iter().any(|d| Self::is_root_of(d, root)));
+            set = SolutionSet {
+                excluded: SolutionSet::to_nums(&excluded).into_iter().map(|(x, _)| x).collect(),
+                precision: set.precision,
+                ..SolutionSet::from_roots(&roots)
+            };
+        }
+        Ok(set)
+    }
+
+    /// The roots found in the solve interval, for equations that are not polynomials.
+    pub fn non_polynomial_solution_set(tree: &BinaryTree<Element>, variable: &String, data_base: &DataBase) -> SolutionSet {
+        let roots: Roots = numeric_solve::find_roots(tree, variable, data_base.solve_interval(), data_base)
+            .into_iter()
+            .map(|x| (Root::Real(x), 1))
+            .collect();
+        SolutionSet::from_roots(&roots)
+    }
+
+    /// The working of `solution`, as an ordered list of steps.
+    pub fn solution_steps(&self) -> Result<Vec<String>, String> {
+        let mut steps = vec![
+            format!("Reduced form: {} = 0", self.to_string()?),
+            format!("Degree: {}", self.degree),
+        ];
+        let vec = Self::make_terms_no_gaps(&self.expr, self.degree.max(0));
+        let c: Vec<f64> = vec.iter().map(|term| term.coefficient).collect();
+        // the values shown are the roots of the answer, not computed again from the formula
+        let (roots, _) = if (1..=2).contains(&self.degree) {self.roots()?} else {(Vec::new(), None)};
+        match self.degree {
+            0 if c[0] == 0.0 => steps.push("0 = 0 holds for every value".to_string()),
+            0 => steps.push(format!("{} = 0 holds for no value", c[0])),
+            1 => {
+                steps.push(format!("Formula: x = -b / a with a = {}, b = {}", c[1], c[0]));
+                if let [(Root::Real(x), _)] = roots.as_slice() {
+                    steps.push(format!("x = -{} / {} = {}", Self::paren(c[0]), Self::paren(c[1]), x + 0.0));
+                }
+            },
+            2 => {
+                let (a, b, c) = (c[2], c[1], c[0]);
+                let discriminant = Self::degree_2_discriminant(&vec)?;
+                steps.push(format!("Coefficients: a = {}, b = {}, c = {}", a, b, c));
+                steps.push(format!("Discriminant: Δ = b^2 - 4ac = {}^2 - 4 * {} * {} = {}",
+                                   Self::paren(b), Self::paren(a), Self::paren(c), discriminant));
+                match roots.as_slice() {
+                    [(Root::Real(x), _)] => {
+                        steps.push("Δ = 0, so there is one double root: x = -b / 2a".to_string());
+                        steps.push(format!("x = -{} / (2 * {}) = {}", Self::paren(b), Self::paren(a), x + 0.0));
+                    },
+                    [(Root::Real(low), _), (Root::Real(high), _)] => {
+                        let sqrt_d = discriminant.sqrt();
+                        let (x1, x2) = if a > 0.0 {(high, low)} else {(low, high)};
+                        steps.push("Δ > 0, so there are two real roots: x = (-b ± √Δ) / 2a".to_string());
+                        steps.push(format!("√Δ = {}", sqrt_d));
+                        steps.push(format!("x1 = (-{} + {}) / (2 * {}) = {}", Self::paren(b), sqrt_d, Self::paren(a), x1 + 0.0));
+                        steps.push(format!("x2 = (-{} - {}) / (2 * {}) = {}", Self::paren(b), sqrt_d, Self::paren(a), x2 + 0.0));
+                    },
+                    [(Root::Complex(r, z), _)] => {
+                        let sqrt_d = (-discriminant).sqrt();
+                        steps.push("Δ < 0, so there are two complex roots: x = (-b ± i√-Δ) / 2a".to_string());
+                        steps.push(format!("√-Δ = {}", sqrt_d));
+                        steps.push(format!("Real part: -b / 2a = -{} / (2 * {}) = {}", Self::paren(b), Self::paren(a), r + 0.0));
+                        steps.push(format!("Imaginary part: √-Δ / 2a = {} / (2 * {}) = ±{}", sqrt_d, Self::paren(a), z));
+                    },
+                    _ => {},
+                }
+            },
+            3 | 4 => {
+                let zeros = c.iter().take_while(|c| **c == 0.0).count();
+                if zeros > 0 {
+                    steps.push(format!("Factor out x^{}: x = 0 is a root of multiplicity {}", zeros, zeros));
+                }
+                let monic: Vec<f64> = c[zeros..].iter().map(|x| x / c[c.len() - 1]).collect();
+                steps.extend(Self::closed_form_steps(&monic));
+            },
+            _ => {
+                steps.push("No formula exists in general: Aberth–Ehrlich iteration from points on a circle around every root".to_string());
+                steps.push("Group the approximations of repeated roots and polish each root with Newton's method".to_string());
+            },
         }
+        Ok(steps)
+    }
+
+    /// Steps of the closed forms for a monic polynomial, given lowest degree first.
+    fn closed_form_steps(monic: &[f64]) -> Vec<String> {
+        let mut steps = Vec::new();
+        match monic.len() - 1 {
+            2 => steps.push(format!("Solve x^2 + {}x + {} = 0 with the quadratic formula", Self::paren(monic[1]), Self::paren(monic[0]))),
+            3 => {
+                let (b, c, d) = (monic[2], monic[1], monic[0]);
+                let p = Self::cancel_to_zero(&[c, -b * b / 3.0]);
+                let q = Self::cancel_to_zero(&[2.0 * b * b * b / 27.0, -b * c / 3.0, d]);
+                let discriminant = (q / 2.0).powi(2) + (p / 3.0).powi(3);
+                steps.push(format!("Cardano: substitute x = t - b/3 = t - {} in x^3 + bx^2 + cx + d", Self::paren(b / 3.0)));
+                steps.push(format!("Depressed cubic t^3 + pt + q = 0 with p = c - b^2/3 = {}, q = 2b^3/27 - bc/3 + d = {}", p, q));
+                steps.push(format!("Discriminant: (q/2)^2 + (p/3)^3 = {}", discriminant));
+                steps.push(if p == 0.0 && q == 0.0 {
+                    format!("p = q = 0, so x = {} is a triple root", -b / 3.0 + 0.0)
+                } else if discriminant > 0.0 {
+                    "Positive: one real root t = u - p/(3u) with u = ∛(-q/2 - sign(q)√((q/2)^2 + (p/3)^3))".to_string()
+                } else {
+                    "Not positive: casus irreducibilis, real roots t = 2√(-p/3) cos(θ - 2πk/3) with cos 3θ = (3q/2p)√(-3/p)".to_string()
+                });
+                steps.push("Divide by (x - root) and solve the remaining quadratic".to_string());
+            },
+            4 => {
+                let (b, c, d, e) = (monic[3], monic[2], monic[1], monic[0]);
+                let p = Self::cancel_to_zero(&[c, -3.0 * b * b / 8.0]);
+                let q = Self::cancel_to_zero(&[d, -b * c / 2.0, b * b * b / 8.0]);
+                let r = Self::cancel_to_zero(&[e, -b * d / 4.0, b * b * c / 16.0, -3.0 * b.powi(4) / 256.0]);
+                steps.push(format!("Ferrari: substitute x = y - b/4 = y - {} in x^4 + bx^3 + cx^2 + dx + e", Self::paren(b / 4.0)));
+                steps.push(format!("Depressed quartic y^4 + py^2 + qy + r = 0 with p = {}, q = {}, r = {}", p, q, r));
+                if q == 0.0 {
+                    steps.push(format!("q = 0: solve z^2 + {}z + {} = 0 with z = y^2", Self::paren(p), Self::paren(r)));
+                } else {
+                    steps.push("Take a positive root m of the resolvent cubic m^3 + pm^2 + (p^2/4 - r)m - q^2/8 = 0".to_string());
+                    steps.push("Split into y^2 ± √(2m)y + p/2 + m ∓ √(2m)q/(4m) = 0 and solve both quadratics".to_string());
+                }
+            },
+            _ => {},
+        }
+        steps
+    }
+
+    pub fn rational_solution_steps(&self, denominators: &[Poly]) -> Result<Vec<String>, String> {
+        let mut denominator_strings = Vec::new();
+        for denominator in denominators {
+            denominator_strings.push(Self::from_coefficients(denominator, self.variable.clone()).to_string()?);
+        }
+        let mut steps = vec![format!("Multiply both sides by the least common denominator of {}", denominator_strings.join(", "))];
+        steps.extend(self.solution_steps()?);
+        steps.push("Exclude the roots that make a denominator zero".to_string());
+        Ok(steps)
+    }
+
+    pub fn non_polynomial_steps(data_base: &DataBase) -> Vec<String> {
+        let (a, b) = data_base.solve_interval();
+        vec![
+            format!("The equation is not a polynomial: sample it at {} points of [{}, {}]", numeric_solve::SAMPLES + 1, a, b),
+            "Refine each sign change with Brent's method, skipping the poles".to_string(),
+            "Try Newton's method where the value comes close to zero without changing sign".to_string(),
+        ]
+    }
+
+    /// A value in a formula, in parentheses when negative.
+    fn paren(value: f64) -> String {
+        if value.is_sign_negative() && value != 0.0 {format!("({})", value)} else {format!("{}", value + 0.0)}
     }
 
-    fn make_terms_no_gaps(terms: &Vec<Term>, degree: i64) -> Vec<Term> {
+    fn make_terms_no_gaps(terms: &[Term], degree: i64) -> Vec<Term> {
         let mut vec = Vec::new();
         let mut index = 0;
         for i in 0..=degree {
@@ -36,35 +390,7 @@ impl Equation {
         vec
     }
 
-    fn degree_1_solution(&self) -> Result<String, String> {
-        // ax + b = 0
-        let vec = Self::make_terms_no_gaps(&self.expr, self.degree);
-        let a = vec[1].coefficient;
-        let b = - vec[0].coefficient;
-        let mut value = b / a;
-        if !value.is_finite() {
-            Err(format!("The calculation resulted in '{}'.", value))
-        } else {
-            if value == 0.0 {
-                value = 0.0;
-            }
-            Ok(format!("Solution:\n{}", value))
-        }
-    }
-
-    fn degree_2_solution(&self) -> Result<String, String> {
-        let vec = Self::make_terms_no_gaps(&self.expr, self.degree);
-        let discriminant = Self::degree_2_discriminant(&vec)?;
-        if discriminant == 0.0 {
-            Self::degree_2_solution_one(&vec)
-        } else if discriminant.is_sign_positive() {
-            Self::degree_2_solution_two(&vec, discriminant)
-        } else {
-            Self::degree_2_solution_complex(&vec, discriminant)
-        }
-    }
-
-    fn degree_2_discriminant(terms: &Vec<Term>) -> Result<f64, String> {
+    fn degree_2_discriminant(terms: &[Term]) -> Result<f64, String> {
         let c = &terms[0].coefficient;
         let b = &terms[1].coefficient;
         let a = &terms[2].coefficient;
@@ -76,52 +402,293 @@ impl Equation {
         }
     }
 
-    fn degree_2_solution_one(terms: &Vec<Term>) -> Result<String, String> {
-        let b = &terms[1].coefficient;
-        let a = &terms[2].coefficient;
-        let value = (-b) / (2.0 * a);
-        if value.is_finite() {
-            Ok(format!("Only one solution on R:\n{}", value))
+    fn from_coefficients(coefficients: &[f64], variable: Option<String>) -> Equation {
+        let mut expr: Vec<Term> = coefficients.iter().enumerate()
+            .filter(|(_, c)| **c != 0.0)
+            .map(|(degree, coefficient)| Term { coefficient: *coefficient, degree: degree as i64 })
+            .collect();
+        if expr.is_empty() {
+            expr.push(Term { coefficient: 0.0, degree: 0 });
+        }
+        let degree = expr.last().unwrap().degree;
+        Equation { expr, degree, variable, exact_coefficients: None }
+    }
+
+    fn is_root_of(coefficients: &[f64], root: &Root) -> bool {
+        let z = match root {
+            Root::Real(x) => Complex64::new(*x, 0.0),
+            Root::Complex(r, z) => Complex64::new(*r, *z),
+        };
+        let zero = Complex64::new(0.0, 0.0);
+        let (value, bound) = coefficients.iter().rev().fold((zero, 0.0), |(value, bound), c| {
+            (value * z + Complex64::new(*c, 0.0), bound * z.abs() + c.abs())
+        });
+        value.abs() <= MERGE_TOLERANCE * bound
+    }
+
+    /// Distinct roots with their multiplicities, and the precision achieved when
+    /// they are found numerically.
+    fn roots(&self) -> Result<(Roots, Option<f64>), String> {
+        let vec = Self::make_terms_no_gaps(&self.expr, self.degree);
+        let coefficients: Vec<f64> = vec.iter().map(|term| term.coefficient).collect();
+        if self.degree <= 4 {
+            if self.degree == 0 {
+                return Ok((vec![], None))
+            }
+            return Ok((Self::group_roots(&Self::closed_form_roots(&coefficients)?), None))
+        }
+        let zeros = coefficients.iter().take_while(|c| **c == 0.0).count();
+        let mut roots = Vec::new();
+        if zeros > 0 {
+            roots.push((Root::Real(0.0), zeros));
+        }
+        let mut precision: f64 = 0.0;
+        if coefficients.len() - zeros > 1 {
+            for (z, multiplicity, error) in numeric_roots::aberth_roots(&coefficients[zeros..])? {
+                precision = precision.max(error);
+                let tolerance = error.max(ROOT_TOLERANCE * z.abs().max(1.0));
+                let re = if z.re.abs() <= tolerance {0.0} else {z.re};
+                if z.im.abs() <= tolerance {
+                    roots.push((Root::Real(re), multiplicity));
+                } else if z.im > 0.0 {
+                    roots.push((Root::Complex(re, z.im), multiplicity));
+                }
+            }
+        }
+        Self::sort_roots(&mut roots);
+        Ok((roots, Some(precision)))
+    }
+
+    /// Roots of a polynomial of degree 4 or less, given lowest degree first,
+    /// with repeated roots listed once per multiplicity.
+    fn closed_form_roots(coefficients: &[f64]) -> Result<Vec<Root>, String> {
+        let zeros = coefficients.iter().take_while(|c| **c == 0.0).count();
+        let mut roots = vec![Root::Real(0.0); zeros];
+        let lead = coefficients[coefficients.len() - 1];
+        let monic: Vec<f64> = coefficients.iter().map(|c| c / lead).collect();
+        if let Some(c) = monic.iter().find(|c| !c.is_finite()) {
+            return Err(format!("The calculation resulted in '{}'.", c))
+        }
+        let reduced = &monic[zeros..];
+        roots.extend(match reduced.len() - 1 {
+            0 => vec![],
+            1 => vec![Root::Real(-reduced[0])],
+            2 => Self::quadratic_roots(reduced[1], reduced[0]),
+            3 => Self::cubic_roots(reduced[2], reduced[1], reduced[0]),
+            _ => Self::quartic_roots(reduced[3], reduced[2], reduced[1], reduced[0]),
+        });
+        let roots: Vec<Root> = roots.into_iter()
+            .map(|root| match root {
+                Root::Real(x) => Root::Real(Self::polish_real_root(&monic, x) + 0.0),
+                Root::Complex(r, z) => Root::Complex(r + 0.0, z),
+            })
+            .collect();
+        for root in &roots {
+            let (r, z) = match root {
+                Root::Real(x) => (*x, 0.0),
+                Root::Complex(r, z) => (*r, *z),
+            };
+            if !r.is_finite() || !z.is_finite() {
+                return Err(format!("The calculation resulted in '{}'.", if r.is_finite() {z} else {r}))
+            }
+        }
+        Ok(roots)
+    }
+
+    /// x^2 + bx + c = 0. The root away from cancellation comes from the formula, and
+    /// the other one from the formula or from c/q, whichever leaves the smaller residual.
+    fn quadratic_roots(b: f64, c: f64) -> Vec<Root> {
+        let discriminant = b * b - 4.0 * c;
+        if discriminant.abs() <= ROOT_TOLERANCE * (b * b).max(4.0 * c.abs()) {
+            vec![Root::Real(-b / 2.0); 2]
+        } else if discriminant > 0.0 {
+            let q = -(b + b.signum() * discriminant.sqrt()) / 2.0;
+            let residual = |x: f64| (x * x + b * x + c).abs();
+            let formula = (-b + b.signum() * discriminant.sqrt()) / 2.0;
+            let other = if residual(formula) <= residual(c / q) {formula} else {c / q};
+            vec![Root::Real(q), Root::Real(other)]
         } else {
-            Err(format!("The calculation resulted in '{}'.", value))
+            vec![Root::Complex(-b / 2.0, (-discriminant).sqrt() / 2.0)]
         }
     }
 
-    fn degree_2_solution_two(terms: &Vec<Term>, discriminant: f64) -> Result<String, String> {
-        let b = &terms[1].coefficient;
-        let a = &terms[2].coefficient;
-        let sqrt_d = discriminant.sqrt();
-        let value1 = (-b + sqrt_d) / (2.0 * a);
-        let value2 = (-b - sqrt_d) / (2.0 * a);
-        if value1.is_finite() && value2.is_finite() {
-            Ok(format!("Two solutions on R:\n{}\n{}", value1, value2))
-        } else if value2.is_finite(){
-            Err(format!("The calculation resulted in '{}'.", value1))
+    /// x^3 + bx^2 + cx + d = 0 by Cardano's formula on t^3 + pt + q = 0 with x = t - b/3.
+    /// One real root is taken from the formula and the others from the deflated quadratic.
+    fn cubic_roots(b: f64, c: f64, d: f64) -> Vec<Root> {
+        let shift = b / 3.0;
+        let p = Self::cancel_to_zero(&[c, -b * b / 3.0]);
+        let q = Self::cancel_to_zero(&[2.0 * b * b * b / 27.0, -b * c / 3.0, d]);
+        if p == 0.0 && q == 0.0 {
+            return vec![Root::Real(-shift); 3]
+        }
+        let half_q = q / 2.0;
+        let third_p = p / 3.0;
+        let discriminant = half_q * half_q + third_p * third_p * third_p;
+        let t = if discriminant > 0.0 {
+            let u = (-half_q - half_q.signum() * discriminant.sqrt()).cbrt();
+            u - third_p / u
         } else {
-            Err(format!("The calculation resulted in '{}'.", value2))
+            // casus irreducibilis: the real roots come from the cosine, without complex cube roots
+            let radius = 2.0 * (-third_p).sqrt();
+            let angle = (3.0 * q / (2.0 * p) * (-1.0 / third_p).sqrt()).clamp(-1.0, 1.0).acos() / 3.0;
+            (0..3).map(|k| radius * (angle - 2.0 * std::f64::consts::PI * k as f64 / 3.0).cos())
+                .fold(0.0, |max: f64, t| if (t - shift).abs() > (max - shift).abs() {t} else {max})
+        };
+        let x = Self::polish_real_root(&[d, c, b, 1.0], t - shift);
+        // x^3 + bx^2 + cx + d = (x - x1)(x^2 + ex + f)
+        let e = b + x;
+        let f = if x != 0.0 {-d / x} else {c + x * e};
+        let mut roots = vec![Root::Real(x)];
+        roots.extend(Self::quadratic_roots(e, f));
+        roots
+    }
+
+    /// x^4 + bx^3 + cx^2 + dx + e = 0 by Ferrari's method on y^4 + py^2 + qy + r = 0 with x = y - b/4.
+    fn quartic_roots(b: f64, c: f64, d: f64, e: f64) -> Vec<Root> {
+        let shift = b / 4.0;
+        let p = Self::cancel_to_zero(&[c, -3.0 * b * b / 8.0]);
+        let q = Self::cancel_to_zero(&[d, -b * c / 2.0, b * b * b / 8.0]);
+        let r = Self::cancel_to_zero(&[e, -b * d / 4.0, b * b * c / 16.0, -3.0 * b.powi(4) / 256.0]);
+        let shifted = |roots: Vec<Root>| roots.into_iter().map(|root| match root {
+            Root::Real(y) => Root::Real(y - shift),
+            Root::Complex(y, z) => Root::Complex(y - shift, z),
+        }).collect();
+        let m = Self::cubic_roots(p, p * p / 4.0 - r, -q * q / 8.0).into_iter()
+            .filter_map(|root| match root {
+                Root::Real(m) => Some(m),
+                Root::Complex(_, _) => None,
+            })
+            .fold(f64::NEG_INFINITY, f64::max);
+        if q == 0.0 || m <= 0.0 {
+            return shifted(Self::biquadratic_roots(p, r))
         }
+        // (y^2 + p/2 + m)^2 = 2m(y - q/(4m))^2
+        let s = (2.0 * m).sqrt();
+        let t = q / (4.0 * m);
+        let mut roots = Self::quadratic_roots(s, p / 2.0 + m - s * t);
+        roots.extend(Self::quadratic_roots(-s, p / 2.0 + m + s * t));
+        shifted(roots)
     }
 
-    fn degree_2_solution_complex(terms: &Vec<Term>, discriminant: f64) -> Result<String, String> {
-        let b = &terms[1].coefficient;
-        let a = &terms[2].coefficient;
-        let sqrt_d = (-discriminant).sqrt();
-        let r_value = (-b) / (2.0 * a);
-        let z_value = (sqrt_d / (2.0 * a)).abs();
-        if r_value.is_finite() && z_value.is_finite() {
-            if r_value == 0.0 && z_value == 1.0{
-                Ok(format!("Two solutions on C:\n± i"))
-            } else if r_value == 0.0 {
-                Ok(format!("Two solutions on C:\n± {}i", z_value))
-            } else if z_value == 1.0 {
-                Ok(format!("Two solutions on C:\n{} ± i", r_value))
-            } else {
-                Ok(format!("Two solutions on C:\n{} ± {}i", r_value, z_value))
+    /// y^4 + py^2 + r = 0
+    fn biquadratic_roots(p: f64, r: f64) -> Vec<Root> {
+        let mut roots = Vec::new();
+        for z in Self::quadratic_roots(p, r) {
+            match z {
+                Root::Real(z) if z >= 0.0 => roots.extend([Root::Real(z.sqrt()), Root::Real(-z.sqrt())]),
+                Root::Real(z) => roots.push(Root::Complex(0.0, (-z).sqrt())),
+                Root::Complex(zr, zi) => {
+                    let modulus = zr.hypot(zi);
+                    let a = ((modulus + zr) / 2.0).sqrt();
+                    let b = ((modulus - zr) / 2.0).sqrt();
+                    roots.extend([Root::Complex(a, b), Root::Complex(-a, b)]);
+                },
             }
-        } else if z_value.is_finite(){
-            Err(format!("The calculation resulted in '{}'.", r_value))
-        } else {
-            Err(format!("The calculation resulted in '{}'.", z_value))
+        }
+        roots
+    }
+
+    /// Sum of the terms, or 0 when it is lost in the rounding of the terms.
+    fn cancel_to_zero(terms: &[f64]) -> f64 {
+        let sum: f64 = terms.iter().sum();
+        let scale = terms.iter().fold(0.0, |max: f64, term| max.max(term.abs()));
+        if sum.abs() <= ROOT_TOLERANCE * scale {0.0} else {sum}
+    }
+
+    /// Newton steps on the polynomial, kept only while they reduce the residual.
+    fn polish_real_root(coefficients: &[f64], x: f64) -> f64 {
+        let evaluate = |x: f64| coefficients.iter().rev().fold((0.0, 0.0), |(value, derivative), c| {
+            (value * x + c, derivative * x + value)
+        });
+        let mut x = x;
+        for _ in 0..POLISH_STEPS {
+            let (value, derivative) = evaluate(x);
+            if value == 0.0 || derivative == 0.0 {
+                break
+            }
+            let next = x - value / derivative;
+            if evaluate(next).0.abs() >= value.abs() || next.is_nan() {
+                break
+            }
+            x = next;
+        }
+        x
+    }
+
+    fn group_roots(roots: &[Root]) -> Roots {
+        let close = |a: f64, b: f64| (a - b).abs() <= MERGE_TOLERANCE * a.abs().max(b.abs()).max(1.0);
+        let mut groups: Roots = Vec::new();
+        for root in roots {
+            let found = groups.iter_mut().find(|(group, _)| match (group, root) {
+                (Root::Real(a), Root::Real(b)) => close(*a, *b),
+                (Root::Complex(a, b), Root::Complex(c, d)) => close(*a, *c) && close(*b, *d),
+                _ => false,
+            });
+            match found {
+                Some((_, multiplicity)) => *multiplicity += 1,
+                None => groups.push((*root, 1)),
+            }
+        }
+        Self::sort_roots(&mut groups);
+        groups
+    }
+
+    fn sort_roots(roots: &mut [(Root, usize)]) {
+        roots.sort_by(|(a, _), (b, _)| match (a, b) {
+            (Root::Real(a), Root::Real(b)) => a.total_cmp(b),
+            (Root::Real(_), Root::Complex(_, _)) => std::cmp::Ordering::Less,
+            (Root::Complex(_, _), Root::Real(_)) => std::cmp::Ordering::Greater,
+            (Root::Complex(a, c), Root::Complex(b, d)) => a.total_cmp(b).then(c.total_cmp(d)),
+        });
+    }
+
+    /// `a, b ± ci`, for the roots without their multiplicities.
+    fn roots_list(roots: &[(Root, usize)]) -> String {
+        roots.iter().map(|(root, _)| Self::root_string(root)).collect::<Vec<String>>().join(", ")
+    }
+
+    fn roots_string(roots: &[(Root, usize)]) -> String {
+        let count: usize = roots.iter()
+            .map(|(root, _)| if let Root::Complex(_, _) = root {2} else {1})
+            .sum();
+        let on_complex = roots.iter().any(|(root, _)| matches!(root, Root::Complex(_, _)));
+        let mut string = match (count, on_complex) {
+            (1, _) => "Only one solution on R:".to_string(),
+            (n, false) => format!("{} solutions on R:", Self::count_word(n)),
+            (n, true) => format!("{} solutions on C:", Self::count_word(n)),
+        };
+        for (root, multiplicity) in roots {
+            string += "\n";
+            string += &Self::root_string(root);
+            if *multiplicity > 1 {
+                string += &format!(" (multiplicity {})", multiplicity);
+            }
+        }
+        string
+    }
+
+    fn root_string(root: &Root) -> String {
+        match root {
+            Root::Real(x) => format!("{}", x),
+            Root::Complex(r, z) => Self::complex_pair_string(*r, *z),
+        }
+    }
+
+    fn count_word(n: usize) -> String {
+        match n {
+            2 => "Two".to_string(),
+            3 => "Three".to_string(),
+            4 => "Four".to_string(),
+            n => format!("{}", n),
+        }
+    }
+
+    fn complex_pair_string(r_value: f64, z_value: f64) -> String {
+        match (r_value == 0.0, z_value == 1.0) {
+            (true, true) => "± i".to_string(),
+            (true, false) => format!("± {}i", z_value),
+            (false, true) => format!("{} ± i", r_value),
+            (false, false) => format!("{} ± {}i", r_value, z_value),
         }
     }
 }
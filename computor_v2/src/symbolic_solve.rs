@@ -0,0 +1,323 @@
+use crate::expression::Expr;
+use crate::equation::{Equation, Term};
+use crate::num::Num;
+use crate::parser::Parser;
+use crate::rational::Rational;
+
+
+const MAX_POWER: i64 = 64;
+const MAX_SYMBOLIC_DEGREE: usize = 2;
+
+
+/// Conditions on the parameters, and the solution under them.
+type Case = (Vec<String>, Vec<String>);
+
+
+fn is_zero(expr: &Expr) -> bool {
+    matches!(expr, Expr::Num(n) if n.is_zero())
+}
+
+
+fn trim(mut coefficients: Vec<Expr>) -> Vec<Expr> {
+    while coefficients.len() > 1 && is_zero(&coefficients[coefficients.len() - 1]) {
+        coefficients.pop();
+    }
+    coefficients
+}
+
+
+fn add(a: &[Expr], b: &[Expr], negate: bool) -> Vec<Expr> {
+    let zero = Expr::from_i64(0);
+    (0..a.len().max(b.len())).map(|i| {
+        let (x, y) = (a.get(i).unwrap_or(&zero).clone(), b.get(i).unwrap_or(&zero).clone());
+        if negate {Expr::sub(x, y)} else {Expr::add(x, y)}.simplify()
+    }).collect()
+}
+
+
+fn mul(a: &[Expr], b: &[Expr]) -> Vec<Expr> {
+    let mut product = vec![Expr::from_i64(0); a.len() + b.len() - 1];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            product[i + j] = Expr::add(product[i + j].clone(), Expr::mul(x.clone(), y.clone())).simplify();
+        }
+    }
+    product
+}
+
+
+/// Coefficients in `variable`, lowest degree first, which may contain the other variables.
+pub fn coefficients(expr: &Expr, variable: &str) -> Option<Vec<Expr>> {
+    if !expr.contains_variable(variable) {
+        return Some(vec![expr.simplify()])
+    }
+    let coefficients = match expr {
+        Expr::Variable(_) => vec![Expr::from_i64(0), Expr::from_i64(1)],
+        Expr::Neg(a) => coefficients(a, variable)?.into_iter().map(|c| Expr::neg(c).simplify()).collect(),
+        Expr::Add(a, b) => add(&coefficients(a, variable)?, &coefficients(b, variable)?, false),
+        Expr::Sub(a, b) => add(&coefficients(a, variable)?, &coefficients(b, variable)?, true),
+        Expr::Mul(a, b) => mul(&trim(coefficients(a, variable)?), &trim(coefficients(b, variable)?)),
+        Expr::Div(a, b) if !b.contains_variable(variable)
+            => coefficients(a, variable)?.into_iter().map(|c| Expr::div(c, *b.clone()).simplify()).collect(),
+        Expr::Pow(a, b) => {
+            let exponent = match &**b {
+                Expr::Num(n) => n.to_int_value().filter(|n| (0..=MAX_POWER).contains(n))?,
+                _ => return None,
+            };
+            let base = trim(coefficients(a, variable)?);
+            (0..exponent).fold(vec![Expr::from_i64(1)], |power, _| mul(&power, &base))
+        },
+        _ => return None,
+    };
+    Some(trim(coefficients))
+}
+
+
+/// A number factor and the rest of a product, `3 * a` giving `(3, a)`. The
+/// factor of a sum is the greatest common divisor of its integer coefficients.
+fn split_coefficient(expr: &Expr) -> (Num, Option<Expr>) {
+    match expr {
+        Expr::Num(n) => (n.clone(), None),
+        Expr::Add(_, _) | Expr::Sub(_, _) => match content(expr) {
+            Some(g) if g > 1 => (Num::from_i64(g), Some(divide_terms(expr, &Num::from_i64(g)))),
+            _ => (Num::from_i64(1), Some(expr.clone())),
+        },
+        Expr::Mul(a, b) => match &**a {
+            Expr::Num(n) => (n.clone(), Some(*b.clone())),
+            _ => (Num::from_i64(1), Some(expr.clone())),
+        },
+        Expr::Neg(a) => {
+            let (n, rest) = split_coefficient(a);
+            (n.reverse_sign(), rest)
+        },
+        _ => (Num::from_i64(1), Some(expr.clone())),
+    }
+}
+
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {a.abs()} else {gcd(b, a % b)}
+}
+
+
+/// The greatest common divisor of the coefficients of the terms of a sum.
+fn content(expr: &Expr) -> Option<i64> {
+    match expr {
+        Expr::Add(a, b) | Expr::Sub(a, b) => Some(gcd(content(a)?, content(b)?)),
+        Expr::Neg(a) => content(a),
+        _ => {
+            let (n, _) = split_coefficient(expr);
+            n.to_int_value().filter(|_| n.is_exact()).map(i64::abs)
+        },
+    }
+}
+
+
+fn divide_terms(expr: &Expr, divisor: &Num) -> Expr {
+    match expr {
+        Expr::Add(a, b) => Expr::add(divide_terms(a, divisor), divide_terms(b, divisor)),
+        Expr::Sub(a, b) => Expr::sub(divide_terms(a, divisor), divide_terms(b, divisor)),
+        Expr::Neg(a) => Expr::neg(divide_terms(a, divisor)),
+        _ => {
+            let (n, rest) = split_coefficient(expr);
+            match n.supported_div(divisor) {
+                Ok(n) => join_coefficient(n, rest),
+                Err(_) => Expr::div(expr.clone(), Expr::Num(divisor.clone())),
+            }
+        },
+    }
+}
+
+
+fn join_coefficient(n: Num, rest: Option<Expr>) -> Expr {
+    match rest {
+        Some(rest) if n.is_one() => rest,
+        Some(rest) => Expr::mul(Expr::Num(n), rest),
+        None => Expr::Num(n),
+    }
+}
+
+
+/// `a / b` with the number factors of both sides reduced, as in `2 * sqrt ( a ) / 2`.
+fn divide(a: Expr, b: Expr) -> Expr {
+    let ((p, a_rest), (q, b_rest)) = (split_coefficient(&a), split_coefficient(&b));
+    match p.supported_div(&q) {
+        Ok(Num::Rational(r)) if !q.is_zero() => {
+            let numerator = Num::from_rational(Rational::from_bigint(r.numer().clone()));
+            let denominator = Num::from_rational(Rational::from_bigint(r.denom().clone()));
+            simplify_quotient(join_coefficient(numerator, a_rest), join_coefficient(denominator, b_rest))
+        },
+        _ => simplify_quotient(a, b),
+    }
+}
+
+
+/// A sum is kept in the order of the formula.
+fn simplify_quotient(a: Expr, b: Expr) -> Expr {
+    match a {
+        Expr::Add(_, _) | Expr::Sub(_, _) if matches!(&b, Expr::Num(n) if n.is_one()) => a,
+        Expr::Add(_, _) | Expr::Sub(_, _) => Expr::div(a, b),
+        _ => Expr::div(a, b).simplify(),
+    }
+}
+
+
+/// `sqrt(expr)` with the square factors of a positive integer coefficient taken
+/// out, so that `sqrt(8 * a)` is `2 * sqrt(2 * a)` and `sqrt(9)` is `3`.
+fn square_root(expr: &Expr) -> Expr {
+    let (k, rest) = split_coefficient(expr);
+    let n = match k.to_int_value() {
+        Some(n) if k.is_exact() && n > 0 => n,
+        _ => return Expr::func("sqrt", expr.clone()),
+    };
+    let outside = (1..=n).take_while(|d| d * d <= n).filter(|d| n % (d * d) == 0).last().unwrap_or(1);
+    let inside = join_coefficient(Num::from_i64(n / (outside * outside)), rest);
+    match inside {
+        Expr::Num(m) if m.is_one() => Expr::from_i64(outside),
+        inside => Expr::mul(Expr::from_i64(outside), Expr::func("sqrt", inside)).simplify(),
+    }
+}
+
+
+fn print(expr: &Expr) -> Result<String, String> {
+    Parser::print_tree(&expr.to_tree())
+}
+
+
+fn with(conditions: &[String], condition: String) -> Vec<String> {
+    let mut conditions = conditions.to_vec();
+    conditions.push(condition);
+    conditions
+}
+
+
+/// Every case of the equation `coefficients = 0`: the general solution when the
+/// leading coefficient is not zero, then the lower degree equation when it is.
+fn push_cases(coefficients: &[Expr], variable: &str, conditions: &[String], cases: &mut Vec<Case>) -> Result<(), String> {
+    let lead = &coefficients[coefficients.len() - 1];
+    let symbolic = !matches!(lead, Expr::Num(_));
+    let x = |root: Expr| -> Result<String, String> {Ok(format!("{} = {}", variable, print(&root)?))};
+    match coefficients {
+        [c] if symbolic => {
+            cases.push((with(conditions, format!("{} = 0", print(c)?)), vec![format!("{} is any number", variable)]));
+            cases.push((with(conditions, format!("{} ≠ 0", print(c)?)), vec![format!("no solution")]));
+        },
+        [c] if is_zero(c) => cases.push((conditions.to_vec(), vec![format!("{} is any number", variable)])),
+        [_] => cases.push((conditions.to_vec(), vec![format!("no solution")])),
+        [b, a] => {
+            let root = Expr::div(Expr::neg(b.clone()), a.clone()).simplify();
+            let general = if symbolic {with(conditions, format!("{} ≠ 0", print(a)?))} else {conditions.to_vec()};
+            cases.push((general, vec![x(root)?]));
+        },
+        [c, b, a] => {
+            let discriminant = Expr::sub(
+                Expr::pow(b.clone(), Expr::from_i64(2)),
+                Expr::mul(Expr::mul(Expr::from_i64(4), a.clone()), c.clone()),
+            ).simplify();
+            // assembled from simplified parts, so that both roots keep the order of the formula
+            let root = |plus: bool| {
+                let sqrt = square_root(&discriminant);
+                let denominator = Expr::mul(Expr::from_i64(2), a.clone()).simplify();
+                let numerator = match (is_zero(b), plus) {
+                    (true, true) => sqrt,
+                    (true, false) => Expr::neg(sqrt).simplify(),
+                    (false, true) => Expr::add(Expr::neg(b.clone()).simplify(), sqrt),
+                    (false, false) => Expr::sub(Expr::neg(b.clone()).simplify(), sqrt),
+                };
+                // a radical that is a number is folded, as in `-1 + sqrt(1)`
+                let numerator = if matches!(discriminant, Expr::Num(_)) {numerator.simplify()} else {numerator};
+                divide(numerator, denominator)
+            };
+            let general = if symbolic {with(conditions, format!("{} ≠ 0", print(a)?))} else {conditions.to_vec()};
+            cases.push((general, vec![x(root(true))?, x(root(false))?]));
+        },
+        _ => return Err(format!("error: solve handles parameters up to degree {} in {}", MAX_SYMBOLIC_DEGREE, variable)),
+    }
+    if symbolic && coefficients.len() > 1 {
+        let lower = trim(coefficients[..coefficients.len() - 1].to_vec());
+        push_cases(&lower, variable, &with(conditions, format!("{} = 0", print(lead)?)), cases)?;
+    }
+    Ok(())
+}
+
+
+/// Solution of `expr = 0` for `variable`. The other variables are parameters,
+/// and the values of the parameters for which the general formula does not
+/// hold are listed as separate cases.
+pub fn solve(expr: &Expr, variable: &str, exact: bool) -> Result<String, String> {
+    let coefficients = coefficients(expr, variable)
+        .ok_or(format!("error: the equation is not a polynomial in {}", variable))?;
+    let numbers: Option<Vec<f64>> = coefficients.iter().map(|c| match c {
+        Expr::Num(n) if n.is_real() => Some(n.float_value()),
+        _ => None,
+    }).collect();
+    if let Some(numbers) = numbers {
+        let expr: Vec<Term> = numbers.iter().enumerate()
+            .filter(|(_, c)| **c != 0.0)
+            .map(|(degree, c)| Term { coefficient: *c, degree: degree as i64 })
+            .collect();
+        let expr = if expr.is_empty() {vec![Term { coefficient: 0.0, degree: 0 }]} else {expr};
+        let degree = expr[expr.len() - 1].degree;
+        let exact_coefficients = coefficients.iter().map(|c| match c {
+            Expr::Num(n @ Num::Rational(_)) => Some(n.clone()),
+            _ => None,
+        }).collect();
+        let equation = Equation { expr, degree, variable: Some(variable.to_string()), exact_coefficients };
+        return if exact {equation.exact_solution()} else {equation.solution()}
+    }
+    let mut cases = Vec::new();
+    push_cases(&coefficients, variable, &[], &mut cases)?;
+    if let [(conditions, solution)] = cases.as_slice() {
+        if conditions.is_empty() {
+            return Ok(solution.join("\n"))
+        }
+    }
+    let lines: Vec<String> = cases.iter()
+        .map(|(conditions, solution)| format!("If {}:\n  {}", conditions.join(" and "), solution.join("\n  ")))
+        .collect();
+    Ok(lines.join("\n"))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_base::DataBase;
+    use crate::lexer::Lexer;
+
+    fn solve_test(code: &str) -> Result<String, String> {
+        let data_base = DataBase::new();
+        let mut parser = Parser::new(Lexer::new(code).make_token_vec()?);
+        let tree = parser.make_tree(&data_base)?;
+        solve(&Expr::from_tree(&tree)?, "x", false)
+    }
+
+    #[test]
+    fn solve_quadratic_parameters() {
+        assert_eq!(solve_test("a * x^2 + b * x + c"), Ok("\
+If a ≠ 0:
+  x = ( - b + sqrt ( b ^ 2 - 4 * a * c ) ) / ( 2 * a )
+  x = ( - b - sqrt ( b ^ 2 - 4 * a * c ) ) / ( 2 * a )
+If a = 0 and b ≠ 0:
+  x = - c / b
+If a = 0 and b = 0 and c = 0:
+  x is any number
+If a = 0 and b = 0 and c ≠ 0:
+  no solution".to_string()));
+    }
+
+    #[test]
+    fn solve_normal() {
+        assert_eq!(solve_test("x^2 - 4"), Ok("Two solutions on R:\n-2\n2".to_string()));
+        assert_eq!(solve_test("2 * x - k"), Ok("x = k / 2".to_string()));
+        assert_eq!(solve_test("x^2 + 3 * x + q"),
+                   Ok("x = ( -3 + sqrt ( 9 - 4 * q ) ) / 2\nx = ( -3 - sqrt ( 9 - 4 * q ) ) / 2".to_string()));
+        assert_eq!(solve_test("(a - 1) * x + 2"), Ok("If a - 1 ≠ 0:\n  x = - 2 / ( a - 1 )\nIf a - 1 = 0:\n  no solution".to_string()));
+        assert_eq!(solve_test("x^2 - a"), Ok("x = sqrt ( a )\nx = - sqrt ( a )".to_string()));
+        assert_eq!(solve_test("x^2 - 8 * a"), Ok("x = 2 * sqrt ( 2 * a )\nx = - 2 * sqrt ( 2 * a )".to_string()));
+        assert_eq!(solve_test("x^2 + 2 * x + c"), Ok("x = -1 + sqrt ( 1 - c )\nx = -1 - sqrt ( 1 - c )".to_string()));
+        assert_eq!(solve_test("a * x^2 + x"),
+                   Ok("If a ≠ 0:\n  x = 0\n  x = - 1 / a\nIf a = 0:\n  x = 0".to_string()));
+        assert_eq!(solve_test("x * sin(x) - a"), Err("error: the equation is not a polynomial in x".to_string()));
+    }
+}
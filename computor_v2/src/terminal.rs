@@ -20,6 +20,7 @@ impl Drop for CleanUp {
     }
 }
 
+#[allow(clippy::box_collection)]
 pub enum TerminalEvent {
     String(Box<String>),
     Continue,
@@ -45,6 +46,7 @@ impl Reader {
 }
 
 
+#[allow(clippy::box_collection)]
 struct RowContents {
     content: Box<String>,
     index: usize,
@@ -98,6 +100,7 @@ impl RowContents {
         true
     }
 
+    #[allow(clippy::box_collection)]
     fn move_content(&mut self) -> Box<String> {
         self.index = 0;
         std::mem::replace(&mut self.content, Box::new(String::new()))
@@ -196,10 +199,10 @@ impl CursorController {
     }
 
     fn update_from_value(&mut self, value: usize, window_size: &(usize, usize)) {
-        self.cursor_x = self.cursor_x + value;
+        self.cursor_x += value;
         self.cursor_y = (self.cursor_y + ((self.cursor_x - 1) / window_size.0))
             .min(window_size.1 - 1);
-        self.cursor_x = self.cursor_x % window_size.0;
+        self.cursor_x %= window_size.0;
     }
 
     fn update_from_minus_value(&mut self, value: usize, window_size: &(usize, usize)) {
@@ -313,6 +316,7 @@ impl Output {
         Ok(())
     }
 
+    #[allow(clippy::box_collection)]
     fn enter(&mut self) -> crossterm::Result<Box<String>> {
         self.cursor_controller.update_from_value(
             self.row_contents.get_content().len() - self.row_contents.get_index(),
@@ -342,10 +346,7 @@ impl Output {
         self.row_contents.remove();
         queue!(self.editor_contents, cursor::Hide)?;
         let move_count = self.cursor_controller.move_cursor(KeyCode::Left, &self.win_size);
-        match move_count {
-            -1 => queue!(self.editor_contents, terminal::ScrollUp(1))?,
-            _ => {},
-        }
+        if move_count == -1 { queue!(self.editor_contents, terminal::ScrollUp(1))? }
         queue!(
             self.editor_contents,
             self.cursor_controller.get_position(),